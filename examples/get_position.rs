@@ -6,7 +6,6 @@
 // distributed except according to those terms.
 
 use std::convert::TryInto;
-use std::thread;
 use std::time::Duration;
 
 use raestro::maestro::builder::Builder;
@@ -25,15 +24,17 @@ fn main() -> ! {
     let channel = Channel::Channel0;
     let pos_min = MIN_QTR_PWM;
     let pos_max = MAX_QTR_PWM;
-    let sleep_duration = Duration::from_secs(1);
+    let settle_timeout = Duration::from_secs(5);
     loop {
         maestro.set_target(channel, pos_min).unwrap();
-        let position = maestro.get_position(channel).unwrap();
+        let position = maestro
+            .wait_for_channel(channel, pos_min, 0u16, settle_timeout)
+            .unwrap();
         assert_eq!(position, pos_min);
-        thread::sleep(sleep_duration);
         maestro.set_target(channel, pos_max).unwrap();
-        let position = maestro.get_position(channel).unwrap();
+        let position = maestro
+            .wait_for_channel(channel, pos_max, 0u16, settle_timeout)
+            .unwrap();
         assert_eq!(position, pos_max);
-        thread::sleep(sleep_duration);
     }
 }
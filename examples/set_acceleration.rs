@@ -6,7 +6,6 @@
 // distributed except according to those terms.
 
 use std::convert::TryInto;
-use std::thread;
 use std::time::Duration;
 
 use raestro::maestro::builder::Builder;
@@ -27,13 +26,13 @@ fn main() -> ! {
     let pos_max = MAX_QTR_PWM;
     let accel_min = 1u8;
     let accel_max = 255u8;
-    let sleep_duration = Duration::from_secs(1);
+    let settle_timeout = Duration::from_secs(5);
     loop {
         maestro.set_acceleration(channel, accel_min).unwrap();
         maestro.set_target(channel, pos_min).unwrap();
-        thread::sleep(sleep_duration);
+        maestro.wait_until_settled(settle_timeout).unwrap();
         maestro.set_acceleration(channel, accel_max).unwrap();
         maestro.set_target(channel, pos_max).unwrap();
-        thread::sleep(sleep_duration);
+        maestro.wait_until_settled(settle_timeout).unwrap();
     }
 }
@@ -6,7 +6,6 @@
 // distributed except according to those terms.
 
 use std::convert::TryInto;
-use std::thread;
 use std::time::Duration;
 
 use raestro::maestro::builder::Builder;
@@ -27,13 +26,13 @@ fn main() -> ! {
     let pos_max = MAX_QTR_PWM;
     let speed0 = 10u16;
     let speed1 = 140u16;
-    let sleep_duration = Duration::from_secs(1);
+    let settle_timeout = Duration::from_secs(5);
     loop {
         maestro.set_speed(channel, speed0).unwrap();
         maestro.set_target(channel, pos_min).unwrap();
-        thread::sleep(sleep_duration);
+        maestro.wait_until_settled(settle_timeout).unwrap();
         maestro.set_speed(channel, speed1).unwrap();
         maestro.set_target(channel, pos_max).unwrap();
-        thread::sleep(sleep_duration);
+        maestro.wait_until_settled(settle_timeout).unwrap();
     }
 }
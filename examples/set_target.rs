@@ -6,7 +6,6 @@
 // distributed except according to those terms.
 
 use std::convert::TryInto;
-use std::thread;
 use std::time::Duration;
 
 use raestro::maestro::builder::Builder;
@@ -27,15 +26,15 @@ fn main() -> ! {
     let channel2 = Channel::Channel2;
     let pos_min = MIN_QTR_PWM;
     let pos_max = MAX_QTR_PWM;
-    let sleep_duration = Duration::from_secs(1);
+    let settle_timeout = Duration::from_secs(5);
     loop {
         maestro.set_target(channel0, pos_min).unwrap();
         maestro.set_target(channel1, pos_min).unwrap();
         maestro.set_target(channel2, pos_min).unwrap();
-        thread::sleep(sleep_duration);
+        maestro.wait_until_settled(settle_timeout).unwrap();
         maestro.set_target(channel0, pos_max).unwrap();
         maestro.set_target(channel1, pos_max).unwrap();
         maestro.set_target(channel2, pos_max).unwrap();
-        thread::sleep(sleep_duration);
+        maestro.wait_until_settled(settle_timeout).unwrap();
     }
 }
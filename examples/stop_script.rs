@@ -6,7 +6,6 @@
 // distributed except according to those terms.
 
 use std::convert::TryInto;
-use std::thread;
 use std::time::Duration;
 
 use raestro::maestro::builder::Builder;
@@ -25,13 +24,13 @@ fn main() -> ! {
     let channel = Channel::Channel0;
     let pos_min = MIN_QTR_PWM;
     let pos_max = MAX_QTR_PWM;
-    let sleep_duration = Duration::from_secs(1);
+    let settle_timeout = Duration::from_secs(5);
     loop {
         maestro.set_target(channel, pos_min).unwrap();
         maestro.stop_script().unwrap();
-        thread::sleep(sleep_duration);
+        maestro.wait_until_settled(settle_timeout).unwrap();
         maestro.set_target(channel, pos_max).unwrap();
         maestro.stop_script().unwrap();
-        thread::sleep(sleep_duration);
+        maestro.wait_until_settled(settle_timeout).unwrap();
     }
 }
@@ -0,0 +1,145 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! A virtual Maestro which lives on the other end of a pseudo-terminal.
+//!
+//! On startup, the path of the pseudo-terminal is printed to stdout. Any
+//! program (Rust or otherwise) can then open that path as if it were the
+//! Maestro's serial port and talk to it using the compact or Pololu
+//! protocols.
+//!
+//! ### Usage:
+//! ```text
+//! raestro-virtual [--log] [--crc] [--link <PATH>] [--device-number <N>]
+//!                 [--model <6|12|18|24>]
+//! ```
+//!
+//! * `--log`: print every decoded command to stderr.
+//! * `--crc`: require a CRC byte at the end of every command packet.
+//! * `--link <PATH>`: also create a symlink to the pseudo-terminal at `PATH`.
+//! * `--device-number <N>`: the device number to answer to (defaults to 12).
+//! * `--model <6|12|18|24>`: the number of channels of the emulated model
+//!   (defaults to 6, the Micro Maestro).
+
+use std::env;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+use std::time::Instant;
+
+use raestro::maestro::constants::Model;
+use raestro::sim::Emulator;
+use raestro::transport::Transport;
+use serialport::SerialPort;
+use serialport::TTYPort;
+
+/// ### Purpose:
+/// How long to block waiting for bytes before advancing the emulator anyways.
+const POLL_DURATION: Duration = Duration::from_millis(10u64);
+
+const USAGE: &str = "usage: raestro-virtual [--log] [--crc] [--link <PATH>] \
+                     [--device-number <N>] [--model <6|12|18|24>]";
+
+/// ### Purpose:
+/// Command line options.
+#[derive(Default)]
+struct Options {
+    log: bool,
+    crc: bool,
+    link: Option<PathBuf>,
+    device_number: Option<u8>,
+    model: Model,
+}
+
+impl Options {
+    fn parse() -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = env::args().skip(1usize);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--log" => options.log = true,
+                "--crc" => options.crc = true,
+                "--link" => {
+                    let link = args.next().ok_or("missing value for --link")?;
+                    options.link = Some(link.into());
+                },
+                "--device-number" => {
+                    let device_number = args
+                        .next()
+                        .and_then(|value| value.parse().ok())
+                        .filter(|&value: &u8| value < 0x80u8)
+                        .ok_or("--device-number must be between 0 and 127")?;
+                    options.device_number = Some(device_number);
+                },
+                "--model" => {
+                    options.model = match args.next().as_deref() {
+                        Some("6") => Model::MicroMaestro6,
+                        Some("12") => Model::MiniMaestro12,
+                        Some("18") => Model::MiniMaestro18,
+                        Some("24") => Model::MiniMaestro24,
+                        _ => {
+                            return Err("--model must be 6, 12, 18 or 24".into())
+                        },
+                    };
+                },
+                "--help" | "-h" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                },
+                _ => return Err(format!("unknown argument `{}`", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn main() -> raestro::Result<()> {
+    let options = Options::parse().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(2);
+    });
+    let (mut master, slave) = TTYPort::pair()?;
+    master.set_timeout(POLL_DURATION)?;
+    let path = slave.name().expect("pseudo-terminals are always named");
+    if let Some(link) = &options.link {
+        let _ = std::fs::remove_file(link);
+        std::os::unix::fs::symlink(&path, link)?;
+    };
+    println!("{}", path);
+    io::stdout().flush()?;
+
+    let mut emulator = Emulator::with_model(options.model);
+    emulator.set_crc(options.crc);
+    if let Some(device_number) = options.device_number {
+        emulator.set_device_number(device_number);
+    };
+    let mut buf = [0u8; 64usize];
+    let mut last_tick = Instant::now();
+    // `slave` is kept open for the lifetime of the daemon so that reads on
+    // `master` do not fail in between clients.
+    let _slave = slave;
+    loop {
+        let bytes_read = match master.read(&mut buf) {
+            Ok(bytes_read) => bytes_read,
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => 0usize,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => 0usize,
+            Err(err) => return Err(err.into()),
+        };
+        let now = Instant::now();
+        emulator.advance(now - last_tick);
+        last_tick = now;
+        let commands = emulator.receive(&buf[..bytes_read]);
+        if options.log {
+            commands.iter().for_each(|command| eprintln!("{}", command));
+        };
+        let response_length = emulator.read(&mut buf)?;
+        master.write_all(&buf[..response_length])?;
+    }
+}
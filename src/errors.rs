@@ -8,10 +8,12 @@
 //! The crate-wide errors definition.
 
 use std::io;
+use std::time::Duration;
 
 use derive_more::Display;
 use rppal::gpio;
 use rppal::uart;
+use serialport::ErrorKind as SerialErrorKind;
 
 /// The custom `raestro` error type.
 ///
@@ -30,26 +32,238 @@ pub enum Error {
     Uninitialized,
 
     /// ### Purpose:
-    /// An invalid value was passed in as a parameter. Mainly used when an
-    /// incorrect microsecond target was passed into `set_target`.
+    /// An invalid value was passed in as a parameter. Mainly used when a
+    /// target outside of the channel's limits was passed into `set_target`.
     #[display(
-        fmt = "Target must be between 3968 quarter-us (992us) and 8000 quarter-us (2000us) but {} quarter-us was used.",
+        fmt = "Target {} quarter-us is outside of the channel's limits.",
         _0
     )]
     InvalidValue(u16),
 
     /// ### Purpose:
-    /// Occurs when the expected number of bytes
-    /// received from the Maestro board does not
-    /// equal [`crate::maestro::internals::RESPONSE_SIZE`].
+    /// Target limits which are empty, or which
+    /// do not fit in a command packet, were
+    /// configured.
+    #[display(
+        fmt = "Target limits must satisfy 0 < min <= max <= 16383 quarter-us but {}..={} was used.",
+        min,
+        max
+    )]
+    InvalidLimits {
+        /// ### Purpose:
+        /// The configured minimum target.
+        min: u16,
+
+        /// ### Purpose:
+        /// The configured maximum target.
+        max: u16,
+    },
+
+    /// ### Purpose:
+    /// A channel which does not exist on the
+    /// Maestro's model was used.
+    #[display(fmt = "Channel {} does not exist on this Maestro.", _0)]
+    InvalidChannel(u8),
+
+    /// ### Purpose:
+    /// The same channel was given more than
+    /// once to a command which moves several
+    /// channels together.
+    #[display(fmt = "Channel {} was given more than once.", _0)]
+    DuplicateChannel(u8),
+
+    /// ### Purpose:
+    /// More channels were given to a command
+    /// which moves several channels together
+    /// than the Maestro's model has.
+    #[display(
+        fmt = "{} channels were given but this Maestro only has {}.",
+        count,
+        channel_count
+    )]
+    TooManyChannels {
+        /// ### Purpose:
+        /// The number of channels given.
+        count: usize,
+
+        /// ### Purpose:
+        /// The number of channels on the Maestro's model.
+        channel_count: u8,
+    },
+
+    /// ### Purpose:
+    /// A command was sent to a channel which is
+    /// not configured in the mode the command
+    /// requires (i.e., `set_target` on an input).
+    #[display(
+        fmt = "Channel {} is not configured in the mode this command requires.",
+        _0
+    )]
+    InvalidChannelMode(u8),
+
+    /// ### Purpose:
+    /// A command was used which the Maestro's
+    /// model does not support (i.e., `set_pwm`
+    /// on the Micro Maestro).
+    #[display(fmt = "This command is not supported by the Maestro's model.")]
+    UnsupportedCommand,
+
+    /// ### Purpose:
+    /// A PWM signal which cannot be sent to the
+    /// Maestro was used.
+    #[display(
+        fmt = "PWM periods must be at most 16383 and on-times at most the period, but an on-time of {} and a period of {} were used.",
+        on_time,
+        period
+    )]
+    InvalidPwm {
+        /// ### Purpose:
+        /// The requested on-time (in 1/48us).
+        on_time: u16,
+
+        /// ### Purpose:
+        /// The requested period (in 1/48us).
+        period: u16,
+    },
+
+    /// ### Purpose:
+    /// A pulse width, speed or acceleration
+    /// which cannot be expressed in the Maestro's
+    /// raw units was used.
+    #[display(fmt = "{} is outside of the range the Maestro can express.", _0)]
+    UnitOutOfRange(f64),
+
+    /// ### Purpose:
+    /// A servo calibration whose endpoints,
+    /// neutral, range or lookup table are
+    /// inconsistent was used.
+    #[display(
+        fmt = "Servo calibrations need min < max, a neutral between them, a positive range and a strictly increasing lookup table."
+    )]
+    InvalidCalibration,
+
+    /// ### Purpose:
+    /// A servo period which the Maestro's model
+    /// cannot be configured with was used.
+    #[display(
+        fmt = "Servo periods must be non-zero (and 20ms on the Micro Maestro) but {:?} was used.",
+        _0
+    )]
+    InvalidPeriod(Duration),
+
+    /// ### Purpose:
+    /// A trajectory with non-positive limits,
+    /// or with too few or out-of-order
+    /// waypoints, was planned.
+    #[display(
+        fmt = "Trajectories need positive, finite speed and acceleration limits, and at least two waypoints in strictly increasing time."
+    )]
+    InvalidTrajectory,
+
+    /// ### Purpose:
+    /// The sequence section of a Control
+    /// Center settings file could not be read.
+    #[display(fmt = "Could not read the sequences: {}.", _0)]
+    InvalidSequence(String),
+
+    /// ### Purpose:
+    /// Maestro script source could not be
+    /// compiled.
+    #[display(
+        fmt = "Script error at line {}, column {}: {}.",
+        line,
+        column,
+        message
+    )]
+    ScriptSyntax {
+        /// The (1-based) line the error was found on.
+        line: usize,
+
+        /// The (1-based) column the error was found at.
+        column: usize,
+
+        /// What was wrong.
+        message: String,
+    },
+
+    /// ### Purpose:
+    /// Maestro script bytecode could not be
+    /// disassembled.
+    #[display(
+        fmt = "Could not disassemble the instruction at address {:#06x}.",
+        _0
+    )]
+    InvalidBytecode(u16),
+
+    /// ### Purpose:
+    /// A script does not define a subroutine
+    /// with the given name.
+    #[display(fmt = "The script has no subroutine named {}.", _0)]
+    UnknownSubroutine(String),
+
+    /// ### Purpose:
+    /// A subroutine number which does not fit in a
+    /// single data byte was used.
+    #[display(
+        fmt = "Subroutine numbers must be between 0 and 127 but {} was used.",
+        _0
+    )]
+    InvalidSubroutine(u8),
+
+    /// ### Purpose:
+    /// A script parameter which does not fit in 14
+    /// bits was used.
+    #[display(
+        fmt = "Script parameters must be between 0 and 16383 but {} was used.",
+        _0
+    )]
+    InvalidParameter(u16),
+
+    /// ### Purpose:
+    /// A device number which does not fit in a
+    /// Pololu protocol data byte was used.
     #[display(
-        fmt = "2 bytes were expected to be read but only {} byte(s) were actually read.",
+        fmt = "Device numbers must be between 0 and 127 but {} was used.",
+        _0
+    )]
+    InvalidDeviceNumber(u8),
+
+    /// ### Purpose:
+    /// A servo number or position which cannot be
+    /// expressed in the Mini SSC protocol was used.
+    #[display(
+        fmt = "Mini SSC servo numbers and positions must be between 0 and 254 but {} was used.",
+        _0
+    )]
+    MiniSscOutOfRange(u16),
+
+    /// ### Purpose:
+    /// The Maestro discarded the last command
+    /// packet because its CRC byte did not match
+    /// (only reported when CRC mode is enabled).
+    #[display(
+        fmt = "The Maestro reported a CRC error; the last command was discarded."
+    )]
+    CrcMismatch,
+
+    /// ### Purpose:
+    /// Occurs when the number of bytes received
+    /// from the Maestro board does not equal the
+    /// size of the response to the command that
+    /// was sent.
+    #[display(
+        fmt = "{} bytes were expected to be read but only {} byte(s) were actually read.",
+        expected_count,
         actual_count
     )]
     FaultyRead {
         /// ### Purpose:
         /// The number of bytes actually read.
         actual_count: usize,
+
+        /// ### Purpose:
+        /// The number of bytes expected to be read.
+        expected_count: usize,
     },
 
     /// ### Purpose:
@@ -71,8 +285,17 @@ pub enum Error {
         expected_count: usize,
     },
 
+    /// ### Purpose:
+    /// A blocking wait (i.e., `wait_until_settled`)
+    /// gave up after the given duration.
+    #[display(fmt = "Timed out after {:?}.", _0)]
+    Timeout(Duration),
+
     /// ### Purpose:
     /// Any [`std::io::Error`] encountered.
+    ///
+    /// ### Notes:
+    /// All `rppal` and `serialport` errors are converted into this variant.
     #[display(fmt = "{}", _0)]
     Io(io::Error),
 }
@@ -124,3 +347,22 @@ impl From<uart::Error> for Error {
         }
     }
 }
+
+impl From<serialport::Error> for Error {
+    fn from(serial_error: serialport::Error) -> Self {
+        let serialport::Error { kind, description } = serial_error;
+        let err_kind = match kind {
+            SerialErrorKind::NoDevice => io::ErrorKind::NotFound,
+            SerialErrorKind::InvalidInput => io::ErrorKind::InvalidInput,
+            SerialErrorKind::Unknown => io::ErrorKind::Other,
+            SerialErrorKind::Io(err_kind) => err_kind,
+        };
+        Self::new_io_error(err_kind, description)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -11,6 +11,9 @@
 
 pub mod errors;
 pub mod maestro;
+pub mod script;
+pub mod sim;
+pub mod transport;
 
 /// ### Purpose:
 /// The global [`Result`] type to be used throughout the application.
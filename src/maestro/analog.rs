@@ -0,0 +1,67 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Readings from the Maestro's analog inputs.
+//!
+//! A channel configured as an input reports its voltage through
+//! `get_position`, as a 10-bit value from `0` (`0V`) to `1023` (`5V`). Only
+//! channels `0` to `11` can be analog inputs; on the Mini Maestro 18 and 24,
+//! channels `12` and up are digital inputs only.
+//!
+//! ### Examples:
+//! ```rust
+//! # use raestro::maestro::analog::AnalogReading;
+//! let reading = AnalogReading::from_raw(1023u16);
+//! assert_eq!(reading.volts, 5f64);
+//! ```
+
+/// ### Purpose:
+/// The largest value an analog input can report.
+pub const MAX_ANALOG_VALUE: u16 = 1023u16;
+
+/// ### Purpose:
+/// The voltage (in volts) reported as [`MAX_ANALOG_VALUE`].
+pub const REFERENCE_VOLTAGE: f64 = 5f64;
+
+/// ### Purpose:
+/// The number of channels (starting from channel `0`) which can be analog
+/// inputs.
+pub(crate) const ANALOG_CHANNEL_COUNT: u8 = 12u8;
+
+/// ### Purpose:
+/// A single reading of an analog input.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AnalogReading {
+    /// ### Purpose:
+    /// The raw value reported by the Maestro (`0` to `1023`). For averaged
+    /// readings, this is the mean rounded to the nearest integer.
+    pub raw: u16,
+
+    /// ### Purpose:
+    /// The voltage on the channel, in volts.
+    pub volts: f64,
+}
+
+impl AnalogReading {
+    /// ### Purpose:
+    /// Converts a raw value reported by the Maestro into a reading.
+    pub fn from_raw(raw: u16) -> Self {
+        Self::from_mean(f64::from(raw))
+    }
+
+    /// ### Purpose:
+    /// Converts the mean of several raw values into a reading.
+    ///
+    /// ### Notes:
+    /// The voltage is computed from the exact mean, so averaging does not
+    /// lose any resolution to rounding.
+    pub(crate) fn from_mean(mean: f64) -> Self {
+        let raw = mean.round() as u16;
+        let volts = mean * REFERENCE_VOLTAGE / f64::from(MAX_ANALOG_VALUE);
+        Self { raw, volts }
+    }
+}
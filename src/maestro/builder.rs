@@ -9,27 +9,78 @@
 //! [`crate::maestro::Maestro`].
 //!
 //! ### Examples:
-//! ```rust
+//! ```rust,no_run
+//! # use raestro::maestro::builder::Builder;
+//! # use raestro::maestro::constants::Baudrate;
+//! # use raestro::maestro::Maestro;
+//! # fn main() -> raestro::Result<()> {
 //! let builder = Builder::default()
 //!     .baudrate(Baudrate::Baudrate50)
 //!     .block_duration(std::time::Duration::from_secs(10));
 //!
 //! // build into a `maestro` instance by doing the following:
 //! let maestro: Maestro = builder.try_into()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To talk to the Maestro over its USB virtual COM port instead, set a device
+//! path and build into a `Maestro<Serial>`:
+//! ```rust,no_run
+//! # use raestro::maestro::builder::Builder;
+//! # use raestro::maestro::constants::Baudrate;
+//! # use raestro::maestro::Maestro;
+//! # use raestro::transport::Serial;
+//! # fn main() -> raestro::Result<()> {
+//! let maestro: Maestro<Serial> = Builder::default()
+//!     .device_path("/dev/ttyACM0")
+//!     .baudrate(Baudrate::Baudrate11520)
+//!     .block_duration(std::time::Duration::from_millis(100))
+//!     .try_into()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Any other [`crate::transport::Transport`] can be used instead of the
+//! Raspberry Pi's UART by calling [`Builder::build_with`]:
+//! ```rust
+//! # use raestro::maestro::builder::Builder;
+//! # use raestro::maestro::Maestro;
+//! # use raestro::transport::Memory;
+//! # fn main() -> raestro::Result<()> {
+//! let maestro: Maestro<Memory> = Builder::default().build_with(Memory::new())?;
+//! # Ok(())
+//! # }
 //! ```
 //!
 //! The internals of the [`Builder`] struct are also public, meaning that they
 //! can easily be modified manually.
 
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use rppal::uart::Parity;
 use rppal::uart::Uart;
 
 use crate::errors::Error;
+use crate::maestro::calibration::ServoCalibration;
 use crate::maestro::constants::Baudrate;
+use crate::maestro::constants::Channel;
+use crate::maestro::constants::ChannelMode;
+use crate::maestro::constants::Model;
+use crate::maestro::constants::Protocol;
+use crate::maestro::constants::UpdateRate;
+use crate::maestro::constants::DEFAULT_SERVO_PERIOD;
+use crate::maestro::constants::MAX_CHANNEL_COUNT;
+use crate::maestro::constants::MAX_QTR_PWM;
+use crate::maestro::constants::MAX_TARGET;
+use crate::maestro::constants::MIN_QTR_PWM;
 use crate::maestro::internals;
+use crate::maestro::mini_ssc::MiniSscSettings;
 use crate::maestro::Maestro;
+use crate::transport::Serial;
+use crate::transport::Transport;
 
 #[derive(Default)]
 /// ### Purpose:
@@ -43,6 +94,61 @@ pub struct Builder {
     /// ### Purpose:
     /// How long to wait for a response before quitting and returning.
     pub block_duration: Option<Duration>,
+
+    /// ### Purpose:
+    /// The path of the serial device to open (only used when building a
+    /// `Maestro<Serial>`).
+    pub device_path: Option<PathBuf>,
+
+    /// ### Purpose:
+    /// The serial protocol in which commands are sent (defaults to
+    /// [`Protocol::Pololu`]).
+    pub protocol: Option<Protocol>,
+
+    /// ### Purpose:
+    /// The device number of the board (defaults to `12`, the factory
+    /// setting).
+    pub device_number: Option<u8>,
+
+    /// ### Purpose:
+    /// The Mini SSC settings saved on the board.
+    pub mini_ssc: Option<MiniSscSettings>,
+
+    /// ### Purpose:
+    /// Whether a CRC byte is appended to every command packet. This must
+    /// match the board's "Enable CRC" setting.
+    pub crc: Option<bool>,
+
+    /// ### Purpose:
+    /// The model of the board (defaults to [`Model::MicroMaestro6`]).
+    pub model: Option<Model>,
+
+    /// ### Purpose:
+    /// The mode of each channel, indexed by channel (all channels default to
+    /// [`ChannelMode::Servo`]). This must match the board's channel settings.
+    pub channel_modes: Option<Vec<ChannelMode>>,
+
+    /// ### Purpose:
+    /// The range of targets (in quarter-us) each channel accepts, indexed by
+    /// channel (all channels default to [`MIN_QTR_PWM`]`..=`[`MAX_QTR_PWM`]).
+    /// These should match the board's configured minimum and maximum.
+    pub target_limits: Option<Vec<RangeInclusive<u16>>>,
+
+    /// ### Purpose:
+    /// The calibration of each channel's servo, indexed by channel (all
+    /// channels default to [`ServoCalibration::default`]).
+    pub calibrations: Option<Vec<ServoCalibration>>,
+
+    /// ### Purpose:
+    /// The servo period (defaults to [`DEFAULT_SERVO_PERIOD`]). This must
+    /// match the board's "Period" setting.
+    pub servo_period: Option<Duration>,
+
+    /// ### Purpose:
+    /// How often each channel's speed and acceleration limits are applied,
+    /// indexed by channel (all channels default to
+    /// [`UpdateRate::HalfPeriod`]).
+    pub update_rates: Option<Vec<UpdateRate>>,
 }
 
 impl Builder {
@@ -62,35 +168,259 @@ impl Builder {
             ..self
         }
     }
+
+    /// ### Purpose:
+    /// Convenience function to configure the serial device path for this
+    /// builder.
+    pub fn device_path<P>(self, device_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        let device_path = Some(device_path.into());
+        Self {
+            device_path,
+            ..self
+        }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the serial protocol for this
+    /// builder.
+    pub fn protocol(self, protocol: Protocol) -> Self {
+        let protocol = Some(protocol);
+        Self { protocol, ..self }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the device number for this builder.
+    pub fn device_number(self, device_number: u8) -> Self {
+        let device_number = Some(device_number);
+        Self {
+            device_number,
+            ..self
+        }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the Mini SSC settings for this
+    /// builder.
+    pub fn mini_ssc(self, mini_ssc: MiniSscSettings) -> Self {
+        let mini_ssc = Some(mini_ssc);
+        Self { mini_ssc, ..self }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to enable or disable CRC mode for this builder.
+    ///
+    /// ### Notes:
+    /// In CRC mode, every command which has no response is followed by a read
+    /// of the Maestro's error register, and a [`Error::CrcMismatch`] is
+    /// returned if the Maestro discarded the command.
+    pub fn crc(self, crc: bool) -> Self {
+        let crc = Some(crc);
+        Self { crc, ..self }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the board model for this builder.
+    pub fn model(self, model: Model) -> Self {
+        let model = Some(model);
+        Self { model, ..self }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the mode of a single channel for
+    /// this builder.
+    pub fn channel_mode(self, channel: Channel, mode: ChannelMode) -> Self {
+        let mut channel_modes = self.channel_modes.unwrap_or_else(|| {
+            vec![ChannelMode::default(); MAX_CHANNEL_COUNT as usize]
+        });
+        channel_modes[channel as usize] = mode;
+        let channel_modes = Some(channel_modes);
+        Self {
+            channel_modes,
+            ..self
+        }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the range of targets (in
+    /// quarter-us) a single channel accepts for this builder.
+    pub fn target_limits(self, channel: Channel, min: u16, max: u16) -> Self {
+        let mut target_limits =
+            self.target_limits.unwrap_or_else(default_target_limits);
+        target_limits[channel as usize] = min..=max;
+        let target_limits = Some(target_limits);
+        Self {
+            target_limits,
+            ..self
+        }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the calibration of a single
+    /// channel's servo for this builder.
+    pub fn calibration(
+        self,
+        channel: Channel,
+        calibration: ServoCalibration,
+    ) -> Self {
+        let mut calibrations = self.calibrations.unwrap_or_else(|| {
+            vec![ServoCalibration::default(); MAX_CHANNEL_COUNT as usize]
+        });
+        calibrations[channel as usize] = calibration;
+        let calibrations = Some(calibrations);
+        Self {
+            calibrations,
+            ..self
+        }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the servo period for this builder.
+    ///
+    /// ### Notes:
+    /// Only the Mini Maestros support periods other than
+    /// [`DEFAULT_SERVO_PERIOD`]; building a Micro Maestro with any other
+    /// period returns [`Error::InvalidPeriod`].
+    pub fn servo_period(self, servo_period: Duration) -> Self {
+        let servo_period = Some(servo_period);
+        Self {
+            servo_period,
+            ..self
+        }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure how often a single channel's speed
+    /// and acceleration limits are applied for this builder.
+    pub fn update_rate(
+        self,
+        channel: Channel,
+        update_rate: UpdateRate,
+    ) -> Self {
+        let mut update_rates = self.update_rates.unwrap_or_else(|| {
+            vec![UpdateRate::default(); MAX_CHANNEL_COUNT as usize]
+        });
+        update_rates[channel as usize] = update_rate;
+        let update_rates = Some(update_rates);
+        Self {
+            update_rates,
+            ..self
+        }
+    }
+
+    /// ### Purpose:
+    /// Builds a [`Maestro`] which communicates over the given transport.
+    ///
+    /// ### Notes:
+    /// The transport is assumed to already be configured for the Maestro's
+    /// baudrate, so [`Builder::baudrate`] is ignored here. If a block-duration
+    /// has been set, it is applied to the transport as its read timeout.
+    pub fn build_with<T>(self, mut transport: T) -> crate::Result<Maestro<T>>
+    where
+        T: Transport,
+    {
+        let Self {
+            block_duration,
+            protocol,
+            device_number,
+            mini_ssc,
+            crc,
+            model,
+            channel_modes,
+            target_limits,
+            calibrations,
+            servo_period,
+            update_rates,
+            ..
+        } = self;
+        let device_number = device_number.unwrap_or(internals::DEVICE_NUMBER);
+        (device_number < 0x80u8)
+            .then_some(())
+            .ok_or(Error::InvalidDeviceNumber(device_number))?;
+        if let Some(block_duration) = block_duration {
+            transport.set_read_timeout(block_duration)?;
+        };
+        let protocol = protocol.unwrap_or_default();
+        let mut mini_ssc = mini_ssc.unwrap_or_default();
+        mini_ssc.resize();
+        let crc = crc.unwrap_or_default();
+        let model = model.unwrap_or_default();
+        let mut channel_modes = channel_modes.unwrap_or_default();
+        channel_modes
+            .resize(MAX_CHANNEL_COUNT as usize, ChannelMode::default());
+        let mut target_limits = target_limits.unwrap_or_default();
+        target_limits
+            .resize(MAX_CHANNEL_COUNT as usize, MIN_QTR_PWM..=MAX_QTR_PWM);
+        target_limits.iter().try_for_each(|limits| {
+            let (&min, &max) = (limits.start(), limits.end());
+            (0u16 < min && min <= max && max <= MAX_TARGET)
+                .then_some(())
+                .ok_or(Error::InvalidLimits { min, max })
+        })?;
+        let mut calibrations = calibrations.unwrap_or_default();
+        calibrations
+            .resize(MAX_CHANNEL_COUNT as usize, ServoCalibration::default());
+        let servo_period = servo_period.unwrap_or(DEFAULT_SERVO_PERIOD);
+        (!servo_period.is_zero()
+            && (model.supports_servo_period()
+                || servo_period == DEFAULT_SERVO_PERIOD))
+            .then_some(())
+            .ok_or(Error::InvalidPeriod(servo_period))?;
+        let mut update_rates = update_rates.unwrap_or_default();
+        update_rates.resize(MAX_CHANNEL_COUNT as usize, UpdateRate::default());
+        let read_buf = [0u8; internals::BUFFER_SIZE];
+        let write_buf = [0u8; internals::BUFFER_SIZE];
+        let maestro = Maestro {
+            transport,
+            protocol,
+            device_number,
+            model,
+            channel_modes,
+            target_limits,
+            calibrations,
+            servo_period,
+            update_rates,
+            mini_ssc,
+            crc,
+            pending_errors: 0u16,
+            read_buf,
+            write_buf,
+        };
+        Ok(maestro)
+    }
+}
+
+/// ### Purpose:
+/// The target limits of every channel, before any have been configured.
+fn default_target_limits() -> Vec<RangeInclusive<u16>> {
+    vec![MIN_QTR_PWM..=MAX_QTR_PWM; MAX_CHANNEL_COUNT as usize]
 }
 
-impl TryFrom<Builder> for Maestro {
+impl TryFrom<Builder> for Maestro<Uart> {
     type Error = crate::errors::Error;
 
-    fn try_from(
-        Builder {
-            baudrate,
-            block_duration,
-        }: Builder,
-    ) -> Result<Self, Self::Error> {
-        let baudrate = baudrate.ok_or_else(|| Error::Uninitialized)? as u32;
-        let mut uart = Uart::new(
+    fn try_from(builder: Builder) -> Result<Self, Self::Error> {
+        let baudrate = builder.baudrate.ok_or(Error::Uninitialized)? as u32;
+        let uart = Uart::new(
             baudrate,
             Parity::None,
             internals::DATA_BITS,
             internals::STOP_BITS,
         )?;
-        let block_duration = block_duration.unwrap_or_default();
-        uart.set_read_mode(0u8, block_duration)?;
-        let read_buf = [0u8; internals::BUFFER_SIZE];
-        let mut write_buf = [0u8; internals::BUFFER_SIZE];
-        write_buf[0usize] = internals::SYNC as u8;
-        write_buf[1usize] = internals::DEVICE_NUMBER as u8;
-        let maestro = Self {
-            uart,
-            read_buf,
-            write_buf,
-        };
-        Ok(maestro)
+        builder.build_with(uart)
+    }
+}
+
+impl TryFrom<Builder> for Maestro<Serial> {
+    type Error = crate::errors::Error;
+
+    fn try_from(builder: Builder) -> Result<Self, Self::Error> {
+        let baudrate = builder.baudrate.ok_or(Error::Uninitialized)?;
+        let device_path =
+            builder.device_path.as_ref().ok_or(Error::Uninitialized)?;
+        let serial = Serial::open(device_path, baudrate)?;
+        builder.build_with(serial)
     }
 }
@@ -0,0 +1,139 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Support for several Maestros daisy-chained on a single serial line.
+//!
+//! In the Pololu protocol, every command packet carries the device number of
+//! the board it is meant for, and every other board on the line ignores it.
+//! A [`MaestroBus`] owns the shared transport and hands out a [`Maestro`] for
+//! each device number. Each command (along with its response) is sent while
+//! holding a lock on the shared transport, so handles can safely be used from
+//! different threads.
+//!
+//! ### Examples:
+//! ```rust
+//! # use raestro::maestro::builder::Builder;
+//! # use raestro::maestro::bus::MaestroBus;
+//! # use raestro::maestro::constants::Channel;
+//! # use raestro::transport::Memory;
+//! # fn main() -> raestro::Result<()> {
+//! let bus = MaestroBus::new(Memory::new());
+//! let mut left = bus.device(Builder::default().device_number(12u8))?;
+//! let mut right = bus.device(Builder::default().device_number(13u8))?;
+//!
+//! left.set_target(Channel::Channel0, 6000u16)?;
+//! right.set_target(Channel::Channel0, 6000u16)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::time::Duration;
+
+use crate::maestro::builder::Builder;
+use crate::maestro::Maestro;
+use crate::transport::Transport;
+
+/// ### Purpose:
+/// A single serial line shared by several Maestros.
+#[derive(Debug)]
+pub struct MaestroBus<T>
+where
+    T: Transport,
+{
+    transport: Arc<Mutex<T>>,
+}
+
+impl<T> MaestroBus<T>
+where
+    T: Transport,
+{
+    /// ### Purpose:
+    /// Creates a new bus which owns the given transport.
+    pub fn new(transport: T) -> Self {
+        let transport = Arc::new(Mutex::new(transport));
+        Self { transport }
+    }
+
+    /// ### Purpose:
+    /// Builds a [`Maestro`] which talks to the device configured in the given
+    /// builder over this bus.
+    ///
+    /// ### Notes:
+    /// The builder's protocol is ignored; daisy-chained boards can only be
+    /// told apart in the Pololu protocol. If the builder has a block-duration,
+    /// it is applied to the shared transport (and so to every device).
+    pub fn device(
+        &self,
+        builder: Builder,
+    ) -> crate::Result<Maestro<BusHandle<T>>> {
+        let transport = Arc::clone(&self.transport);
+        let handle = BusHandle { transport };
+        let builder = Builder {
+            protocol: None,
+            ..builder
+        };
+        builder.build_with(handle)
+    }
+
+    /// ### Purpose:
+    /// Locks the shared transport, blocking any other device until the
+    /// returned guard is dropped.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        lock(&self.transport)
+    }
+}
+
+/// ### Purpose:
+/// A [`Transport`] which forwards to the transport owned by a
+/// [`MaestroBus`].
+#[derive(Debug)]
+pub struct BusHandle<T>
+where
+    T: Transport,
+{
+    transport: Arc<Mutex<T>>,
+}
+
+fn lock<T>(transport: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panic while holding the lock cannot leave the transport in a state
+    // which is any worse than a dropped byte on the line, so poisoning is
+    // ignored.
+    transport.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<T> Transport for BusHandle<T>
+where
+    T: Transport,
+{
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        lock(&self.transport).read(buf)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize> {
+        lock(&self.transport).write(buf)
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        lock(&self.transport).flush()
+    }
+
+    fn set_read_timeout(&mut self, timeout: Duration) -> crate::Result<()> {
+        lock(&self.transport).set_read_timeout(timeout)
+    }
+
+    fn transact(
+        &mut self,
+        request: &[u8],
+        response: &mut [u8],
+    ) -> crate::Result<usize> {
+        lock(&self.transport).transact(request, response)
+    }
+}
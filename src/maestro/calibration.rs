@@ -0,0 +1,301 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Per-servo calibrations, mapping angles onto pulse widths.
+//!
+//! A [`ServoCalibration`] maps angles (in degrees) onto a normalized position
+//! from `-1.0` to `1.0`, and normalized positions onto pulse widths:
+//! `-1.0` is the servo's minimum pulse width, `0.0` its neutral and `1.0` its
+//! maximum. Each half is mapped linearly, so the neutral does not need to sit
+//! half-way between the endpoints.
+//!
+//! By default, angles are mapped linearly as well (`range` degrees either side
+//! of neutral). Servos which are not linear can be given a lookup table of
+//! `(degrees, normalized)` points instead, which is interpolated linearly.
+//!
+//! ### Examples:
+//! ```rust
+//! # use raestro::maestro::calibration::ServoCalibration;
+//! # use raestro::maestro::units::PulseWidth;
+//! # fn main() -> raestro::Result<()> {
+//! let calibration = ServoCalibration::new(
+//!     PulseWidth::from_micros(1000f64)?,
+//!     PulseWidth::from_micros(2000f64)?,
+//!     PulseWidth::from_micros(1400f64)?,
+//!     90f64,
+//! )?;
+//!
+//! assert_eq!(calibration.to_pulse_width(-45f64)?.micros(), 1200f64);
+//! assert_eq!(calibration.to_pulse_width(45f64)?.micros(), 1700f64);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::errors::Error;
+use crate::maestro::constants::MAX_QTR_PWM;
+use crate::maestro::constants::MIN_QTR_PWM;
+use crate::maestro::units::PulseWidth;
+
+/// ### Purpose:
+/// The pulse width servos are conventionally centred at (`1500us`).
+const NEUTRAL_QTR_PWM: u16 = 6000u16;
+
+/// ### Purpose:
+/// The angle (in degrees) conventionally covered either side of neutral.
+const DEFAULT_RANGE: f64 = 90f64;
+
+/// ### Purpose:
+/// The mapping between a single servo's angles and pulse widths.
+///
+/// ### Notes:
+/// Defaults to `992us..=2000us` (the Maestro's default target limits) with a
+/// neutral of `1500us`, over `90` degrees either side.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ServoCalibration {
+    min: PulseWidth,
+    max: PulseWidth,
+    neutral: PulseWidth,
+    range: f64,
+    reversed: bool,
+    table: Vec<(f64, f64)>,
+}
+
+impl ServoCalibration {
+    /// ### Purpose:
+    /// Creates a linear calibration from the servo's endpoints and neutral,
+    /// and the angle (in degrees) between neutral and either endpoint.
+    ///
+    /// ### Notes:
+    /// The neutral must lie between the endpoints, and the endpoints cannot
+    /// be equal.
+    pub fn new(
+        min: PulseWidth,
+        max: PulseWidth,
+        neutral: PulseWidth,
+        range: f64,
+    ) -> crate::Result<Self> {
+        let valid = min < max
+            && (min..=max).contains(&neutral)
+            && range.is_finite()
+            && range > 0f64;
+        valid
+            .then_some(Self {
+                min,
+                max,
+                neutral,
+                range,
+                reversed: false,
+                table: Vec::new(),
+            })
+            .ok_or(Error::InvalidCalibration)
+    }
+
+    /// ### Purpose:
+    /// Reverses the direction of the servo, so that positive angles move
+    /// towards the minimum pulse width instead.
+    pub fn reversed(self, reversed: bool) -> Self {
+        Self { reversed, ..self }
+    }
+
+    /// ### Purpose:
+    /// Replaces the linear mapping of angles with a lookup table of
+    /// `(degrees, normalized)` points.
+    ///
+    /// ### Notes:
+    /// The table needs at least two points, both columns must be strictly
+    /// increasing, and every normalized position must be within `-1.0` to
+    /// `1.0`. Angles outside of the first and last points are rejected.
+    pub fn with_table(self, table: Vec<(f64, f64)>) -> crate::Result<Self> {
+        let increasing = table.windows(2usize).all(|pair| {
+            pair[0usize].0 < pair[1usize].0 && pair[0usize].1 < pair[1usize].1
+        });
+        let bounded = table.iter().all(|&(degrees, normalized)| {
+            degrees.is_finite() && (-1f64..=1f64).contains(&normalized)
+        });
+        (table.len() >= 2usize && increasing && bounded)
+            .then_some(Self { table, ..self })
+            .ok_or(Error::InvalidCalibration)
+    }
+
+    /// ### Purpose:
+    /// The servo's minimum pulse width.
+    pub fn min(&self) -> PulseWidth {
+        self.min
+    }
+
+    /// ### Purpose:
+    /// The servo's maximum pulse width.
+    pub fn max(&self) -> PulseWidth {
+        self.max
+    }
+
+    /// ### Purpose:
+    /// The servo's neutral pulse width.
+    pub fn neutral(&self) -> PulseWidth {
+        self.neutral
+    }
+
+    /// ### Purpose:
+    /// Whether the servo's direction is reversed.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// ### Purpose:
+    /// The range of angles (in degrees) this calibration accepts.
+    pub fn angles(&self) -> (f64, f64) {
+        match (self.table.first(), self.table.last()) {
+            (Some(&(first, _)), Some(&(last, _))) => (first, last),
+            _ => (-self.range, self.range),
+        }
+    }
+
+    /// ### Purpose:
+    /// How far (in us) the pulse width moves per degree.
+    ///
+    /// ### Notes:
+    /// For calibrations with a lookup table, this is averaged over the whole
+    /// range of the table.
+    pub fn micros_per_degree(&self) -> f64 {
+        let (first, last) = self.angles();
+        let micros = |degrees| {
+            self.degrees_to_normalized(degrees)
+                .map(|normalized| self.normalized_to_micros(normalized))
+                .unwrap_or_default()
+        };
+        (micros(last) - micros(first)).abs() / (last - first)
+    }
+
+    /// ### Purpose:
+    /// Maps an angle (in degrees) onto a pulse width.
+    pub fn to_pulse_width(&self, degrees: f64) -> crate::Result<PulseWidth> {
+        let normalized = self.degrees_to_normalized(degrees)?;
+        self.normalized_to_pulse_width(normalized)
+    }
+
+    /// ### Purpose:
+    /// Maps a pulse width back onto an angle (in degrees).
+    ///
+    /// ### Notes:
+    /// Pulse widths outside of the servo's endpoints are clamped to them.
+    pub fn to_degrees(&self, pulse_width: PulseWidth) -> f64 {
+        let normalized = self.pulse_width_to_normalized(pulse_width);
+        self.normalized_to_degrees(normalized)
+    }
+
+    /// ### Purpose:
+    /// Maps a normalized position (from `-1.0` to `1.0`) onto a pulse width.
+    pub fn normalized_to_pulse_width(
+        &self,
+        normalized: f64,
+    ) -> crate::Result<PulseWidth> {
+        if !(-1f64..=1f64).contains(&normalized) {
+            return Err(Error::UnitOutOfRange(normalized));
+        };
+        PulseWidth::from_micros(self.normalized_to_micros(normalized))
+    }
+
+    /// ### Purpose:
+    /// Maps a pulse width back onto a normalized position.
+    ///
+    /// ### Notes:
+    /// Pulse widths outside of the servo's endpoints are clamped to them.
+    pub fn pulse_width_to_normalized(&self, pulse_width: PulseWidth) -> f64 {
+        let pulse_width = pulse_width.clamp(self.min, self.max);
+        let offset = pulse_width.micros() - self.neutral.micros();
+        let normalized = match pulse_width < self.neutral {
+            true => offset / (self.neutral.micros() - self.min.micros()),
+            false if self.max == self.neutral => 0f64,
+            false => offset / (self.max.micros() - self.neutral.micros()),
+        };
+        if self.reversed {
+            -normalized
+        } else {
+            normalized
+        }
+    }
+
+    /// ### Purpose:
+    /// Maps a normalized position onto a pulse width (in us), unrounded.
+    fn normalized_to_micros(&self, normalized: f64) -> f64 {
+        let normalized = if self.reversed {
+            -normalized
+        } else {
+            normalized
+        };
+        let neutral = self.neutral.micros();
+        match normalized < 0f64 {
+            true => neutral + normalized * (neutral - self.min.micros()),
+            false => neutral + normalized * (self.max.micros() - neutral),
+        }
+    }
+
+    /// ### Purpose:
+    /// Maps an angle (in degrees) onto a normalized position.
+    fn degrees_to_normalized(&self, degrees: f64) -> crate::Result<f64> {
+        let (first, last) = self.angles();
+        if !(first..=last).contains(&degrees) {
+            return Err(Error::UnitOutOfRange(degrees));
+        };
+        let normalized = match self.table.is_empty() {
+            true => degrees / self.range,
+            false => interpolate(self.table.iter().copied(), degrees),
+        };
+        Ok(normalized)
+    }
+
+    /// ### Purpose:
+    /// Maps a normalized position back onto an angle (in degrees).
+    fn normalized_to_degrees(&self, normalized: f64) -> f64 {
+        match self.table.is_empty() {
+            true => normalized * self.range,
+            false => interpolate(
+                self.table
+                    .iter()
+                    .map(|&(degrees, normalized)| (normalized, degrees)),
+                normalized.clamp(
+                    self.table[0usize].1,
+                    self.table[self.table.len() - 1usize].1,
+                ),
+            ),
+        }
+    }
+}
+
+impl Default for ServoCalibration {
+    fn default() -> Self {
+        Self {
+            min: PulseWidth::from(MIN_QTR_PWM),
+            max: PulseWidth::from(MAX_QTR_PWM),
+            neutral: PulseWidth::from(NEUTRAL_QTR_PWM),
+            range: DEFAULT_RANGE,
+            reversed: false,
+            table: Vec::new(),
+        }
+    }
+}
+
+/// ### Purpose:
+/// Linearly interpolates `x` over a series of `(x, y)` points, which must be
+/// strictly increasing in `x` and contain `x`.
+fn interpolate<I>(points: I, x: f64) -> f64
+where
+    I: Iterator<Item = (f64, f64)> + Clone,
+{
+    let mut previous = points.clone().next().unwrap_or_default();
+    for (x1, y1) in points {
+        let (x0, y0) = previous;
+        if x <= x1 {
+            return match x1 > x0 {
+                true => y0 + (x - x0) * (y1 - y0) / (x1 - x0),
+                false => y1,
+            };
+        };
+        previous = (x1, y1);
+    }
+    previous.1
+}
@@ -12,34 +12,63 @@
 mod tests;
 
 use std::cmp::Ordering;
+use std::time::Duration;
 
-/// The minimum PWM (in quarter us) that can be
-/// sent to any channel by the Maestro.
+use crate::errors::Error;
+
+/// The default minimum PWM (in quarter us) that
+/// can be sent to a channel by the Maestro.
 ///
-/// All values below `MIN_QTR_PWM` being used as
-/// parameters to `set_target` will result in an
-/// error.
+/// Unless other limits are configured for a
+/// channel (see `Builder::target_limits`), all
+/// non-zero values below `MIN_QTR_PWM` being
+/// used as parameters to `set_target` will
+/// result in an error.
 pub const MIN_QTR_PWM: u16 = 3968u16;
 
-/// The maximum PWM (in quarter us) that can be
-/// sent to any channel by the Maestro.
+/// The default maximum PWM (in quarter us) that
+/// can be sent to a channel by the Maestro.
 ///
-/// All values above `MAX_QTR_PWM` being used as
+/// Unless other limits are configured for a
+/// channel (see `Builder::target_limits`), all
+/// values above `MAX_QTR_PWM` being used as
 /// parameters to `set_target` will result in an
 /// error.
 pub const MAX_QTR_PWM: u16 = 8000u16;
 
+/// The largest target (in quarter us) which can
+/// be expressed in a command packet.
+pub const MAX_TARGET: u16 = 0x3fffu16;
+
+/// The largest subroutine number which can be
+/// passed into `restart_script_at`.
+pub const MAX_SUBROUTINE: u8 = 127u8;
+
+/// The largest parameter which can be passed
+/// into `restart_script_with_parameter`.
+pub const MAX_SCRIPT_PARAMETER: u16 = 0x3fffu16;
+
+/// The default servo period (and the only one
+/// the Micro Maestro supports).
+pub const DEFAULT_SERVO_PERIOD: Duration = Duration::from_millis(20u64);
+
 /// ### Purpose:
-/// Maximum number of channels on the Maestro.
-const CHANNEL_COUNT: u8 = 6u8;
+/// Maximum number of channels on any Maestro model.
+pub(crate) const MAX_CHANNEL_COUNT: u8 = 24u8;
 
 /// ### Purpose:
 /// All available channels to send commands to.
-#[derive(Copy, Clone, PartialEq)]
+///
+/// ### Notes:
+/// Not every model has every channel; the [`crate::maestro::Maestro`] checks
+/// each channel against its [`Model::channel_count`] and rejects the ones
+/// which do not exist with [`Error::InvalidChannel`].
+#[derive(Copy, Clone, PartialEq, Default)]
 #[cfg_attr(test, derive(Debug))]
 #[repr(u8)]
 pub enum Channel {
     #[allow(missing_docs)]
+    #[default]
     Channel0 = 0x0u8,
 
     #[allow(missing_docs)]
@@ -56,30 +85,179 @@ pub enum Channel {
 
     #[allow(missing_docs)]
     Channel5 = 0x5u8,
+
+    #[allow(missing_docs)]
+    Channel6 = 0x6u8,
+
+    #[allow(missing_docs)]
+    Channel7 = 0x7u8,
+
+    #[allow(missing_docs)]
+    Channel8 = 0x8u8,
+
+    #[allow(missing_docs)]
+    Channel9 = 0x9u8,
+
+    #[allow(missing_docs)]
+    Channel10 = 0xau8,
+
+    #[allow(missing_docs)]
+    Channel11 = 0xbu8,
+
+    #[allow(missing_docs)]
+    Channel12 = 0xcu8,
+
+    #[allow(missing_docs)]
+    Channel13 = 0xdu8,
+
+    #[allow(missing_docs)]
+    Channel14 = 0xeu8,
+
+    #[allow(missing_docs)]
+    Channel15 = 0xfu8,
+
+    #[allow(missing_docs)]
+    Channel16 = 0x10u8,
+
+    #[allow(missing_docs)]
+    Channel17 = 0x11u8,
+
+    #[allow(missing_docs)]
+    Channel18 = 0x12u8,
+
+    #[allow(missing_docs)]
+    Channel19 = 0x13u8,
+
+    #[allow(missing_docs)]
+    Channel20 = 0x14u8,
+
+    #[allow(missing_docs)]
+    Channel21 = 0x15u8,
+
+    #[allow(missing_docs)]
+    Channel22 = 0x16u8,
+
+    #[allow(missing_docs)]
+    Channel23 = 0x17u8,
 }
 
-impl Default for Channel {
-    fn default() -> Self {
-        Self::Channel0
-    }
+/// ### Purpose:
+/// All channels, indexed by their channel number.
+const CHANNELS: [Channel; MAX_CHANNEL_COUNT as usize] = [
+    Channel::Channel0,
+    Channel::Channel1,
+    Channel::Channel2,
+    Channel::Channel3,
+    Channel::Channel4,
+    Channel::Channel5,
+    Channel::Channel6,
+    Channel::Channel7,
+    Channel::Channel8,
+    Channel::Channel9,
+    Channel::Channel10,
+    Channel::Channel11,
+    Channel::Channel12,
+    Channel::Channel13,
+    Channel::Channel14,
+    Channel::Channel15,
+    Channel::Channel16,
+    Channel::Channel17,
+    Channel::Channel18,
+    Channel::Channel19,
+    Channel::Channel20,
+    Channel::Channel21,
+    Channel::Channel22,
+    Channel::Channel23,
+];
+
+/// ### Purpose:
+/// The modes in which a channel can be configured (through the Maestro
+/// Control Center).
+///
+/// ### Notes:
+/// Only servo channels accept servo commands (i.e., `set_target`,
+/// `set_speed`). The mode must match the board's settings; `raestro` cannot
+/// change it.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum ChannelMode {
+    /// The channel drives a servo.
+    #[default]
+    Servo,
+
+    /// The channel is an input, whose level (or voltage) is read back
+    /// through `get_position`.
+    Input,
+
+    /// The channel is a digital output, which is driven low by targets below
+    /// `1500us` and high by all others.
+    Output,
 }
 
-impl Iterator for Channel {
-    type Item = Channel;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let channel = *self as u8;
-        match channel {
-            _ if channel == CHANNEL_COUNT - 1 => None,
-            _ => {
-                let channel = channel + 1;
-                let channel = unsafe { std::mem::transmute(channel) };
-                Some(channel)
-            },
+/// ### Purpose:
+/// How often the Maestro applies a channel's speed and acceleration limits.
+///
+/// ### Notes:
+/// Speeds and accelerations are expressed per update, so their physical
+/// units scale with the update interval. This must match the board's
+/// settings.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum UpdateRate {
+    /// Twice per servo period (i.e., every `10ms` at the default `20ms`
+    /// period).
+    #[default]
+    HalfPeriod,
+
+    /// Once per servo period.
+    Period,
+}
+
+impl UpdateRate {
+    /// ### Purpose:
+    /// The time between updates at the given servo period.
+    pub fn interval(self, servo_period: Duration) -> Duration {
+        match self {
+            Self::HalfPeriod => servo_period / 2u32,
+            Self::Period => servo_period,
         }
     }
 }
 
+impl Channel {
+    /// ### Purpose:
+    /// Converts a raw channel number into a [`Channel`], checking that it
+    /// exists on the given model.
+    ///
+    /// ### Examples:
+    /// ```rust
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::constants::Model;
+    /// assert!(Channel::new(5u8, Model::MicroMaestro6).is_ok());
+    /// assert!(Channel::new(6u8, Model::MicroMaestro6).is_err());
+    /// ```
+    pub fn new(channel: u8, model: Model) -> crate::Result<Self> {
+        Self::try_from(channel)
+            .ok()
+            .filter(|&channel| model.has_channel(channel))
+            .ok_or(Error::InvalidChannel(channel))
+    }
+}
+
+impl TryFrom<u8> for Channel {
+    type Error = Error;
+
+    /// ### Purpose:
+    /// Converts a raw channel number into a [`Channel`].
+    ///
+    /// ### Notes:
+    /// This only checks the channel against the largest Maestro model.
+    fn try_from(channel: u8) -> Result<Self, Self::Error> {
+        CHANNELS
+            .get(channel as usize)
+            .copied()
+            .ok_or(Error::InvalidChannel(channel))
+    }
+}
+
 /// ### Purpose:
 /// Available baudrates supported by the Maestro.
 ///
@@ -93,10 +271,168 @@ pub enum Baudrate {
     #[allow(missing_docs)]
     Baudrate50 = 50u32,
 
+    #[allow(missing_docs)]
+    Baudrate9600 = 9600u32,
+
+    #[allow(missing_docs)]
+    Baudrate19200 = 19200u32,
+
+    #[allow(missing_docs)]
+    Baudrate38400 = 38400u32,
+
+    #[allow(missing_docs)]
+    Baudrate57600 = 57600u32,
+
     #[allow(missing_docs)]
     Baudrate11520 = 115200u32,
 }
 
+/// ### Purpose:
+/// The serial protocols in which commands can be sent to the Maestro.
+///
+/// ### Notes:
+/// The Maestro accepts both protocols at all times, so this only changes what
+/// `raestro` sends.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum Protocol {
+    /// Each command is prefixed with the `0xAA` sync byte and the device
+    /// number, and the command byte has its top bit cleared. This is required
+    /// when several Maestros share a single serial line.
+    #[default]
+    Pololu,
+
+    /// The command byte is sent with its top bit set, with no prefix. Each
+    /// command is 2 bytes shorter than in the Pololu protocol.
+    Compact,
+}
+
+/// ### Purpose:
+/// The Maestro models which `raestro` can talk to.
+///
+/// ### Notes:
+/// All models speak the same serial protocol, but the Mini Maestros support a
+/// few extra commands (i.e., Set Multiple Targets and Set PWM) and have much
+/// deeper script stacks.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum Model {
+    /// The 6-channel Micro Maestro.
+    #[default]
+    MicroMaestro6,
+
+    /// The 12-channel Mini Maestro.
+    MiniMaestro12,
+
+    /// The 18-channel Mini Maestro.
+    MiniMaestro18,
+
+    /// The 24-channel Mini Maestro.
+    MiniMaestro24,
+}
+
+impl Model {
+    /// ### Purpose:
+    /// The number of channels on this model.
+    pub fn channel_count(self) -> u8 {
+        match self {
+            Self::MicroMaestro6 => 6u8,
+            Self::MiniMaestro12 => 12u8,
+            Self::MiniMaestro18 => 18u8,
+            Self::MiniMaestro24 => 24u8,
+        }
+    }
+
+    /// ### Purpose:
+    /// All of the channels on this model, in order.
+    pub fn channels(self) -> impl Iterator<Item = Channel> {
+        CHANNELS[..self.channel_count() as usize].iter().copied()
+    }
+
+    /// ### Purpose:
+    /// Whether the given channel exists on this model.
+    pub fn has_channel(self, channel: Channel) -> bool {
+        (channel as u8) < self.channel_count()
+    }
+
+    /// ### Purpose:
+    /// The maximum depth of the script's data stack.
+    pub fn script_stack_depth(self) -> u8 {
+        match self {
+            Self::MicroMaestro6 => 32u8,
+            _ => 126u8,
+        }
+    }
+
+    /// ### Purpose:
+    /// The maximum depth of the script's call stack (i.e., how deeply
+    /// subroutines can be nested).
+    pub fn script_call_stack_depth(self) -> u8 {
+        match self {
+            Self::MicroMaestro6 => 10u8,
+            _ => 126u8,
+        }
+    }
+
+    /// ### Purpose:
+    /// Whether this model supports the Set Multiple Targets command.
+    pub fn supports_set_multiple_targets(self) -> bool {
+        self != Self::MicroMaestro6
+    }
+
+    /// ### Purpose:
+    /// Whether this model has a general-purpose PWM output (set through the
+    /// Set PWM command).
+    pub fn supports_pwm(self) -> bool {
+        self.pwm_channel().is_some()
+    }
+
+    /// ### Purpose:
+    /// The channel the general-purpose PWM output is on, if this model has
+    /// one.
+    pub fn pwm_channel(self) -> Option<Channel> {
+        match self {
+            Self::MicroMaestro6 => None,
+            Self::MiniMaestro12 => Some(Channel::Channel8),
+            Self::MiniMaestro18 | Self::MiniMaestro24 => {
+                Some(Channel::Channel12)
+            },
+        }
+    }
+
+    /// ### Purpose:
+    /// Whether this model's servo period can be changed from
+    /// [`DEFAULT_SERVO_PERIOD`].
+    pub fn supports_servo_period(self) -> bool {
+        self != Self::MicroMaestro6
+    }
+}
+
+/// ### Purpose:
+/// The state of the Maestro's script, as returned by `script_status`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ScriptStatus {
+    /// The script is running.
+    Running,
+
+    /// The script is stopped (i.e., it has quit, hit an error, or was stopped
+    /// by `stop_script`).
+    Stopped,
+}
+
+impl From<u8> for ScriptStatus {
+    /// ### Purpose:
+    /// Converts the byte returned by the Maestro into a [`ScriptStatus`].
+    ///
+    /// ### Notes:
+    /// The Maestro responds with `0x00` if the script is running, and `0x01`
+    /// otherwise.
+    fn from(data: u8) -> Self {
+        match data {
+            0u8 => Self::Running,
+            _ => Self::Stopped,
+        }
+    }
+}
+
 /// ### Purpose:
 /// All available errors throwable by the Maestro board.
 ///
@@ -105,8 +441,7 @@ pub enum Baudrate {
 /// documentation provided was taken directly
 /// from [Section 4.e of the Pololu Micro Maestro
 /// manual](https://www.pololu.com/docs/pdf/0J40/maestro.pdf).
-#[derive(PartialEq)]
-#[cfg_attr(test, derive(Debug))]
+#[derive(Copy, Clone, PartialEq, Debug)]
 #[repr(u16)]
 pub enum ErrorValues {
     /// A hardware-level error that occurs when a byte’s stop bit is not
@@ -205,7 +540,7 @@ impl ErrorValues {
     /// can be set in the [`u16`]. All other bits are ignored.
     pub fn from_data(data: u16) -> Vec<ErrorValues> {
         const MASK: u16 = 0x0001u16;
-        let (_, errors) = (0u16..=8u16).into_iter().fold(
+        let (_, errors) = (0u16..=8u16).fold(
             (data, vec![]),
             |(mut data, mut errors), index| {
                 let masked_data = data & MASK;
@@ -236,7 +571,7 @@ impl From<u16> for ErrorValues {
             ..=(ErrorValues::ScriptPcError as u16))
             .contains(&data);
         match contained {
-            true => unsafe { std::mem::transmute(data) },
+            true => unsafe { std::mem::transmute::<u16, ErrorValues>(data) },
             false => unreachable!(
                 "The data should always be contained within the above"
             ),
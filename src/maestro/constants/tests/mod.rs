@@ -1 +1,2 @@
 mod maestro_error;
+mod model;
@@ -0,0 +1,59 @@
+use crate::errors::Error;
+use crate::maestro::constants::Channel;
+use crate::maestro::constants::Model;
+
+#[test]
+fn channel_counts() {
+    assert_eq!(Model::MicroMaestro6.channels().count(), 6usize);
+    assert_eq!(Model::MiniMaestro12.channels().count(), 12usize);
+    assert_eq!(Model::MiniMaestro18.channels().count(), 18usize);
+    assert_eq!(Model::MiniMaestro24.channels().count(), 24usize);
+}
+
+#[test]
+fn has_channel() {
+    assert!(Model::MicroMaestro6.has_channel(Channel::Channel5));
+    assert!(!Model::MicroMaestro6.has_channel(Channel::Channel6));
+    assert!(Model::MiniMaestro18.has_channel(Channel::Channel17));
+    assert!(!Model::MiniMaestro18.has_channel(Channel::Channel18));
+}
+
+#[test]
+fn channel_from_u8() {
+    assert_eq!(Channel::try_from(17u8).unwrap(), Channel::Channel17);
+    assert!(matches!(
+        Channel::try_from(24u8),
+        Err(Error::InvalidChannel(24u8))
+    ));
+}
+
+#[test]
+fn channel_on_model() {
+    assert_eq!(
+        Channel::new(11u8, Model::MiniMaestro12).unwrap(),
+        Channel::Channel11
+    );
+    assert!(matches!(
+        Channel::new(12u8, Model::MiniMaestro12),
+        Err(Error::InvalidChannel(12u8))
+    ));
+    assert!(matches!(
+        Channel::new(24u8, Model::MiniMaestro24),
+        Err(Error::InvalidChannel(24u8))
+    ));
+}
+
+#[test]
+fn limits() {
+    let micro = Model::MicroMaestro6;
+    let mini = Model::MiniMaestro12;
+
+    assert_eq!(micro.script_stack_depth(), 32u8);
+    assert_eq!(micro.script_call_stack_depth(), 10u8);
+    assert!(!micro.supports_set_multiple_targets());
+    assert!(!micro.supports_pwm());
+    assert_eq!(mini.script_stack_depth(), 126u8);
+    assert_eq!(mini.script_call_stack_depth(), 126u8);
+    assert!(mini.supports_set_multiple_targets());
+    assert!(mini.supports_pwm());
+}
@@ -1,26 +1,34 @@
 #![allow(unused)]
 
-pub(super) const BUFFER_SIZE: usize = 6usize;
-pub(super) const SYNC: u8 = 0xaau8;
-pub(super) const DEVICE_NUMBER: u8 = 0x0cu8;
-pub(super) const DATA_BITS: u8 = 8u8;
-pub(super) const STOP_BITS: u8 = 1u8;
-pub(super) const MIN_WRITE_LENGTH: usize = 3usize;
-pub(super) const WRITE_COMMAND_SIZE: usize = 3usize;
-pub(super) const WRITE_CHANNEL_SIZE: usize = 4usize;
-pub(super) const WRITE_CHANNEL_AND_PAYLOAD_SIZE: usize = 6usize;
-pub(super) const RESPONSE_SIZE: u8 = 2u8;
+use std::time::Duration;
+
+pub(crate) const BUFFER_SIZE: usize = 256usize;
+pub(crate) const SYNC: u8 = 0xaau8;
+pub(crate) const DEVICE_NUMBER: u8 = 0x0cu8;
+pub(crate) const MINI_SSC: u8 = 0xffu8;
+pub(crate) const CRC7_POLY: u8 = 0x91u8;
+pub(crate) const DATA_BITS: u8 = 8u8;
+pub(crate) const STOP_BITS: u8 = 1u8;
+pub(crate) const RESPONSE_SIZE: u8 = 2u8;
+pub(crate) const OFF: u16 = 0u16;
+pub(crate) const OUTPUT_LOW: u16 = OFF;
+pub(crate) const OUTPUT_HIGH: u16 = 6000u16;
+pub(crate) const INPUT_THRESHOLD: u16 = 512u16;
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(10u64);
+pub(crate) const SYNC_RAMP_FRACTION: f64 = 0.25f64;
 
 /// ### Purpose:
 /// All available command flags supported by the `Pololu Protocol`.
-#[derive(Copy, Clone, PartialEq)]
-#[cfg_attr(test, derive(Debug))]
+#[derive(Copy, Clone, PartialEq, Debug)]
 #[repr(u8)]
-pub(super) enum CommandFlags {
+pub(crate) enum CommandFlags {
     SetTarget = 0x84u8,
     SetSpeed = 0x87u8,
     SetAcceleration = 0x89u8,
+    SetPwm = 0x8Au8,
     GetPosition = 0x90u8,
+    GetMovingState = 0x93u8,
+    SetMultipleTargets = 0x9Fu8,
     GetErrors = 0xA1u8,
     GoHome = 0xA2u8,
     StopScript = 0xA4u8,
@@ -28,3 +36,42 @@ pub(super) enum CommandFlags {
     RestartScriptAtSubRoutineWithParameter = 0xA8u8,
     GetScriptStatus = 0xAEu8,
 }
+
+impl CommandFlags {
+    /// ### Purpose:
+    /// The number of bytes the Maestro responds to this command with.
+    pub(crate) fn response_size(self) -> usize {
+        match self {
+            Self::GetPosition | Self::GetErrors => RESPONSE_SIZE as usize,
+            Self::GetMovingState | Self::GetScriptStatus => 1usize,
+            _ => 0usize,
+        }
+    }
+}
+
+impl TryFrom<u8> for CommandFlags {
+    type Error = u8;
+
+    /// ### Purpose:
+    /// Converts a raw command byte (with its top bit set) back into a
+    /// [`CommandFlags`], returning the byte itself if it is unknown.
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        let flag = match byte {
+            0x84u8 => Self::SetTarget,
+            0x87u8 => Self::SetSpeed,
+            0x89u8 => Self::SetAcceleration,
+            0x8Au8 => Self::SetPwm,
+            0x90u8 => Self::GetPosition,
+            0x93u8 => Self::GetMovingState,
+            0x9Fu8 => Self::SetMultipleTargets,
+            0xA1u8 => Self::GetErrors,
+            0xA2u8 => Self::GoHome,
+            0xA4u8 => Self::StopScript,
+            0xA7u8 => Self::RestartScriptAtSubRoutine,
+            0xA8u8 => Self::RestartScriptAtSubRoutineWithParameter,
+            0xAEu8 => Self::GetScriptStatus,
+            _ => return Err(byte),
+        };
+        Ok(flag)
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Support for the Mini SSC II protocol.
+//!
+//! A Mini SSC command is three bytes long: `0xFF`, a servo number and an 8-bit
+//! position (`0` to `254`). The Maestro maps position `127` to the channel's
+//! neutral point, and positions `0` and `254` to `neutral - range` and
+//! `neutral + range`, respectively. Servo numbers are offset by the Maestro's
+//! Mini SSC offset, which lets a Maestro sit behind other Mini SSC devices on
+//! the same serial line.
+//!
+//! The neutral points, ranges and offset configured here must match the
+//! settings saved on the board (through the Maestro Control Center).
+//!
+//! ### Examples:
+//! ```rust
+//! # use raestro::maestro::builder::Builder;
+//! # use raestro::maestro::constants::Channel;
+//! # use raestro::maestro::mini_ssc::MiniSscSettings;
+//! # use raestro::maestro::Maestro;
+//! # use raestro::transport::Memory;
+//! # fn main() -> raestro::Result<()> {
+//! let settings = MiniSscSettings::default()
+//!     .offset(8u8)
+//!     .range(Channel::Channel0, 6000u16, 2000u16);
+//! let mut maestro: Maestro<Memory> = Builder::default()
+//!     .mini_ssc(settings)
+//!     .build_with(Memory::new())?;
+//!
+//! maestro.mini_ssc().set_target(Channel::Channel0, 7000u16)?;
+//! assert_eq!(maestro.transport().written(), [0xffu8, 0x08u8, 0xbfu8]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::errors::Error;
+use crate::maestro::constants::Channel;
+use crate::maestro::constants::ChannelMode;
+use crate::maestro::constants::MAX_CHANNEL_COUNT;
+use crate::maestro::internals;
+use crate::maestro::units::PulseWidth;
+use crate::maestro::Maestro;
+use crate::transport::Transport;
+
+/// ### Purpose:
+/// The largest position (and servo number) expressible in Mini SSC.
+pub const MAX_MINI_SSC_VALUE: u8 = 254u8;
+
+/// ### Purpose:
+/// The Mini SSC position which maps to a channel's neutral point.
+const NEUTRAL_POSITION: u8 = 127u8;
+
+/// ### Purpose:
+/// The mapping of Mini SSC positions onto a single channel.
+///
+/// ### Notes:
+/// Both fields are in quarter-us. The Maestro's defaults are a neutral point
+/// of `1500us` and a range of `476.25us`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MiniSscRange {
+    /// ### Purpose:
+    /// The target which Mini SSC position `127` maps to.
+    pub neutral: u16,
+
+    /// ### Purpose:
+    /// How far positions `0` and `254` are from the neutral point.
+    pub range: u16,
+}
+
+impl Default for MiniSscRange {
+    fn default() -> Self {
+        Self {
+            neutral: 6000u16,
+            range: 1905u16,
+        }
+    }
+}
+
+impl MiniSscRange {
+    /// ### Purpose:
+    /// Converts a Mini SSC position into a target (in quarter-us).
+    pub fn to_target(&self, position: u8) -> u16 {
+        let Self { neutral, range } = *self;
+        let offset = i32::from(position) - i32::from(NEUTRAL_POSITION);
+        let target = i32::from(neutral)
+            + offset * i32::from(range) / i32::from(NEUTRAL_POSITION);
+        target.clamp(0i32, i32::from(u16::MAX)) as u16
+    }
+
+    /// ### Purpose:
+    /// Converts a target (in quarter-us) into the nearest Mini SSC position,
+    /// or `None` if the target lies outside of this range.
+    pub fn to_position(&self, target: u16) -> Option<u8> {
+        let Self { neutral, range } = *self;
+        let offset = f64::from(target) - f64::from(neutral);
+        let position = f64::from(NEUTRAL_POSITION)
+            + offset * f64::from(NEUTRAL_POSITION) / f64::from(range.max(1u16));
+        let position = position.round();
+        (0f64..=f64::from(MAX_MINI_SSC_VALUE))
+            .contains(&position)
+            .then_some(position as u8)
+    }
+}
+
+/// ### Purpose:
+/// The Mini SSC configuration of a Maestro board.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MiniSscSettings {
+    /// ### Purpose:
+    /// The servo number which maps to channel 0.
+    pub offset: u8,
+
+    /// ### Purpose:
+    /// The range of each channel, indexed by channel.
+    pub ranges: Vec<MiniSscRange>,
+}
+
+impl Default for MiniSscSettings {
+    fn default() -> Self {
+        Self {
+            offset: 0u8,
+            ranges: vec![MiniSscRange::default(); MAX_CHANNEL_COUNT as usize],
+        }
+    }
+}
+
+impl MiniSscSettings {
+    /// ### Purpose:
+    /// Convenience function to configure the Mini SSC offset.
+    pub fn offset(self, offset: u8) -> Self {
+        Self { offset, ..self }
+    }
+
+    /// ### Purpose:
+    /// Convenience function to configure the neutral point and range of a
+    /// single channel (both in quarter-us).
+    pub fn range(mut self, channel: Channel, neutral: u16, range: u16) -> Self {
+        self.resize();
+        self.ranges[channel as usize] = MiniSscRange { neutral, range };
+        self
+    }
+
+    /// ### Purpose:
+    /// Pads (or truncates) `self.ranges` to one range per channel, so that
+    /// every channel can be indexed.
+    pub(crate) fn resize(&mut self) {
+        self.ranges
+            .resize(MAX_CHANNEL_COUNT as usize, MiniSscRange::default());
+    }
+
+    /// ### Purpose:
+    /// The channel addressed by the given servo number, if any.
+    pub fn channel_index(&self, servo: u8) -> Option<usize> {
+        servo
+            .checked_sub(self.offset)
+            .map(usize::from)
+            .filter(|&index| index < self.ranges.len())
+    }
+}
+
+/// ### Purpose:
+/// A handle for sending Mini SSC commands through a [`Maestro`].
+///
+/// ### Notes:
+/// Obtained through [`Maestro::mini_ssc`].
+pub struct MiniSsc<'a, T>
+where
+    T: Transport,
+{
+    pub(super) maestro: &'a mut Maestro<T>,
+}
+
+impl<'a, T> MiniSsc<'a, T>
+where
+    T: Transport,
+{
+    /// ### Purpose:
+    /// Sends the given 8-bit position to the given channel.
+    ///
+    /// ### Notes:
+    /// Positions can only be between `0` and `254`.
+    pub fn set_position(
+        &mut self,
+        channel: Channel,
+        position: u8,
+    ) -> crate::Result<()> {
+        (position <= MAX_MINI_SSC_VALUE)
+            .then_some(())
+            .ok_or(Error::MiniSscOutOfRange(u16::from(position)))?;
+        self.maestro.check_mode(channel, ChannelMode::Servo)?;
+        let channel = self.maestro.check_channel(channel)?;
+        let servo =
+            u16::from(self.maestro.mini_ssc.offset) + u16::from(channel);
+        let servo = u8::try_from(servo)
+            .ok()
+            .filter(|&servo| servo <= MAX_MINI_SSC_VALUE)
+            .ok_or(Error::MiniSscOutOfRange(servo))?;
+        let write_buf = &mut self.maestro.write_buf;
+        write_buf[0usize] = internals::MINI_SSC;
+        write_buf[1usize] = servo;
+        write_buf[2usize] = position;
+        self.maestro.transact(3usize, 0usize)
+    }
+
+    /// ### Purpose:
+    /// Sends the given target (in quarter-us) to the given channel.
+    ///
+    /// ### Notes:
+    /// The target is rounded to the nearest 8-bit position using the
+    /// channel's configured neutral point and range. Targets outside of that
+    /// range will return an error.
+    pub fn set_target<P>(
+        &mut self,
+        channel: Channel,
+        target: P,
+    ) -> crate::Result<()>
+    where
+        P: Into<PulseWidth>,
+    {
+        let target = target.into().qtr_micros();
+        let index = usize::from(self.maestro.check_channel(channel)?);
+        let position = self.maestro.mini_ssc.ranges[index]
+            .to_position(target)
+            .ok_or(Error::MiniSscOutOfRange(target))?;
+        self.set_position(channel, position)
+    }
+}
@@ -8,72 +8,225 @@
 //! The main source module for the [`Maestro`] struct, as well as all related
 //! definitions.
 
+pub mod analog;
 pub mod builder;
+pub mod bus;
+pub mod calibration;
 pub mod constants;
-mod internals;
-mod utils;
+pub(crate) mod internals;
+pub mod mini_ssc;
+pub mod motion;
+pub mod pwm;
+pub mod sequence;
+pub mod units;
+pub(crate) mod utils;
+
+#[cfg(test)]
+mod tests;
 
 use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
 use rppal::uart::Uart;
 
 use crate::errors::Error;
+use crate::maestro::analog::AnalogReading;
+use crate::maestro::calibration::ServoCalibration;
+use crate::maestro::constants::ChannelMode;
 use crate::maestro::constants::ErrorValues;
+use crate::maestro::constants::Model;
+use crate::maestro::constants::Protocol;
+use crate::maestro::constants::ScriptStatus;
+use crate::maestro::constants::UpdateRate;
+use crate::maestro::mini_ssc::MiniSsc;
+use crate::maestro::mini_ssc::MiniSscSettings;
+use crate::maestro::motion::Trajectory;
+use crate::maestro::pwm::Pwm;
+use crate::maestro::units::Converted;
+use crate::maestro::units::PulseWidth;
+use crate::maestro::units::ServoAccel;
+use crate::maestro::units::ServoSpeed;
+use crate::maestro::utils::crc7;
 use crate::maestro::utils::mask_byte;
 use crate::maestro::utils::microsec_to_target;
+use crate::script::Program;
+use crate::transport::Transport;
 
 /// ### Purpose:
 /// The main wrapper around the Maestro
 /// communications interface.
+///
+/// ### Notes:
+/// The Maestro is generic over the [`Transport`] used to reach the board.
+/// By default, this is the Raspberry Pi's GPIO UART.
 #[cfg_attr(test, derive(Debug))]
-pub struct Maestro {
-    uart: Uart,
+pub struct Maestro<T = Uart>
+where
+    T: Transport,
+{
+    transport: T,
+    protocol: Protocol,
+    device_number: u8,
+    model: Model,
+    channel_modes: Vec<ChannelMode>,
+    target_limits: Vec<RangeInclusive<u16>>,
+    calibrations: Vec<ServoCalibration>,
+    servo_period: Duration,
+    update_rates: Vec<UpdateRate>,
+    mini_ssc: MiniSscSettings,
+    crc: bool,
+    pending_errors: u16,
     read_buf: [u8; internals::BUFFER_SIZE],
     write_buf: [u8; internals::BUFFER_SIZE],
 }
 
-impl Maestro {
+impl<T> Maestro<T>
+where
+    T: Transport,
+{
+    /// ### Purpose:
+    /// The serial protocol this Maestro is sending commands in.
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    /// ### Purpose:
+    /// The device number this Maestro is addressed by in the Pololu
+    /// protocol.
+    pub fn device_number(&self) -> u8 {
+        self.device_number
+    }
+
+    /// ### Purpose:
+    /// The model of the board this Maestro was built for.
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// ### Purpose:
+    /// The range of targets (in quarter-us) the given channel accepts.
+    ///
+    /// ### Notes:
+    /// A target of `0` (i.e., turning the channel off) is always accepted.
+    pub fn target_limits(
+        &self,
+        channel: constants::Channel,
+    ) -> RangeInclusive<u16> {
+        self.target_limits[channel as usize].clone()
+    }
+
+    /// ### Purpose:
+    /// The calibration used by `set_angle` and `get_angle` on the given
+    /// channel.
+    pub fn calibration(
+        &self,
+        channel: constants::Channel,
+    ) -> &ServoCalibration {
+        &self.calibrations[channel as usize]
+    }
+
+    /// ### Purpose:
+    /// The servo period this Maestro was built with.
+    pub fn servo_period(&self) -> Duration {
+        self.servo_period
+    }
+
+    /// ### Purpose:
+    /// How often the given channel's speed and acceleration limits are
+    /// applied.
+    pub fn update_rate(&self, channel: constants::Channel) -> UpdateRate {
+        self.update_rates[channel as usize]
+    }
+
+    /// ### Purpose:
+    /// The mode the given channel has been configured in.
+    pub fn channel_mode(&self, channel: constants::Channel) -> ChannelMode {
+        self.channel_modes[channel as usize]
+    }
+
+    /// ### Purpose:
+    /// A handle for sending commands in the Mini SSC protocol.
+    ///
+    /// ### Notes:
+    /// See [`mini_ssc`] for how positions are mapped onto targets.
+    pub fn mini_ssc(&mut self) -> MiniSsc<'_, T> {
+        MiniSsc { maestro: self }
+    }
+
+    /// ### Purpose:
+    /// A shared reference to the underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// ### Purpose:
+    /// A mutable reference to the underlying transport.
+    ///
+    /// ### Notes:
+    /// Writing to the transport directly bypasses the Maestro entirely, and
+    /// can leave the board in the middle of a half-received command packet.
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
     /// Sets the target of the servo motor at the
     /// given channel with
     /// the given microseconds.
     ///
-    /// Microsecond ranges can only be between
-    /// `992us` and `2000us`.
+    /// By default, microsecond ranges can only
+    /// be between `992us` and `2000us`.
     /// However, the input to `set_target` is in
     /// quarter microseconds. Thus, the accepted
     /// range to `set_target` is between `3968`
-    /// and `8000`.
-    /// Any values outside of this range will
-    /// return an error.
+    /// and `8000`. Other ranges can be
+    /// configured per channel through
+    /// [`builder::Builder::target_limits`].
+    /// Any values outside of the channel's range
+    /// will return an error, except for `0`,
+    /// which stops sending pulses (see
+    /// `release`).
     ///
     /// The units to `set_target` are in:
     /// `target * (0.25) [us]`
     ///
     /// # Example Usage
-    /// ```
-    /// let maestro: Maestro = Builder::default()
-    ///     .baudrate(Baudrate::Baudrate50)
-    ///     .block_duration(Duration::from_secs(10))
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
     ///     .try_into()?;
     ///
     /// // can be any arbitrary channel in the Channel enum
-    /// let channel: Channel = Channel::C_0;
+    /// let channel: Channel = Channel::Channel0;
     ///
     /// // can be any value between 3968 and 8000
     /// // 4000 quarter microsecs would be 1000us, thus this example sets a target of 1000us
     /// let qtr_microsec = 4000u16;
     ///
-    /// maestro.set_target(channel, qtr_microsec);
+    /// maestro.set_target(channel, qtr_microsec)?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn set_target(
+    pub fn set_target<P>(
         &mut self,
         channel: constants::Channel,
-        target: u16,
-    ) -> crate::Result<()> {
-        (constants::MIN_QTR_PWM..=constants::MAX_QTR_PWM)
-            .contains(&target)
-            .then(|| ())
-            .ok_or_else(|| Error::InvalidValue(target))?;
+        target: P,
+    ) -> crate::Result<()>
+    where
+        P: Into<PulseWidth>,
+    {
+        let target = target.into().qtr_micros();
+        self.check_target(channel as u8, target)?;
+        self.check_mode(channel, ChannelMode::Servo)?;
         self.write_channel_and_payload(
             internals::CommandFlags::SetTarget,
             channel,
@@ -81,30 +234,192 @@ impl Maestro {
         )
     }
 
+    /// Sets the targets of a contiguous block of
+    /// channels, starting at the given channel.
+    ///
+    /// Each target follows the same rules as in
+    /// `set_target`. The block must fit within
+    /// the Maestro's channels, otherwise an error
+    /// is returned.
+    ///
+    /// On the Mini Maestros, this is sent as a
+    /// single Set Multiple Targets command, so
+    /// that all of the channels start moving in
+    /// the same frame. The Micro Maestro does not
+    /// support that command, so one `set_target`
+    /// packet is sent per channel instead (all in
+    /// a single write).
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::constants::Model;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .model(Model::MiniMaestro12)
+    ///     .try_into()?;
+    ///
+    /// // sets channels 1, 2 and 3 at once
+    /// maestro.set_targets(Channel::Channel1, &[4000u16, 6000u16, 8000u16])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_targets<P>(
+        &mut self,
+        first_channel: constants::Channel,
+        targets: &[P],
+    ) -> crate::Result<()>
+    where
+        P: Into<PulseWidth> + Copy,
+    {
+        let targets = targets
+            .iter()
+            .map(|&target| target.into().qtr_micros())
+            .collect::<Vec<_>>();
+        let first_channel = self.check_channel(first_channel)?;
+        let end = usize::from(first_channel) + targets.len();
+        if end > usize::from(self.model.channel_count()) {
+            let last_channel = (end - 1usize).min(usize::from(u8::MAX)) as u8;
+            return Err(Error::InvalidChannel(last_channel));
+        };
+        (first_channel..).zip(targets.iter()).try_for_each(
+            |(channel, &target)| self.check_target(channel, target),
+        )?;
+        let channel_modes =
+            &self.channel_modes[usize::from(first_channel)..end];
+        if let Some(offset) = channel_modes
+            .iter()
+            .position(|&mode| mode != ChannelMode::Servo)
+        {
+            let channel = first_channel + offset as u8;
+            return Err(Error::InvalidChannelMode(channel));
+        };
+        match (targets.len(), self.model.supports_set_multiple_targets()) {
+            (0usize, _) => Ok(()),
+            (_, true) => self.write_multiple_targets(first_channel, &targets),
+            (_, false) => {
+                let targets = (first_channel..).zip(targets.iter().copied());
+                self.write_individual_targets(targets)
+            },
+        }
+    }
+
+    /// Stops sending pulses to the servo at the
+    /// given channel (i.e., sets its target to
+    /// `0`), which lets most servos go limp.
+    ///
+    /// The next target sent to the channel is
+    /// applied immediately, without any speed or
+    /// acceleration limit, since the Maestro no
+    /// longer knows where the servo is.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// maestro.release(Channel::Channel0)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn release(
+        &mut self,
+        channel: constants::Channel,
+    ) -> crate::Result<()> {
+        self.set_target(channel, PulseWidth::OFF)
+    }
+
+    /// Stops sending pulses to every servo
+    /// channel on the Maestro, in a single
+    /// write.
+    ///
+    /// Input and output channels are left
+    /// untouched.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// maestro.release_all()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn release_all(&mut self) -> crate::Result<()> {
+        let targets = self
+            .model
+            .channels()
+            .filter(|&channel| self.channel_mode(channel) == ChannelMode::Servo)
+            .map(|channel| (channel as u8, internals::OFF))
+            .collect::<Vec<_>>();
+        match targets.is_empty() {
+            true => Ok(()),
+            false => self.write_individual_targets(targets),
+        }
+    }
+
     /// Sets the rotational speed of the servo
     /// motor at the given channel with the
     /// given speed.
     ///
     /// The units to `set_speed` are in:
     /// `speed * (0.025) [us / ms]`
+    /// at the default servo period (see
+    /// `set_speed_deg_per_sec` for other
+    /// periods).
     ///
     /// # Example Usage
-    /// ```
-    /// let maestro: Maestro = Builder::default()
-    ///     .baudrate(Baudrate::Baudrate50)
-    ///     .block_duration(Duration::from_secs(10))
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
     ///     .try_into()?;
     ///
-    /// let channel: Channel = Channel::C_0; // can be any arbitrary channel in the Channel enum
+    /// let channel: Channel = Channel::Channel0; // can be any arbitrary channel in the Channel enum
     /// let speed = 10u16;
     ///
-    /// m.set_speed(channel, speed);
+    /// maestro.set_speed(channel, speed)?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn set_speed(
+    pub fn set_speed<S>(
         &mut self,
         channel: constants::Channel,
-        speed: u16,
-    ) -> crate::Result<()> {
+        speed: S,
+    ) -> crate::Result<()>
+    where
+        S: Into<ServoSpeed>,
+    {
+        let speed = speed.into().raw();
+        self.check_mode(channel, ChannelMode::Servo)?;
+        check_speed(speed)?;
         self.write_channel_and_payload(
             internals::CommandFlags::SetSpeed,
             channel,
@@ -130,25 +445,40 @@ impl Maestro {
     ///
     /// The units to `set_acceleration` are in:
     /// `acceleration * 0.0003125 [us / ((ms)^2)]`
+    /// at the default servo period (see
+    /// `set_accel_deg_per_sec2` for other
+    /// periods).
     ///
     /// # Example Usage
-    /// ```
-    /// use raestro::prelude::*;
-    ///
-    /// let mut m = Maestro::new();
-    /// m.start(BaudRates::BR_115200).unwrap();
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
     ///
-    /// let channel: Channel = Channel::C_0; // can be any arbitrary channel in the Channel enum
+    /// let channel: Channel = Channel::Channel0; // can be any arbitrary channel in the Channel enum
     /// let acceleration = 10u8;
     ///
-    /// m.set_acceleration(channel, acceleration);
+    /// maestro.set_acceleration(channel, acceleration)?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn set_acceleration(
+    pub fn set_acceleration<A>(
         &mut self,
         channel: constants::Channel,
-        acceleration: u8,
-    ) -> crate::Result<()> {
-        let acceleration = acceleration as u16;
+        acceleration: A,
+    ) -> crate::Result<()>
+    where
+        A: Into<ServoAccel>,
+    {
+        self.check_mode(channel, ChannelMode::Servo)?;
+        let acceleration = u16::from(acceleration.into().raw());
         self.write_channel_and_payload(
             internals::CommandFlags::SetAcceleration,
             channel,
@@ -156,19 +486,264 @@ impl Maestro {
         )
     }
 
+    /// Sets the speed limit of the servo at the
+    /// given channel in degrees per second.
+    ///
+    /// The speed is converted into pulse widths
+    /// through the channel's calibration (see
+    /// `set_angle`), and into raw units through
+    /// the channel's update interval (see
+    /// [`builder::Builder::servo_period`] and
+    /// [`builder::Builder::update_rate`]).
+    ///
+    /// Speeds which are too fast for the raw
+    /// field are clamped to the fastest limit the
+    /// Maestro can express. The speed that was
+    /// actually sent, and whether it was clamped,
+    /// are returned.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// let speed = maestro.set_speed_deg_per_sec(Channel::Channel0, 60f64)?;
+    /// assert!(!speed.saturated);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_speed_deg_per_sec(
+        &mut self,
+        channel: constants::Channel,
+        speed: f64,
+    ) -> crate::Result<Converted<ServoSpeed>> {
+        (speed > 0f64)
+            .then_some(())
+            .ok_or(Error::UnitOutOfRange(speed))?;
+        let micros = speed * self.calibration(channel).micros_per_degree();
+        let converted = ServoSpeed::saturating_from_micros_per_second(
+            micros,
+            self.update_interval(channel),
+        )?;
+        self.set_speed(channel, converted.value)?;
+        Ok(converted)
+    }
+
+    /// Sets the acceleration limit of the servo
+    /// at the given channel in degrees per second
+    /// squared.
+    ///
+    /// The acceleration is converted in the same
+    /// way as in `set_speed_deg_per_sec`, and is
+    /// likewise clamped to the fastest limit the
+    /// Maestro can express.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// let acceleration =
+    ///     maestro.set_accel_deg_per_sec2(Channel::Channel0, 360f64)?;
+    /// assert!(!acceleration.saturated);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_accel_deg_per_sec2(
+        &mut self,
+        channel: constants::Channel,
+        acceleration: f64,
+    ) -> crate::Result<Converted<ServoAccel>> {
+        (acceleration > 0f64)
+            .then_some(())
+            .ok_or(Error::UnitOutOfRange(acceleration))?;
+        let micros =
+            acceleration * self.calibration(channel).micros_per_degree();
+        let converted = ServoAccel::saturating_from_micros_per_second_squared(
+            micros,
+            self.update_interval(channel),
+        )?;
+        self.set_acceleration(channel, converted.value)?;
+        Ok(converted)
+    }
+
+    /// Drives the given output channel high or
+    /// low.
+    ///
+    /// The channel must have been configured as
+    /// an output (see
+    /// [`builder::Builder::channel_mode`]),
+    /// otherwise an error is returned.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::constants::ChannelMode;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .channel_mode(Channel::Channel5, ChannelMode::Output)
+    ///     .try_into()?;
+    ///
+    /// maestro.set_digital_output(Channel::Channel5, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_digital_output(
+        &mut self,
+        channel: constants::Channel,
+        high: bool,
+    ) -> crate::Result<()> {
+        self.check_mode(channel, ChannelMode::Output)?;
+        let target = match high {
+            true => internals::OUTPUT_HIGH,
+            false => internals::OUTPUT_LOW,
+        };
+        self.write_channel_and_payload(
+            internals::CommandFlags::SetTarget,
+            channel,
+            target,
+        )
+    }
+
+    /// Reads the level of the given input
+    /// channel.
+    ///
+    /// The channel must have been configured as
+    /// an input (see
+    /// [`builder::Builder::channel_mode`]),
+    /// otherwise an error is returned.
+    ///
+    /// Inputs are read through `get_position`.
+    /// Analog inputs report values from `0` to
+    /// `1023`, so anything from `512` upwards
+    /// (i.e., above `2.5V`) is read as high.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::constants::ChannelMode;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .channel_mode(Channel::Channel4, ChannelMode::Input)
+    ///     .try_into()?;
+    ///
+    /// let pressed = maestro.read_digital_input(Channel::Channel4)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_digital_input(
+        &mut self,
+        channel: constants::Channel,
+    ) -> crate::Result<bool> {
+        self.check_mode(channel, ChannelMode::Input)?;
+        let level = self.get_position(channel)?;
+        Ok(level >= internals::INPUT_THRESHOLD)
+    }
+
+    /// Sets the Mini Maestro's general-purpose
+    /// PWM output.
+    ///
+    /// See [`pwm`] for details, and
+    /// [`pwm::Pwm`] for building the signal
+    /// from raw ticks or from a frequency and a
+    /// duty cycle. An on-time of `0` turns the
+    /// output off.
+    ///
+    /// The PWM channel (see
+    /// [`Model::pwm_channel`]) must be
+    /// configured as an output. The Micro
+    /// Maestro has no PWM output, so this
+    /// returns an error on it.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::constants::ChannelMode;
+    /// # use raestro::maestro::constants::Model;
+    /// # use raestro::maestro::pwm::Pwm;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .model(Model::MiniMaestro12)
+    ///     .channel_mode(Channel::Channel8, ChannelMode::Output)
+    ///     .try_into()?;
+    ///
+    /// let pwm = Pwm::from_frequency(10_000f64, 0.5f64)?;
+    /// maestro.set_pwm(pwm)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_pwm(&mut self, pwm: Pwm) -> crate::Result<()> {
+        let channel =
+            self.model.pwm_channel().ok_or(Error::UnsupportedCommand)?;
+        self.check_mode(channel, ChannelMode::Output)?;
+        let Pwm { on_time, period } = Pwm::new(pwm.on_time, pwm.period)?;
+        let command_flag = internals::CommandFlags::SetPwm;
+        let index = self.write_header(0usize, command_flag);
+        let (on_time_lower, on_time_upper) = microsec_to_target(on_time);
+        let (period_lower, period_upper) = microsec_to_target(period);
+        let Self { write_buf, .. } = self;
+        write_buf[index] = on_time_lower;
+        write_buf[index + 1usize] = on_time_upper;
+        write_buf[index + 2usize] = period_lower;
+        write_buf[index + 3usize] = period_upper;
+        self.send(command_flag, index + 4usize)
+    }
+
     /// Sends all servos to home position.
     ///
     /// Home position is defined as
     /// `992us`.
     ///
     /// # Example Usage
-    /// ```
-    /// use raestro::prelude::*;
-    ///
-    /// let mut m = Maestro::new();
-    /// m.start(BaudRates::BR_115200).unwrap();
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
     ///
-    /// m.go_home();
+    /// maestro.go_home()?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn go_home(&mut self) -> crate::Result<()> {
         self.write_command(internals::CommandFlags::GoHome)
@@ -178,18 +753,174 @@ impl Maestro {
     /// Maestro to be stopped immediately.
     ///
     /// # Example Usage
-    /// ```
-    /// use raestro::prelude::*;
-    ///
-    /// let mut m = Maestro::new();
-    /// m.start(BaudRates::BR_115200).unwrap();
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
     ///
-    /// m.stop_script();
+    /// maestro.stop_script()?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn stop_script(&mut self) -> crate::Result<()> {
         self.write_command(internals::CommandFlags::StopScript)
     }
 
+    /// Restarts the Maestro's script at the
+    /// given subroutine.
+    ///
+    /// Subroutines are numbered in the order
+    /// they are defined in the script, starting
+    /// from `0`, and can only be between `0`
+    /// and `127`. Any other subroutine number
+    /// will return an error.
+    ///
+    /// The subroutine should end in a `quit`
+    /// command or an infinite loop; returning
+    /// from it causes a call stack underflow
+    /// on the Maestro.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// maestro.restart_script_at(0u8)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restart_script_at(&mut self, subroutine: u8) -> crate::Result<()> {
+        check_subroutine(subroutine)?;
+        self.write_byte(
+            internals::CommandFlags::RestartScriptAtSubRoutine,
+            subroutine,
+        )
+    }
+
+    /// Restarts the Maestro's script at the
+    /// given subroutine, after pushing the given
+    /// parameter onto the script's stack.
+    ///
+    /// Parameters are sent in 14 bits, so they
+    /// can only be between `0` and `16383`. Any
+    /// values outside of this range will
+    /// return an error.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// // Run subroutine 1 with 4000 on top of the stack.
+    /// maestro.restart_script_with_parameter(1u8, 4000u16)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restart_script_with_parameter(
+        &mut self,
+        subroutine: u8,
+        parameter: u16,
+    ) -> crate::Result<()> {
+        check_subroutine(subroutine)?;
+        (parameter <= constants::MAX_SCRIPT_PARAMETER)
+            .then_some(())
+            .ok_or(Error::InvalidParameter(parameter))?;
+        self.write_byte_and_payload(
+            internals::CommandFlags::RestartScriptAtSubRoutineWithParameter,
+            subroutine,
+            parameter,
+        )
+    }
+
+    /// Restarts the Maestro's script at the
+    /// subroutine with the given name, as
+    /// numbered by the compiled program's
+    /// subroutine table.
+    ///
+    /// Looking subroutines up by name keeps
+    /// working when subroutines are added to or
+    /// removed from the script. Names which the
+    /// program does not define will return an
+    /// error.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::Maestro;
+    /// # use raestro::script;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// let program = script::compile("quit sub wave 4000 0 servo quit")?;
+    /// maestro.restart_script_named(&program, "wave")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restart_script_named(
+        &mut self,
+        program: &Program,
+        name: &str,
+    ) -> crate::Result<()> {
+        let subroutine = program
+            .subroutine(name)
+            .ok_or_else(|| Error::UnknownSubroutine(name.to_string()))?;
+        self.restart_script_at(subroutine)
+    }
+
+    /// Gets whether the Maestro's script is
+    /// currently running.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::ScriptStatus;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// if maestro.script_status()? == ScriptStatus::Stopped {
+    ///     maestro.restart_script_at(0u8)?;
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn script_status(&mut self) -> crate::Result<ScriptStatus> {
+        self.write_command(internals::CommandFlags::GetScriptStatus)?;
+        let status = ScriptStatus::from(self.read_buf[0usize]);
+        Ok(status)
+    }
+
     /// Gets the `PWM` signal being broadcasted to
     /// the servo at the given channel.
     ///
@@ -232,30 +963,213 @@ impl Maestro {
     /// servo is currently broadcasting `1000us`
     /// to the respective channel.
     ///
-    /// # Example Usage
-    /// ```ignore
-    /// use raestro::prelude::*;
+    /// On input channels, `get_position`
+    /// instead returns the input's level (see
+    /// `read_digital_input` and `read_analog`).
     ///
-    /// let mut m = Maestro::new();
-    /// m.start(BaudRates::BR_115200).unwrap();
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
     ///
-    /// let channel: Channel = Channel::C_0; // can be any arbitrary channel in the Channel enum
+    /// let channel: Channel = Channel::Channel0; // can be any arbitrary channel in the Channel enum
     /// let target = 4000u16; // can be any value between 3968u16 and 8000u16
     ///
-    /// m.set_target(channel, target);
+    /// maestro.set_target(channel, target)?;
     ///
-    /// let actual_position = m.get_position(channel).unwrap();
+    /// let actual_position = maestro.get_position(channel)?;
     ///
-    /// assert_eq!(target, actual_position);
+    /// assert_eq!(actual_position, target);
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn get_position(
         &mut self,
         channel: constants::Channel,
-    ) -> crate::Result<u16> {
+    ) -> crate::Result<PulseWidth> {
         self.write_channel(internals::CommandFlags::GetPosition, channel)?;
-        self.read(internals::RESPONSE_SIZE as usize)?;
         let pos = self.prepare_data_from_buffer();
-        Ok(pos)
+        Ok(PulseWidth::from(pos))
+    }
+
+    /// Sets the target of the servo at the given
+    /// channel to the given angle (in degrees).
+    ///
+    /// The angle is mapped onto a pulse width by
+    /// the channel's calibration (see
+    /// [`builder::Builder::calibration`] and
+    /// [`calibration`]), and is then sent as in
+    /// `set_target`. Angles outside of the
+    /// calibration's range return an error.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::calibration::ServoCalibration;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::units::PulseWidth;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let calibration = ServoCalibration::new(
+    ///     PulseWidth::from_micros(1050f64)?,
+    ///     PulseWidth::from_micros(1950f64)?,
+    ///     PulseWidth::from_micros(1480f64)?,
+    ///     80f64,
+    /// )?
+    /// .reversed(true);
+    ///
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .calibration(Channel::Channel0, calibration)
+    ///     .try_into()?;
+    ///
+    /// maestro.set_angle(Channel::Channel0, 30f64)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_angle(
+        &mut self,
+        channel: constants::Channel,
+        degrees: f64,
+    ) -> crate::Result<()> {
+        let target = self.calibration(channel).to_pulse_width(degrees)?;
+        self.set_target(channel, target)
+    }
+
+    /// Gets the angle (in degrees) the servo at
+    /// the given channel is currently at.
+    ///
+    /// This is `get_position` mapped back
+    /// through the channel's calibration.
+    /// Positions outside of the calibrated
+    /// endpoints (i.e., a channel which is off)
+    /// are clamped to them.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// let degrees = maestro.get_angle(Channel::Channel0)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_angle(
+        &mut self,
+        channel: constants::Channel,
+    ) -> crate::Result<f64> {
+        let position = self.get_position(channel)?;
+        Ok(self.calibration(channel).to_degrees(position))
+    }
+
+    /// Reads the voltage on the given analog
+    /// input channel.
+    ///
+    /// The channel must have been configured as
+    /// an input (see
+    /// [`builder::Builder::channel_mode`]), and
+    /// must be one of channels `0` to `11`,
+    /// otherwise an error is returned. See
+    /// [`analog`] for how readings are
+    /// converted into volts.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::constants::ChannelMode;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .channel_mode(Channel::Channel3, ChannelMode::Input)
+    ///     .try_into()?;
+    ///
+    /// let reading = maestro.read_analog(Channel::Channel3)?;
+    /// println!("{} ({}V)", reading.raw, reading.volts);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_analog(
+        &mut self,
+        channel: constants::Channel,
+    ) -> crate::Result<AnalogReading> {
+        self.check_analog(channel)?;
+        let raw = self.get_position(channel)?.qtr_micros();
+        Ok(AnalogReading::from_raw(raw))
+    }
+
+    /// Reads the voltage on the given analog
+    /// input channel several times, and
+    /// averages the readings.
+    ///
+    /// Samples are taken `interval` apart. The
+    /// Maestro only samples its inputs once per
+    /// servo period (`20ms` by default), so
+    /// shorter intervals will mostly read the
+    /// same value several times. At least one
+    /// sample is always taken.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::constants::ChannelMode;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .channel_mode(Channel::Channel3, ChannelMode::Input)
+    ///     .try_into()?;
+    ///
+    /// // 8 samples over 160ms
+    /// let interval = Duration::from_millis(20);
+    /// let reading = maestro.read_analog_averaged(Channel::Channel3, 8usize, interval)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_analog_averaged(
+        &mut self,
+        channel: constants::Channel,
+        samples: usize,
+        interval: Duration,
+    ) -> crate::Result<AnalogReading> {
+        self.check_analog(channel)?;
+        let samples = samples.max(1usize);
+        let mut sum = 0u64;
+        for sample in 0usize..samples {
+            if sample != 0usize {
+                thread::sleep(interval);
+            };
+            sum += u64::from(self.get_position(channel)?.qtr_micros());
+        }
+        let mean = sum as f64 / samples as f64;
+        Ok(AnalogReading::from_mean(mean))
     }
 
     /// Gets any errors encountered by the Maestro
@@ -271,81 +1185,678 @@ impl Maestro {
     /// additional hardware to implement it.
     ///
     /// # Example Usage
-    /// ```ignore
-    /// use raestro::prelude::*;
-    ///
-    /// let mut m = Maestro::new();
-    /// m.start(BaudRates::BR_115200).unwrap();
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
     ///
-    /// let errors = m.get_errors().unwrap();
+    /// let errors = maestro.get_errors()?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn get_errors(&mut self) -> crate::Result<Vec<ErrorValues>> {
         self.write_command(internals::CommandFlags::GetErrors)?;
-        self.read(internals::RESPONSE_SIZE as usize)?;
-        let data = self.prepare_data_from_buffer();
+        self.pending_errors |= self.prepare_data_from_buffer();
+        let data = std::mem::take(&mut self.pending_errors);
         let errors = ErrorValues::from_data(data);
         Ok(errors)
     }
 
+    /// Gets whether any servo is still moving
+    /// towards its target.
+    ///
+    /// # Important
+    /// Much like `get_position`, this only
+    /// reflects the *ramped* `PWM` signal being
+    /// broadcasted (i.e., whether any channel's
+    /// speed or acceleration limit is still
+    /// holding it back from its target). It says
+    /// nothing about the physical servo.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// maestro.set_target(Channel::Channel0, 8000u16)?;
+    /// while maestro.is_moving()? {}
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_moving(&mut self) -> crate::Result<bool> {
+        self.write_command(internals::CommandFlags::GetMovingState)?;
+        let moving = self.read_buf[0usize] != 0u8;
+        Ok(moving)
+    }
+
+    /// Blocks until no servo is moving, polling
+    /// the Maestro with `is_moving`.
+    ///
+    /// Returns [`Error::Timeout`] if the servos
+    /// are still moving once the given timeout
+    /// has elapsed.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// maestro.set_speed(Channel::Channel0, 10u16)?;
+    /// maestro.set_target(Channel::Channel0, 8000u16)?;
+    /// maestro.wait_until_settled(Duration::from_secs(5))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait_until_settled(
+        &mut self,
+        timeout: Duration,
+    ) -> crate::Result<()> {
+        self.poll(timeout, |maestro| {
+            let moving = maestro.is_moving()?;
+            Ok((!moving).then_some(()))
+        })
+    }
+
+    /// Blocks until the given channel's
+    /// position is within `tolerance` (in
+    /// quarter-us) of the given target, polling
+    /// the Maestro with `get_position`.
+    ///
+    /// Returns the last position read, or
+    /// [`Error::Timeout`] if the channel did not
+    /// get close enough once the given timeout
+    /// has elapsed.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// let channel = Channel::Channel0;
+    /// maestro.set_speed(channel, 10u16)?;
+    /// maestro.set_target(channel, 8000u16)?;
+    ///
+    /// // Start the next move once the servo is within 10us of its target.
+    /// let timeout = Duration::from_secs(5);
+    /// maestro.wait_for_channel(channel, 8000u16, 40u16, timeout)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait_for_channel<P, Q>(
+        &mut self,
+        channel: constants::Channel,
+        target: P,
+        tolerance: Q,
+        timeout: Duration,
+    ) -> crate::Result<PulseWidth>
+    where
+        P: Into<PulseWidth>,
+        Q: Into<PulseWidth>,
+    {
+        let target = target.into().qtr_micros();
+        let tolerance = tolerance.into().qtr_micros();
+        self.poll(timeout, |maestro| {
+            let position = maestro.get_position(channel)?;
+            let arrived = position.qtr_micros().abs_diff(target) <= tolerance;
+            Ok(arrived.then_some(position))
+        })
+    }
+
+    /// Streams the given trajectory to the given
+    /// channel, sending one target every
+    /// `interval` until the trajectory ends.
+    ///
+    /// This blocks for the whole duration of the
+    /// trajectory. Targets are sent on a fixed
+    /// schedule from when `follow` is called; if
+    /// sending falls behind, the late targets are
+    /// sent immediately rather than skipped. See
+    /// [`motion`] for the trajectories available.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::motion::SCurve;
+    /// # use raestro::maestro::units::PulseWidth;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// let start = maestro.get_position(Channel::Channel0)?;
+    /// let end = PulseWidth::from_micros(1800f64)?;
+    /// let trajectory = SCurve::new(start, end, 1000f64, 4000f64)?;
+    ///
+    /// maestro.set_speed(Channel::Channel0, 0u16)?;
+    /// maestro.set_acceleration(Channel::Channel0, 0u8)?;
+    /// maestro.follow(Channel::Channel0, &trajectory, Duration::from_millis(20))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn follow<J>(
+        &mut self,
+        channel: constants::Channel,
+        trajectory: &J,
+        interval: Duration,
+    ) -> crate::Result<()>
+    where
+        J: Trajectory,
+    {
+        let start = Instant::now();
+        for (time, target) in trajectory.samples(interval) {
+            let deadline = start + time;
+            if let Some(remaining) =
+                deadline.checked_duration_since(Instant::now())
+            {
+                thread::sleep(remaining);
+            };
+            self.set_target(channel, target)?;
+        }
+        Ok(())
+    }
+
+    /// Moves several channels to their targets
+    /// so that they all arrive at the same time,
+    /// `duration` from now.
+    ///
+    /// The current position of every channel is
+    /// read with `get_position`, and each channel
+    /// is given a speed and acceleration limit in
+    /// proportion to how far it has to move: each
+    /// one accelerates for the first quarter of
+    /// the move and decelerates for the last. If
+    /// any of those accelerations are too fast for
+    /// the Maestro to express, the moves are made
+    /// at constant speed (without an acceleration
+    /// limit) instead. The targets are then all
+    /// sent in a single write.
+    ///
+    /// Since the limits are rounded to whole
+    /// units, the channels only arrive together
+    /// to within a few of the Maestro's updates.
+    ///
+    /// The speed and acceleration limits are left
+    /// set on the channels afterwards. Channels
+    /// which are off, or which are being turned
+    /// off, are sent their targets without any
+    /// limits being changed. Every channel and
+    /// target is validated as in `set_target`
+    /// before anything is sent, and each channel
+    /// may only be given once.
+    ///
+    /// # Example Usage
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use raestro::maestro::builder::Builder;
+    /// # use raestro::maestro::constants::Baudrate;
+    /// # use raestro::maestro::constants::Channel;
+    /// # use raestro::maestro::Maestro;
+    /// # fn main() -> raestro::Result<()> {
+    /// let mut maestro: Maestro = Builder::default()
+    ///     .baudrate(Baudrate::Baudrate11520)
+    ///     .block_duration(Duration::from_millis(100))
+    ///     .try_into()?;
+    ///
+    /// let moves = [
+    ///     (Channel::Channel0, 8000u16),
+    ///     (Channel::Channel1, 5000u16),
+    ///     (Channel::Channel2, 4500u16),
+    /// ];
+    /// maestro.move_synchronized(&moves, Duration::from_millis(800))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn move_synchronized<P>(
+        &mut self,
+        moves: &[(constants::Channel, P)],
+        duration: Duration,
+    ) -> crate::Result<()>
+    where
+        P: Into<PulseWidth> + Copy,
+    {
+        if duration.is_zero() {
+            return Err(Error::InvalidTrajectory);
+        };
+        let channel_count = self.model.channel_count();
+        if moves.len() > usize::from(channel_count) {
+            return Err(Error::TooManyChannels {
+                count: moves.len(),
+                channel_count,
+            });
+        };
+        if let Some(index) = (1usize..moves.len()).find(|&index| {
+            moves[..index]
+                .iter()
+                .any(|&(other, _)| other == moves[index].0)
+        }) {
+            return Err(Error::DuplicateChannel(moves[index].0 as u8));
+        };
+        let moves = moves
+            .iter()
+            .map(|&(channel, target)| (channel, target.into().qtr_micros()))
+            .collect::<Vec<_>>();
+        moves.iter().try_for_each(|&(channel, target)| {
+            let channel_number = self.check_channel(channel)?;
+            self.check_target(channel_number, target)?;
+            self.check_mode(channel, ChannelMode::Servo)
+        })?;
+        if moves.is_empty() {
+            return Ok(());
+        };
+        let distances = moves
+            .iter()
+            .map(|&(channel, target)| {
+                let position = self.get_position(channel)?.qtr_micros();
+                let distance = match position == internals::OFF
+                    || target == internals::OFF
+                {
+                    true => 0u16,
+                    false => position.abs_diff(target),
+                };
+                Ok(PulseWidth::from(distance).micros())
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        let limits = self
+            .plan_synchronized(
+                &moves,
+                &distances,
+                duration,
+                internals::SYNC_RAMP_FRACTION,
+            )
+            .or_else(|_| {
+                self.plan_synchronized(&moves, &distances, duration, 0f64)
+            })?;
+        moves
+            .iter()
+            .zip(limits)
+            .try_for_each(|(&(channel, _), limits)| match limits {
+                Some((speed, acceleration)) => {
+                    self.set_speed(channel, speed)?;
+                    self.set_acceleration(channel, acceleration)
+                },
+                None => Ok(()),
+            })?;
+        let targets = moves
+            .iter()
+            .map(|&(channel, target)| (channel as u8, target));
+        self.write_individual_targets(targets)
+    }
+
     /// ### Purpose:
-    /// Reads the given number of bytes into
-    /// `self.read_buf`.
+    /// Computes the speed and acceleration
+    /// limits which move each channel its given
+    /// distance (in us) in the given duration,
+    /// spending `ramp_fraction` of the duration
+    /// on each of accelerating and decelerating.
     ///
     /// ### Notes:
-    /// Please note that the `self.uart.read`
-    /// method is being utilized to send the
-    /// commands over `UART`. This command
-    /// operates on a blocking read. Blocking
-    /// duration is default set to
-    /// `DEFAULT_BLOCKING_DURATION`.
-    fn read(&mut self, length: usize) -> crate::Result<()> {
-        let Self { uart, read_buf, .. } = self;
-        let mut slice = &mut read_buf[0..length];
-        let bytes_read = uart.read(&mut slice)?;
-        let comparison = bytes_read.cmp(&length);
-        match comparison {
-            Ordering::Equal => Ok(()),
-            _ => Err(Error::FaultyRead {
-                actual_count: bytes_read,
-            }),
+    /// Channels which do not move get no limits.
+    /// Returns an error if any limit is too fast
+    /// to express.
+    fn plan_synchronized(
+        &self,
+        moves: &[(constants::Channel, u16)],
+        distances: &[f64],
+        duration: Duration,
+        ramp_fraction: f64,
+    ) -> crate::Result<Vec<Option<(ServoSpeed, ServoAccel)>>> {
+        let total = duration.as_secs_f64();
+        let ramp = total * ramp_fraction;
+        let to_accel = ServoAccel::saturating_from_micros_per_second_squared;
+        moves
+            .iter()
+            .zip(distances)
+            .map(|(&(channel, _), &distance)| {
+                if distance == 0f64 {
+                    return Ok(None);
+                };
+                let interval = self.update_interval(channel);
+                let (speed, acceleration) = match ramp > 0f64 {
+                    true => {
+                        let acceleration = distance / (ramp * (total - ramp));
+                        let converted = to_accel(acceleration, interval)?;
+                        if converted.saturated {
+                            return Err(Error::UnitOutOfRange(acceleration));
+                        };
+                        // Re-plan around the acceleration that will actually
+                        // be used, which has been rounded to a whole unit.
+                        let actual = converted
+                            .value
+                            .micros_per_second_squared_at(interval)
+                            .unwrap_or(acceleration);
+                        let discriminant = actual * actual * total * total
+                            - 4f64 * actual * distance;
+                        if discriminant < 0f64 {
+                            return Err(Error::UnitOutOfRange(acceleration));
+                        };
+                        let speed =
+                            (actual * total - discriminant.sqrt()) / 2f64;
+                        (speed, converted.value)
+                    },
+                    false => (distance / total, ServoAccel::UNLIMITED),
+                };
+                let converted = ServoSpeed::saturating_from_micros_per_second(
+                    speed, interval,
+                )?;
+                if converted.saturated {
+                    return Err(Error::UnitOutOfRange(speed));
+                };
+                Ok(Some((converted.value, acceleration)))
+            })
+            .collect()
+    }
+
+    /// ### Purpose:
+    /// Checks that the given channel exists on
+    /// this Maestro's model, returning its raw
+    /// channel number.
+    fn check_channel(&self, channel: constants::Channel) -> crate::Result<u8> {
+        let channel_number = channel as u8;
+        self.model
+            .has_channel(channel)
+            .then_some(channel_number)
+            .ok_or(Error::InvalidChannel(channel_number))
+    }
+
+    /// ### Purpose:
+    /// Checks that the given target is either
+    /// `0` or within the given channel's
+    /// limits.
+    fn check_target(&self, channel: u8, target: u16) -> crate::Result<()> {
+        let limits = &self.target_limits[usize::from(channel)];
+        (target == internals::OFF || limits.contains(&target))
+            .then_some(())
+            .ok_or(Error::InvalidValue(target))
+    }
+
+    /// ### Purpose:
+    /// The time between updates of the given
+    /// channel's speed and acceleration limits.
+    fn update_interval(&self, channel: constants::Channel) -> Duration {
+        self.update_rate(channel).interval(self.servo_period)
+    }
+
+    /// ### Purpose:
+    /// Checks that the given channel has been
+    /// configured in the given mode.
+    fn check_mode(
+        &self,
+        channel: constants::Channel,
+        mode: ChannelMode,
+    ) -> crate::Result<()> {
+        (self.channel_mode(channel) == mode)
+            .then_some(())
+            .ok_or(Error::InvalidChannelMode(channel as u8))
+    }
+
+    /// ### Purpose:
+    /// Checks that the given channel has been
+    /// configured as an input, and that it can
+    /// read analog voltages.
+    fn check_analog(&self, channel: constants::Channel) -> crate::Result<()> {
+        self.check_mode(channel, ChannelMode::Input)?;
+        ((channel as u8) < analog::ANALOG_CHANNEL_COUNT)
+            .then_some(())
+            .ok_or(Error::InvalidChannelMode(channel as u8))
+    }
+
+    /// ### Purpose:
+    /// Repeatedly calls `check` until it returns
+    /// a value or the given timeout elapses.
+    ///
+    /// ### Notes:
+    /// `check` is always called at least once,
+    /// and is called at most once every
+    /// [`internals::POLL_INTERVAL`].
+    fn poll<F, R>(
+        &mut self,
+        timeout: Duration,
+        mut check: F,
+    ) -> crate::Result<R>
+    where
+        F: FnMut(&mut Self) -> crate::Result<Option<R>>,
+    {
+        let start = Instant::now();
+        loop {
+            if let Some(result) = check(self)? {
+                return Ok(result);
+            };
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::Timeout(timeout));
+            };
+            thread::sleep(internals::POLL_INTERVAL.min(timeout - elapsed));
+        }
+    }
+
+    /// ### Purpose:
+    /// Reads the Maestro's error register into
+    /// `self.pending_errors`.
+    ///
+    /// ### Notes:
+    /// The Maestro clears its error register
+    /// whenever it is read. Errors are therefore
+    /// accumulated here until they are handed
+    /// out by [`Maestro::get_errors`], so that
+    /// reads made internally (i.e., when
+    /// verifying CRCs) do not lose them.
+    ///
+    /// The request bypasses `self.send`, so
+    /// that reading the error register never
+    /// tries to verify itself.
+    fn read_errors(&mut self) -> crate::Result<()> {
+        let command_flag = internals::CommandFlags::GetErrors;
+        let index = self.write_header(0usize, command_flag);
+        let length = self.append_crc(0usize, index);
+        self.transact(length, command_flag.response_size())?;
+        self.pending_errors |= self.prepare_data_from_buffer();
+        Ok(())
+    }
+
+    /// ### Purpose:
+    /// Checks whether the Maestro rejected the
+    /// last command packet because of a CRC
+    /// mismatch.
+    ///
+    /// ### Notes:
+    /// If so, the CRC error is consumed and
+    /// reported as [`Error::CrcMismatch`]. Any
+    /// other errors are left pending for
+    /// [`Maestro::get_errors`].
+    fn verify_crc(&mut self) -> crate::Result<()> {
+        const CRC_ERROR: u16 = 1u16 << (ErrorValues::SerCrcError as u16);
+        self.read_errors()?;
+        match self.pending_errors & CRC_ERROR {
+            0u16 => Ok(()),
+            _ => {
+                self.pending_errors &= !CRC_ERROR;
+                Err(Error::CrcMismatch)
+            },
         }
     }
 
     /// ### Purpose:
     /// Writes the given number of bytes over to
-    /// the Maestro.
+    /// the Maestro, and then reads the given
+    /// number of response bytes into
+    /// `self.read_buf`.
     ///
     /// ### Notes:
     /// The bytes that are being written are
     /// located in the `self.write_buf` array.
     /// This is the method that actually calls
-    /// `self.uart.write`. Other methods in this
-    /// `impl` block just write to
+    /// `self.transport.transact`. Other methods
+    /// in this `impl` block just write to
     /// `self.write_buf`, but do not actually send
-    /// data over the `UART` pins.
-    fn write(&mut self, length: usize) -> crate::Result<()> {
+    /// data over the transport.
+    ///
+    /// Reads block for at most the duration
+    /// configured through
+    /// [`builder::Builder::block_duration`].
+    fn transact(
+        &mut self,
+        length: usize,
+        response_size: usize,
+    ) -> crate::Result<()> {
         let Self {
-            uart, write_buf, ..
+            transport,
+            read_buf,
+            write_buf,
+            ..
         } = self;
-        let mut slice = &mut write_buf[0..length];
-        let bytes_written = uart.write(&mut slice)?;
-        let comparison = bytes_written.cmp(&length);
+        let request = &write_buf[0..length];
+        let response = &mut read_buf[0..response_size];
+        let bytes_read = transport.transact(request, response)?;
+        let comparison = bytes_read.cmp(&response_size);
         match comparison {
             Ordering::Equal => Ok(()),
-            _ => Err(Error::FaultyWrite {
-                actual_count: bytes_written,
-                expected_count: length,
+            _ => Err(Error::FaultyRead {
+                actual_count: bytes_read,
+                expected_count: response_size,
             }),
         }
     }
 
+    /// ### Purpose:
+    /// Sends the command packet of the given
+    /// length which has been written into
+    /// `self.write_buf`, and reads back its
+    /// response (if it has any).
+    ///
+    /// ### Notes:
+    /// In CRC mode, the CRC byte is appended to
+    /// the packet before sending it. Commands
+    /// which have no response are then
+    /// verified by reading back the Maestro's
+    /// error register, since the Maestro
+    /// silently discards packets which fail its
+    /// CRC check. Commands with a response are
+    /// verified by the response arriving in
+    /// full; if it comes up short, the error
+    /// register is checked as well, so that a
+    /// rejected packet is reported as
+    /// [`Error::CrcMismatch`] rather than as
+    /// [`Error::FaultyRead`].
+    fn send(
+        &mut self,
+        command_flag: internals::CommandFlags,
+        length: usize,
+    ) -> crate::Result<()> {
+        let response_size = command_flag.response_size();
+        let length = self.append_crc(0usize, length);
+        let result = self.transact(length, response_size);
+        match (self.crc, response_size, result) {
+            (true, 0usize, Ok(())) => self.verify_crc(),
+            (true, _, Err(error @ Error::FaultyRead { .. })) => {
+                match self.verify_crc() {
+                    Err(Error::CrcMismatch) => Err(Error::CrcMismatch),
+                    _ => Err(error),
+                }
+            },
+            (_, _, result) => result,
+        }
+    }
+
+    /// ### Purpose:
+    /// Appends the CRC byte of the packet
+    /// spanning `start..end` in `self.write_buf`
+    /// (if CRC mode is enabled).
+    ///
+    /// ### Notes:
+    /// Returns the index just past the end of
+    /// the packet.
+    fn append_crc(&mut self, start: usize, end: usize) -> usize {
+        match self.crc {
+            true => {
+                self.write_buf[end] = crc7(&self.write_buf[start..end]);
+                end + 1usize
+            },
+            false => end,
+        }
+    }
+
+    /// ### Purpose:
+    /// Writes the protocol header and the
+    /// command byte into `self.write_buf`,
+    /// starting at the given index.
+    ///
+    /// ### Notes:
+    /// Returns the index at which the command's
+    /// data bytes should be written.
+    ///
+    /// In the Pololu protocol, the command byte
+    /// is preceded by the sync byte and the
+    /// device number, and has its top bit
+    /// cleared. In the compact protocol, the
+    /// command byte is sent as-is.
+    fn write_header(
+        &mut self,
+        start: usize,
+        command_flag: internals::CommandFlags,
+    ) -> usize {
+        let Self {
+            write_buf,
+            protocol,
+            device_number,
+            ..
+        } = self;
+        match protocol {
+            Protocol::Pololu => {
+                write_buf[start] = internals::SYNC;
+                write_buf[start + 1usize] = *device_number;
+                write_buf[start + 2usize] = mask_byte(command_flag as u8);
+                start + 3usize
+            },
+            Protocol::Compact => {
+                write_buf[start] = command_flag as u8;
+                start + 1usize
+            },
+        }
+    }
+
     /// ### Purpose:
     /// Writes the given arguments into the
     /// appropriate place in `self.write_buf`.
     ///
     /// ### Notes:
     /// This method does not actually send the
-    /// bytes over the `UART` pins. It just
+    /// bytes over the transport. It just
     /// writes them into the correct place in the
     /// buffer and then calls `self.write`
     /// while passing in the desired length.
@@ -355,14 +1866,33 @@ impl Maestro {
         channel: constants::Channel,
         microsec: u16,
     ) -> crate::Result<()> {
+        let channel = self.check_channel(channel)?;
+        self.write_byte_and_payload(command_flag, channel, microsec)
+    }
+
+    /// ### Purpose:
+    /// Writes the given arguments into the
+    /// appropriate place in `self.write_buf`.
+    ///
+    /// ### Notes:
+    /// Identical to
+    /// [`Maestro::write_channel_and_payload`],
+    /// except that the first data byte need not
+    /// be a channel (i.e., it can be a
+    /// subroutine number).
+    fn write_byte_and_payload(
+        &mut self,
+        command_flag: internals::CommandFlags,
+        byte: u8,
+        payload: u16,
+    ) -> crate::Result<()> {
+        let index = self.write_header(0usize, command_flag);
+        let (lower, upper) = microsec_to_target(payload);
         let Self { write_buf, .. } = self;
-        let command = mask_byte(command_flag as u8);
-        let (lower, upper) = microsec_to_target(microsec);
-        write_buf[2usize] = command;
-        write_buf[3usize] = channel as u8;
-        write_buf[4usize] = lower;
-        write_buf[5usize] = upper;
-        self.write(internals::WRITE_CHANNEL_AND_PAYLOAD_SIZE)
+        write_buf[index] = byte;
+        write_buf[index + 1usize] = lower;
+        write_buf[index + 2usize] = upper;
+        self.send(command_flag, index + 3usize)
     }
 
     /// ### Purpose:
@@ -371,7 +1901,7 @@ impl Maestro {
     ///
     /// ### Notes:
     /// This method does not actually send the
-    /// bytes over the `UART` pins. It just
+    /// bytes over the transport. It just
     /// writes them into the correct place in the
     /// buffer and then calls `self.write`
     /// while passing in the desired length.
@@ -381,11 +1911,26 @@ impl Maestro {
         command_flag: internals::CommandFlags,
         channel: constants::Channel,
     ) -> crate::Result<()> {
-        let Self { write_buf, .. } = self;
-        let command = mask_byte(command_flag as u8);
-        write_buf[2usize] = command;
-        write_buf[3usize] = channel as u8;
-        self.write(internals::WRITE_CHANNEL_SIZE)
+        let channel = self.check_channel(channel)?;
+        self.write_byte(command_flag, channel)
+    }
+
+    /// ### Purpose:
+    /// Writes the given arguments into the
+    /// appropriate place in `self.write_buf`.
+    ///
+    /// ### Notes:
+    /// Identical to [`Maestro::write_channel`],
+    /// except that the data byte need not be a
+    /// channel.
+    fn write_byte(
+        &mut self,
+        command_flag: internals::CommandFlags,
+        byte: u8,
+    ) -> crate::Result<()> {
+        let index = self.write_header(0usize, command_flag);
+        self.write_buf[index] = byte;
+        self.send(command_flag, index + 1usize)
     }
 
     /// ### Purpose:
@@ -394,7 +1939,7 @@ impl Maestro {
     ///
     /// ### Notes:
     /// This method does not actually send the
-    /// bytes over the `UART` pins. It just
+    /// bytes over the transport. It just
     /// writes them into the correct place in the
     /// buffer and then calls `self.write`
     /// while passing in the desired length.
@@ -402,10 +1947,69 @@ impl Maestro {
         &mut self,
         command_flag: internals::CommandFlags,
     ) -> crate::Result<()> {
+        let index = self.write_header(0usize, command_flag);
+        self.send(command_flag, index)
+    }
+
+    /// ### Purpose:
+    /// Sends the given targets as a single Set
+    /// Multiple Targets command.
+    ///
+    /// ### Notes:
+    /// The targets are assumed to have already
+    /// been validated.
+    fn write_multiple_targets(
+        &mut self,
+        first_channel: u8,
+        targets: &[u16],
+    ) -> crate::Result<()> {
+        let command_flag = internals::CommandFlags::SetMultipleTargets;
+        let index = self.write_header(0usize, command_flag);
         let Self { write_buf, .. } = self;
-        let command = mask_byte(command_flag as u8);
-        write_buf[2usize] = command;
-        self.write(internals::WRITE_COMMAND_SIZE)
+        write_buf[index] = targets.len() as u8;
+        write_buf[index + 1usize] = first_channel;
+        let index = targets.iter().fold(index + 2usize, |index, &target| {
+            let (lower, upper) = microsec_to_target(target);
+            write_buf[index] = lower;
+            write_buf[index + 1usize] = upper;
+            index + 2usize
+        });
+        self.send(command_flag, index)
+    }
+
+    /// ### Purpose:
+    /// Sends the given channel-target pairs as
+    /// one Set Target packet per channel, all in
+    /// a single write.
+    ///
+    /// ### Notes:
+    /// The targets are assumed to have already
+    /// been validated. In CRC mode, every packet
+    /// gets its own CRC byte, and the Maestro's
+    /// error register is only read back once
+    /// the whole batch has been sent.
+    fn write_individual_targets<I>(&mut self, targets: I) -> crate::Result<()>
+    where
+        I: IntoIterator<Item = (u8, u16)>,
+    {
+        let command_flag = internals::CommandFlags::SetTarget;
+        let length =
+            targets
+                .into_iter()
+                .fold(0usize, |start, (channel, target)| {
+                    let index = self.write_header(start, command_flag);
+                    let (lower, upper) = microsec_to_target(target);
+                    let Self { write_buf, .. } = self;
+                    write_buf[index] = channel;
+                    write_buf[index + 1usize] = lower;
+                    write_buf[index + 2usize] = upper;
+                    self.append_crc(start, index + 3usize)
+                });
+        self.transact(length, 0usize)?;
+        match self.crc {
+            true => self.verify_crc(),
+            false => Ok(()),
+        }
     }
 
     /// ### Purpose:
@@ -418,3 +2022,21 @@ impl Maestro {
         top | bottom
     }
 }
+
+/// ### Purpose:
+/// Checks that the given subroutine number fits
+/// in a single data byte.
+fn check_subroutine(subroutine: u8) -> crate::Result<()> {
+    (subroutine <= constants::MAX_SUBROUTINE)
+        .then_some(())
+        .ok_or(Error::InvalidSubroutine(subroutine))
+}
+
+/// ### Purpose:
+/// Checks that the given raw speed fits in a
+/// command packet.
+fn check_speed(speed: u16) -> crate::Result<()> {
+    (speed <= units::MAX_SPEED)
+        .then_some(())
+        .ok_or(Error::UnitOutOfRange(f64::from(speed)))
+}
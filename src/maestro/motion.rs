@@ -0,0 +1,405 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Host-side motion profiles, streamed to the Maestro as a series of
+//! targets.
+//!
+//! The Maestro itself can only ramp a channel with a single speed and
+//! acceleration limit. A [`Trajectory`] instead describes a channel's
+//! position over time, and is sampled at a fixed rate and sent to the Maestro
+//! one target at a time (see [`crate::maestro::Maestro::follow`]).
+//!
+//! Three trajectories are provided:
+//! - [`Trapezoidal`]: constant acceleration up to a cruising speed, and back
+//!   down again.
+//! - [`SCurve`]: the same, but with acceleration eased in and out, so that
+//!   the acceleration itself never jumps.
+//! - [`Spline`]: a natural cubic spline through timed waypoints.
+//!
+//! All positions are in us, speeds in us per second and accelerations in us
+//! per second squared.
+//!
+//! ### Examples:
+//! ```rust
+//! # use std::time::Duration;
+//! # use raestro::maestro::motion::Trajectory;
+//! # use raestro::maestro::motion::Trapezoidal;
+//! # use raestro::maestro::units::PulseWidth;
+//! # fn main() -> raestro::Result<()> {
+//! let trajectory = Trapezoidal::new(
+//!     PulseWidth::from_micros(1000f64)?,
+//!     PulseWidth::from_micros(2000f64)?,
+//!     2000f64,
+//!     8000f64,
+//! )?;
+//! assert_eq!(trajectory.duration(), Duration::from_millis(750));
+//!
+//! for (time, target) in trajectory.samples(Duration::from_millis(20)) {
+//!     // send `target` at `time`
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ### Notes:
+//! The channel's own speed and acceleration limits still apply to every
+//! target that is streamed, and will smooth out (and lag behind) the
+//! trajectory unless they are set to unlimited.
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::maestro::constants::MAX_TARGET;
+use crate::maestro::units::PulseWidth;
+
+/// ### Purpose:
+/// A channel's position over time.
+pub trait Trajectory {
+    /// ### Purpose:
+    /// How long the trajectory takes to complete.
+    fn duration(&self) -> Duration;
+
+    /// ### Purpose:
+    /// The position (in us) at the given time since the trajectory started.
+    ///
+    /// ### Notes:
+    /// Times past the end of the trajectory return its final position.
+    fn position(&self, time: Duration) -> f64;
+
+    /// ### Purpose:
+    /// The position at the given time, rounded to the nearest target the
+    /// Maestro can be sent.
+    fn sample(&self, time: Duration) -> PulseWidth {
+        let qtr_micros = (self.position(time) * 4f64)
+            .round()
+            .clamp(0f64, f64::from(MAX_TARGET));
+        PulseWidth::from(qtr_micros as u16)
+    }
+
+    /// ### Purpose:
+    /// Samples the trajectory every `interval`, from its start up to and
+    /// including its end.
+    ///
+    /// ### Notes:
+    /// A zero interval only yields the final sample.
+    fn samples(&self, interval: Duration) -> Samples<'_, Self>
+    where
+        Self: Sized,
+    {
+        Samples {
+            trajectory: self,
+            interval,
+            next: Some(Duration::ZERO),
+        }
+    }
+}
+
+/// ### Purpose:
+/// An iterator over a trajectory's samples, as `(time, target)` pairs.
+#[derive(Debug)]
+pub struct Samples<'a, J> {
+    trajectory: &'a J,
+    interval: Duration,
+    next: Option<Duration>,
+}
+
+impl<J> Iterator for Samples<'_, J>
+where
+    J: Trajectory,
+{
+    type Item = (Duration, PulseWidth);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let duration = self.trajectory.duration();
+        let time = match self.interval.is_zero() {
+            true => self.next.take().map(|_| duration),
+            false => self.next.take().map(|time| time.min(duration)),
+        }?;
+        self.next = (time < duration).then(|| time + self.interval);
+        Some((time, self.trajectory.sample(time)))
+    }
+}
+
+/// ### Purpose:
+/// How a [`Profile`] accelerates up to its cruising speed.
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Ramp {
+    /// Constant acceleration.
+    Linear,
+
+    /// Raised-cosine acceleration, which eases in and out.
+    Cosine,
+}
+
+impl Ramp {
+    /// ### Purpose:
+    /// How much longer this ramp takes than a linear ramp with the same peak
+    /// acceleration.
+    fn stretch(self) -> f64 {
+        match self {
+            Self::Linear => 1f64,
+            Self::Cosine => PI / 2f64,
+        }
+    }
+}
+
+/// ### Purpose:
+/// A point-to-point move which ramps up to a cruising speed, cruises, and
+/// ramps back down again.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Profile {
+    ramp: Ramp,
+    start: f64,
+    distance: f64,
+    speed: f64,
+    ramp_time: f64,
+    cruise_time: f64,
+}
+
+impl Profile {
+    /// ### Purpose:
+    /// Plans a move which respects the given speed and acceleration limits,
+    /// falling short of the speed limit on moves too short to reach it.
+    fn new(
+        ramp: Ramp,
+        start: PulseWidth,
+        end: PulseWidth,
+        max_speed: f64,
+        max_acceleration: f64,
+    ) -> crate::Result<Self> {
+        let limits = [max_speed, max_acceleration];
+        if !limits
+            .iter()
+            .all(|limit| limit.is_finite() && *limit > 0f64)
+        {
+            return Err(Error::InvalidTrajectory);
+        };
+        let start = start.micros();
+        let distance = end.micros() - start;
+        let speed = (distance.abs() * max_acceleration / ramp.stretch())
+            .sqrt()
+            .min(max_speed);
+        let ramp_time = ramp.stretch() * speed / max_acceleration;
+        let cruise_time = match speed > 0f64 {
+            true => (distance.abs() - speed * ramp_time) / speed,
+            false => 0f64,
+        };
+        Ok(Self {
+            ramp,
+            start,
+            distance,
+            speed,
+            ramp_time,
+            cruise_time: cruise_time.max(0f64),
+        })
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs_f64(2f64 * self.ramp_time + self.cruise_time)
+    }
+
+    fn position(&self, time: Duration) -> f64 {
+        let time = time.as_secs_f64();
+        let total = 2f64 * self.ramp_time + self.cruise_time;
+        let travelled = if time >= total {
+            self.distance.abs()
+        } else if time < self.ramp_time {
+            self.ramped(time)
+        } else if time < self.ramp_time + self.cruise_time {
+            self.ramped(self.ramp_time) + self.speed * (time - self.ramp_time)
+        } else {
+            self.distance.abs() - self.ramped(total - time)
+        };
+        self.start + travelled.copysign(self.distance)
+    }
+
+    /// ### Purpose:
+    /// The distance covered the given time into the ramp up.
+    fn ramped(&self, time: f64) -> f64 {
+        match self.ramp {
+            Ramp::Linear => self.speed * time * time / (2f64 * self.ramp_time),
+            Ramp::Cosine => {
+                let phase = PI * time / self.ramp_time;
+                self.speed / 2f64 * (time - self.ramp_time / PI * phase.sin())
+            },
+        }
+    }
+}
+
+/// ### Purpose:
+/// A move with a trapezoidal speed profile.
+///
+/// ### Notes:
+/// Moves which are too short to reach `max_speed` accelerate for half of the
+/// move and decelerate for the other half (i.e., a triangular profile).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Trapezoidal(Profile);
+
+impl Trapezoidal {
+    /// ### Purpose:
+    /// Plans a move from `start` to `end` with the given speed (us/s) and
+    /// acceleration (us/s^2) limits.
+    pub fn new(
+        start: PulseWidth,
+        end: PulseWidth,
+        max_speed: f64,
+        max_acceleration: f64,
+    ) -> crate::Result<Self> {
+        Profile::new(Ramp::Linear, start, end, max_speed, max_acceleration)
+            .map(Self)
+    }
+}
+
+impl Trajectory for Trapezoidal {
+    fn duration(&self) -> Duration {
+        self.0.duration()
+    }
+
+    fn position(&self, time: Duration) -> f64 {
+        self.0.position(time)
+    }
+}
+
+/// ### Purpose:
+/// A move with an S-shaped speed profile.
+///
+/// ### Notes:
+/// The acceleration follows a raised cosine, peaking at `max_acceleration`
+/// half-way through each ramp. This takes `pi / 2` times longer to reach the
+/// cruising speed than [`Trapezoidal`], but never jerks the servo.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SCurve(Profile);
+
+impl SCurve {
+    /// ### Purpose:
+    /// Plans a move from `start` to `end` with the given speed (us/s) and
+    /// peak acceleration (us/s^2) limits.
+    pub fn new(
+        start: PulseWidth,
+        end: PulseWidth,
+        max_speed: f64,
+        max_acceleration: f64,
+    ) -> crate::Result<Self> {
+        Profile::new(Ramp::Cosine, start, end, max_speed, max_acceleration)
+            .map(Self)
+    }
+}
+
+impl Trajectory for SCurve {
+    fn duration(&self) -> Duration {
+        self.0.duration()
+    }
+
+    fn position(&self, time: Duration) -> f64 {
+        self.0.position(time)
+    }
+}
+
+/// ### Purpose:
+/// A natural cubic spline through a series of timed waypoints.
+///
+/// ### Notes:
+/// The trajectory holds the first waypoint until its time, and ends at the
+/// last waypoint. A spline can overshoot its waypoints between them; samples
+/// are clamped to the targets the Maestro can be sent, but not to the
+/// channel's limits.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Spline {
+    times: Vec<f64>,
+    positions: Vec<f64>,
+    curvatures: Vec<f64>,
+}
+
+impl Spline {
+    /// ### Purpose:
+    /// Fits a spline through the given `(time, position)` waypoints.
+    ///
+    /// ### Notes:
+    /// At least two waypoints are needed, and their times must be strictly
+    /// increasing.
+    pub fn new(waypoints: &[(Duration, PulseWidth)]) -> crate::Result<Self> {
+        let increasing = waypoints
+            .windows(2usize)
+            .all(|pair| pair[0usize].0 < pair[1usize].0);
+        if waypoints.len() < 2usize || !increasing {
+            return Err(Error::InvalidTrajectory);
+        };
+        let times = waypoints
+            .iter()
+            .map(|(time, _)| time.as_secs_f64())
+            .collect::<Vec<_>>();
+        let positions = waypoints
+            .iter()
+            .map(|(_, position)| position.micros())
+            .collect::<Vec<_>>();
+        let curvatures = natural_curvatures(&times, &positions);
+        Ok(Self {
+            times,
+            positions,
+            curvatures,
+        })
+    }
+}
+
+impl Trajectory for Spline {
+    fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.times[self.times.len() - 1usize])
+    }
+
+    fn position(&self, time: Duration) -> f64 {
+        let time = time.as_secs_f64();
+        let last = self.times.len() - 1usize;
+        if time <= self.times[0usize] {
+            return self.positions[0usize];
+        };
+        if time >= self.times[last] {
+            return self.positions[last];
+        };
+        let i = self.times.partition_point(|&t| t <= time) - 1usize;
+        let (t0, t1) = (self.times[i], self.times[i + 1usize]);
+        let (y0, y1) = (self.positions[i], self.positions[i + 1usize]);
+        let (m0, m1) = (self.curvatures[i], self.curvatures[i + 1usize]);
+        let h = t1 - t0;
+        let (a, b) = (t1 - time, time - t0);
+        (m0 * a.powi(3) + m1 * b.powi(3)) / (6f64 * h)
+            + (y0 / h - m0 * h / 6f64) * a
+            + (y1 / h - m1 * h / 6f64) * b
+    }
+}
+
+/// ### Purpose:
+/// Solves for the second derivative of a natural cubic spline at each of its
+/// knots (which is `0` at both ends).
+fn natural_curvatures(times: &[f64], positions: &[f64]) -> Vec<f64> {
+    let n = times.len();
+    let mut curvatures = vec![0f64; n];
+    if n < 3usize {
+        return curvatures;
+    };
+    let h = times.windows(2usize).map(|w| w[1usize] - w[0usize]);
+    let h = h.collect::<Vec<_>>();
+    let slope = |i: usize| (positions[i + 1usize] - positions[i]) / h[i];
+
+    // Forward sweep of the Thomas algorithm over the interior knots.
+    let mut diagonal = vec![0f64; n];
+    let mut rhs = vec![0f64; n];
+    for i in 1usize..n - 1usize {
+        diagonal[i] = 2f64 * (h[i - 1usize] + h[i]);
+        rhs[i] = 6f64 * (slope(i) - slope(i - 1usize));
+        if i > 1usize {
+            let factor = h[i - 1usize] / diagonal[i - 1usize];
+            diagonal[i] -= factor * h[i - 1usize];
+            rhs[i] -= factor * rhs[i - 1usize];
+        };
+    }
+    for i in (1usize..n - 1usize).rev() {
+        let next = curvatures[i + 1usize];
+        curvatures[i] = (rhs[i] - h[i] * next) / diagonal[i];
+    }
+    curvatures
+}
@@ -0,0 +1,100 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Settings for the Mini Maestro's general-purpose PWM output.
+//!
+//! The PWM signal is output on channel `8` of the Mini Maestro 12, and on
+//! channel `12` of the Mini Maestro 18 and 24. That channel must be
+//! configured as an output. Both the on-time and the period are in units of
+//! `1/48us` (i.e., ticks of the Maestro's 48MHz clock).
+//!
+//! ### Examples:
+//! ```rust
+//! # use raestro::maestro::pwm::Pwm;
+//! # fn main() -> raestro::Result<()> {
+//! // 10kHz at 25% duty cycle.
+//! let pwm = Pwm::from_frequency(10_000f64, 0.25f64)?;
+//! assert_eq!(pwm.period, 4800u16);
+//! assert_eq!(pwm.on_time, 1200u16);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::errors::Error;
+
+/// ### Purpose:
+/// The number of PWM ticks per second.
+pub const PWM_CLOCK_HZ: f64 = 48_000_000f64;
+
+/// ### Purpose:
+/// The largest on-time or period (in `1/48us`) the Set PWM command can carry.
+pub const MAX_PWM_VALUE: u16 = 0x3fffu16;
+
+/// ### Purpose:
+/// A PWM signal, in `1/48us` units.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Pwm {
+    /// ### Purpose:
+    /// How long the signal is high for in each period. An on-time of `0`
+    /// turns the output off.
+    pub on_time: u16,
+
+    /// ### Purpose:
+    /// The length of each period.
+    pub period: u16,
+}
+
+impl Pwm {
+    /// ### Purpose:
+    /// Creates a PWM signal from a raw on-time and period.
+    ///
+    /// ### Notes:
+    /// Both must fit in 14 bits, and the on-time cannot be longer than the
+    /// period.
+    pub fn new(on_time: u16, period: u16) -> crate::Result<Self> {
+        (period <= MAX_PWM_VALUE && on_time <= period)
+            .then_some(Self { on_time, period })
+            .ok_or(Error::InvalidPwm { on_time, period })
+    }
+
+    /// ### Purpose:
+    /// Creates a PWM signal with the given frequency (in Hz) and duty cycle
+    /// (from `0.0` to `1.0`).
+    ///
+    /// ### Notes:
+    /// Both are rounded to the nearest tick. Frequencies below roughly
+    /// `2930Hz` do not fit in the 14-bit period and return an error.
+    pub fn from_frequency(
+        frequency: f64,
+        duty_cycle: f64,
+    ) -> crate::Result<Self> {
+        let period = (PWM_CLOCK_HZ / frequency).round();
+        let on_time = (period * duty_cycle).round();
+        let valid = period.is_finite()
+            && (1f64..=f64::from(MAX_PWM_VALUE)).contains(&period)
+            && (0f64..=1f64).contains(&duty_cycle);
+        match valid {
+            true => Self::new(on_time as u16, period as u16),
+            false => Err(Error::InvalidPwm {
+                on_time: on_time.clamp(0f64, f64::from(u16::MAX)) as u16,
+                period: period.clamp(0f64, f64::from(u16::MAX)) as u16,
+            }),
+        }
+    }
+
+    /// ### Purpose:
+    /// The frequency of this signal, in Hz.
+    pub fn frequency(&self) -> f64 {
+        PWM_CLOCK_HZ / f64::from(self.period)
+    }
+
+    /// ### Purpose:
+    /// The fraction of each period for which the signal is high.
+    pub fn duty_cycle(&self) -> f64 {
+        f64::from(self.on_time) / f64::from(self.period)
+    }
+}
@@ -0,0 +1,576 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Keyframe sequences, as recorded in the Maestro Control Center.
+//!
+//! A [`Sequence`] is a list of [`Frame`]s, each of which holds a target for
+//! every channel and how long to hold those targets for. Sequences can be
+//! read from (and written to) the `<Sequences>` section of a Control Center
+//! settings file, and are played back from the host by a [`Player`].
+//!
+//! ### Examples:
+//! ```rust
+//! # use raestro::maestro::sequence;
+//! # fn main() -> raestro::Result<()> {
+//! let settings = r#"
+//!   <Sequences>
+//!     <Sequence name="Wave">
+//!       <Frame name="Up" duration="500">8000 6000 0 0 0 0</Frame>
+//!       <Frame name="Down" duration="250">4000 6000 0 0 0 0</Frame>
+//!     </Sequence>
+//!   </Sequences>"#;
+//!
+//! let sequences = sequence::from_settings_xml(settings)?;
+//! assert_eq!(sequences[0].name, "Wave");
+//! assert_eq!(sequences[0].frames[1].targets[0], 4000u16);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::errors::Error;
+use crate::maestro::constants::ChannelMode;
+use crate::maestro::Maestro;
+use crate::transport::Transport;
+
+/// ### Purpose:
+/// A single keyframe of a [`Sequence`].
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Frame {
+    /// ### Purpose:
+    /// The frame's name, as shown in the Control Center.
+    pub name: String,
+
+    /// ### Purpose:
+    /// How long the frame's targets are held for before the next frame.
+    pub duration: Duration,
+
+    /// ### Purpose:
+    /// The target (in quarter-us) of each channel, indexed by channel. A
+    /// target of `0` turns the channel off.
+    pub targets: Vec<u16>,
+}
+
+/// ### Purpose:
+/// A named list of keyframes.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Sequence {
+    /// ### Purpose:
+    /// The sequence's name, as shown in the Control Center.
+    pub name: String,
+
+    /// ### Purpose:
+    /// The sequence's frames, in the order they are played.
+    pub frames: Vec<Frame>,
+}
+
+impl Sequence {
+    /// ### Purpose:
+    /// How long the whole sequence takes to play once.
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+
+    /// ### Purpose:
+    /// The index of the frame being held at the given time since the
+    /// sequence started, or `None` if the sequence is empty.
+    ///
+    /// ### Notes:
+    /// Times past the end of the sequence return the last frame.
+    pub fn frame_at(&self, time: Duration) -> Option<usize> {
+        let mut start = Duration::ZERO;
+        let index = self.frames.iter().position(|frame| {
+            start += frame.duration;
+            time < start
+        });
+        index.or_else(|| self.frames.len().checked_sub(1usize))
+    }
+
+    /// ### Purpose:
+    /// The time since the sequence started at which the given frame begins.
+    pub fn frame_start(&self, index: usize) -> Duration {
+        self.frames
+            .iter()
+            .take(index)
+            .map(|frame| frame.duration)
+            .sum()
+    }
+}
+
+/// ### Purpose:
+/// Plays a [`Sequence`] back on a [`Maestro`].
+///
+/// ### Notes:
+/// The player does not keep time by itself: [`Player::advance`] moves the
+/// play head forward and sends the targets of every frame it lands on, so
+/// that playback can be driven by any loop (or by simulated time in tests).
+/// [`Player::run`] drives it from the wall clock instead.
+///
+/// Only the targets of channels which exist on the Maestro's model and are
+/// configured as servos are sent; all other targets in a frame are ignored.
+/// If an `advance` skips over a frame entirely, that frame is never sent.
+#[derive(Clone, Debug)]
+pub struct Player {
+    sequence: Sequence,
+    position: Duration,
+    playing: bool,
+    looping: bool,
+    sent: Option<usize>,
+}
+
+impl Player {
+    /// ### Purpose:
+    /// Creates a paused player at the start of the given sequence.
+    pub fn new(sequence: Sequence) -> Self {
+        Self {
+            sequence,
+            position: Duration::ZERO,
+            playing: false,
+            looping: false,
+            sent: None,
+        }
+    }
+
+    /// ### Purpose:
+    /// The sequence being played.
+    pub fn sequence(&self) -> &Sequence {
+        &self.sequence
+    }
+
+    /// ### Purpose:
+    /// Resumes playback from the current position.
+    ///
+    /// ### Notes:
+    /// Playing a sequence which has finished restarts it from the beginning.
+    pub fn play(&mut self) {
+        if self.position >= self.sequence.duration() {
+            self.seek(Duration::ZERO);
+        };
+        self.playing = true;
+    }
+
+    /// ### Purpose:
+    /// Pauses playback, holding the current frame.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// ### Purpose:
+    /// Whether the player is currently playing.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// ### Purpose:
+    /// Sets whether playback wraps around to the first frame at the end of
+    /// the sequence, instead of stopping.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// ### Purpose:
+    /// Whether playback loops.
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// ### Purpose:
+    /// The play head's time since the start of the sequence.
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    /// ### Purpose:
+    /// Moves the play head to the given time since the start of the
+    /// sequence (clamped to its end).
+    ///
+    /// ### Notes:
+    /// The frame at the new position is sent on the next `advance`, even if
+    /// the player is paused.
+    pub fn seek(&mut self, time: Duration) {
+        self.position = time.min(self.sequence.duration());
+        self.sent = None;
+    }
+
+    /// ### Purpose:
+    /// Moves the play head to the start of the given frame (or the end of
+    /// the sequence, if there is no such frame).
+    pub fn seek_frame(&mut self, index: usize) {
+        self.seek(self.sequence.frame_start(index));
+    }
+
+    /// ### Purpose:
+    /// Moves the play head forward by the given time (if playing), and sends
+    /// the targets of the frame it lands on if they have not been sent yet.
+    pub fn advance<T>(
+        &mut self,
+        maestro: &mut Maestro<T>,
+        elapsed: Duration,
+    ) -> crate::Result<()>
+    where
+        T: Transport,
+    {
+        let duration = self.sequence.duration();
+        if self.playing {
+            self.position += elapsed;
+            if self.position >= duration {
+                match self.looping && !duration.is_zero() {
+                    true => {
+                        let wrapped =
+                            self.position.as_nanos() % duration.as_nanos();
+                        self.position = Duration::from_nanos(wrapped as u64);
+                        self.sent = None;
+                    },
+                    false => {
+                        self.position = duration;
+                        self.playing = false;
+                    },
+                };
+            };
+        };
+        match self.sequence.frame_at(self.position) {
+            Some(index) if self.sent != Some(index) => {
+                send_frame(maestro, &self.sequence.frames[index])?;
+                self.sent = Some(index);
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// ### Purpose:
+    /// Plays the sequence in real time, checking the clock every `interval`,
+    /// until it finishes.
+    ///
+    /// ### Notes:
+    /// This blocks for as long as the sequence plays, which is forever if
+    /// looping is enabled.
+    pub fn run<T>(
+        &mut self,
+        maestro: &mut Maestro<T>,
+        interval: Duration,
+    ) -> crate::Result<()>
+    where
+        T: Transport,
+    {
+        self.play();
+        self.advance(maestro, Duration::ZERO)?;
+        let mut last = Instant::now();
+        while self.playing {
+            thread::sleep(interval);
+            let now = Instant::now();
+            self.advance(maestro, now - last)?;
+            last = now;
+        }
+        Ok(())
+    }
+}
+
+/// ### Purpose:
+/// Sends the targets of a single frame in one write.
+fn send_frame<T>(maestro: &mut Maestro<T>, frame: &Frame) -> crate::Result<()>
+where
+    T: Transport,
+{
+    let targets = maestro
+        .model()
+        .channels()
+        .zip(frame.targets.iter())
+        .filter(|&(channel, _)| {
+            maestro.channel_mode(channel) == ChannelMode::Servo
+        })
+        .map(|(channel, &target)| (channel as u8, target))
+        .collect::<Vec<_>>();
+    targets.iter().try_for_each(|&(channel, target)| {
+        maestro.check_target(channel, target)
+    })?;
+    match targets.is_empty() {
+        true => Ok(()),
+        false => maestro.write_individual_targets(targets),
+    }
+}
+
+/// ### Purpose:
+/// Reads every sequence from the `<Sequences>` section of a Control Center
+/// settings file.
+///
+/// ### Notes:
+/// Either a whole settings file or just its `<Sequences>` element can be
+/// passed in. A settings file without any sequences yields an empty list.
+/// Frame durations are in milliseconds, and frame targets are listed in
+/// quarter-us, separated by spaces, starting at channel `0`.
+pub fn from_settings_xml(xml: &str) -> crate::Result<Vec<Sequence>> {
+    let start = match xml.find("<Sequences") {
+        Some(start) => start,
+        None => return Ok(vec![]),
+    };
+    let mut reader = Reader {
+        rest: &xml[start..],
+    };
+    match reader.expect_start()? {
+        Tag {
+            name, empty: true, ..
+        } if name == "Sequences" => Ok(vec![]),
+        Tag { name, .. } if name == "Sequences" => {
+            let mut sequences = vec![];
+            loop {
+                match reader.next()? {
+                    Token::End(name) if name == "Sequences" => {
+                        break Ok(sequences)
+                    },
+                    Token::Start(tag) if tag.name == "Sequence" => {
+                        sequences.push(read_sequence(&mut reader, tag)?)
+                    },
+                    token => break Err(unexpected(token)),
+                }
+            }
+        },
+        tag => Err(unexpected(Token::Start(tag))),
+    }
+}
+
+/// ### Purpose:
+/// Writes the given sequences as the `<Sequences>` section of a Control
+/// Center settings file.
+pub fn to_settings_xml(sequences: &[Sequence]) -> String {
+    let mut xml = String::from("<Sequences>\n");
+    sequences.iter().for_each(|sequence| {
+        let _ =
+            writeln!(xml, "  <Sequence name=\"{}\">", escape(&sequence.name));
+        sequence.frames.iter().for_each(|frame| {
+            let targets = frame
+                .targets
+                .iter()
+                .map(u16::to_string)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let _ = writeln!(
+                xml,
+                "    <Frame name=\"{}\" duration=\"{}\">{}</Frame>",
+                escape(&frame.name),
+                frame.duration.as_millis(),
+                targets,
+            );
+        });
+        xml.push_str("  </Sequence>\n");
+    });
+    xml.push_str("</Sequences>\n");
+    xml
+}
+
+/// ### Purpose:
+/// Reads the frames of a `<Sequence>` element, up to its end tag.
+fn read_sequence(reader: &mut Reader<'_>, tag: Tag) -> crate::Result<Sequence> {
+    let name = tag.attribute("name").unwrap_or_default();
+    let mut sequence = Sequence {
+        name,
+        frames: vec![],
+    };
+    if tag.empty {
+        return Ok(sequence);
+    };
+    loop {
+        match reader.next()? {
+            Token::End(name) if name == "Sequence" => break Ok(sequence),
+            Token::Start(tag) if tag.name == "Frame" => {
+                sequence.frames.push(read_frame(reader, tag)?)
+            },
+            token => break Err(unexpected(token)),
+        }
+    }
+}
+
+/// ### Purpose:
+/// Reads a `<Frame>` element, up to its end tag.
+fn read_frame(reader: &mut Reader<'_>, tag: Tag) -> crate::Result<Frame> {
+    let name = tag.attribute("name").unwrap_or_default();
+    let duration = tag
+        .attribute("duration")
+        .and_then(|duration| duration.trim().parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .ok_or_else(|| invalid("frames need a duration in milliseconds"))?;
+    let text = match tag.empty {
+        true => String::new(),
+        false => match reader.next()? {
+            Token::Text(text) => {
+                reader.expect_end("Frame")?;
+                text
+            },
+            Token::End(name) if name == "Frame" => String::new(),
+            token => return Err(unexpected(token)),
+        },
+    };
+    let targets = text
+        .split_whitespace()
+        .map(|target| {
+            target
+                .parse::<u16>()
+                .map_err(|_| invalid(&format!("invalid target {target:?}")))
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    Ok(Frame {
+        name,
+        duration,
+        targets,
+    })
+}
+
+/// ### Purpose:
+/// A start (or empty-element) tag.
+#[derive(Debug)]
+struct Tag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    empty: bool,
+}
+
+impl Tag {
+    fn attribute(&self, name: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// ### Purpose:
+/// The parts of an XML document the sequence section is made of.
+#[derive(Debug)]
+enum Token {
+    Start(Tag),
+    End(String),
+    Text(String),
+}
+
+/// ### Purpose:
+/// A minimal XML reader, which only understands elements, attributes, text
+/// and the standard entities. Comments, processing instructions and
+/// whitespace-only text are skipped.
+struct Reader<'a> {
+    rest: &'a str,
+}
+
+impl Reader<'_> {
+    fn next(&mut self) -> crate::Result<Token> {
+        loop {
+            let rest = self.rest;
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment
+                    .find("-->")
+                    .ok_or_else(|| invalid("unterminated comment"))?;
+                self.rest = &comment[end + 3usize..];
+            } else if let Some(instruction) = rest.strip_prefix("<?") {
+                let end = instruction
+                    .find("?>")
+                    .ok_or_else(|| invalid("unterminated instruction"))?;
+                self.rest = &instruction[end + 2usize..];
+            } else if let Some(tag) = rest.strip_prefix("</") {
+                let end =
+                    tag.find('>').ok_or_else(|| invalid("unterminated tag"))?;
+                self.rest = &tag[end + 1usize..];
+                break Ok(Token::End(tag[..end].trim().to_string()));
+            } else if let Some(tag) = rest.strip_prefix('<') {
+                let end =
+                    tag.find('>').ok_or_else(|| invalid("unterminated tag"))?;
+                self.rest = &tag[end + 1usize..];
+                break parse_tag(&tag[..end]).map(Token::Start);
+            } else if rest.is_empty() {
+                break Err(invalid("unexpected end of file"));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.rest = &rest[end..];
+                let text = &rest[..end];
+                if !text.trim().is_empty() {
+                    break Ok(Token::Text(unescape(text)));
+                };
+            };
+        }
+    }
+
+    fn expect_start(&mut self) -> crate::Result<Tag> {
+        match self.next()? {
+            Token::Start(tag) => Ok(tag),
+            token => Err(unexpected(token)),
+        }
+    }
+
+    fn expect_end(&mut self, name: &str) -> crate::Result<()> {
+        match self.next()? {
+            Token::End(end) if end == name => Ok(()),
+            token => Err(unexpected(token)),
+        }
+    }
+}
+
+/// ### Purpose:
+/// Parses the inside of a start tag (i.e., `Frame name="a" duration="10"`).
+fn parse_tag(tag: &str) -> crate::Result<Tag> {
+    let (tag, empty) = match tag.strip_suffix('/') {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = tag[..name_end].to_string();
+    let mut rest = tag[name_end..].trim_start();
+    let mut attributes = vec![];
+    while !rest.is_empty() {
+        let equals = rest
+            .find('=')
+            .ok_or_else(|| invalid("attributes need a value"))?;
+        let key = rest[..equals].trim().to_string();
+        let value = rest[equals + 1usize..].trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|&quote| quote == '"' || quote == '\'')
+            .ok_or_else(|| invalid("attribute values must be quoted"))?;
+        let end = value[1usize..]
+            .find(quote)
+            .ok_or_else(|| invalid("unterminated attribute value"))?;
+        attributes.push((key, unescape(&value[1usize..end + 1usize])));
+        rest = value[end + 2usize..].trim_start();
+    }
+    Ok(Tag {
+        name,
+        attributes,
+        empty,
+    })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidSequence(reason.to_string())
+}
+
+fn unexpected(token: Token) -> Error {
+    let found = match token {
+        Token::Start(tag) => format!("unexpected <{}>", tag.name),
+        Token::End(name) => format!("unexpected </{name}>"),
+        Token::Text(text) => format!("unexpected text {:?}", text.trim()),
+    };
+    Error::InvalidSequence(found)
+}
@@ -0,0 +1,1450 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+use std::thread;
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::maestro::builder::Builder;
+use crate::maestro::bus::MaestroBus;
+use crate::maestro::calibration::ServoCalibration;
+use crate::maestro::constants::Channel;
+use crate::maestro::constants::ChannelMode;
+use crate::maestro::constants::ErrorValues;
+use crate::maestro::constants::Model;
+use crate::maestro::constants::Protocol;
+use crate::maestro::constants::ScriptStatus;
+use crate::maestro::constants::UpdateRate;
+use crate::maestro::mini_ssc::MiniSscSettings;
+use crate::maestro::motion::SCurve;
+use crate::maestro::motion::Spline;
+use crate::maestro::motion::Trajectory;
+use crate::maestro::motion::Trapezoidal;
+use crate::maestro::pwm::Pwm;
+use crate::maestro::sequence;
+use crate::maestro::sequence::Frame;
+use crate::maestro::sequence::Player;
+use crate::maestro::sequence::Sequence;
+use crate::maestro::units::PulseWidth;
+use crate::maestro::units::ServoAccel;
+use crate::maestro::units::ServoSpeed;
+use crate::maestro::Maestro;
+use crate::script;
+use crate::sim::Emulator;
+use crate::transport::Memory;
+use crate::transport::Transport;
+
+fn maestro() -> Maestro<Memory> {
+    Builder::default().build_with(Memory::new()).unwrap()
+}
+
+fn compact_maestro() -> Maestro<Memory> {
+    Builder::default()
+        .protocol(Protocol::Compact)
+        .build_with(Memory::new())
+        .unwrap()
+}
+
+#[test]
+fn set_target_packet() {
+    let mut maestro = maestro();
+    maestro.set_target(Channel::Channel2, 6000u16).unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [0xaau8, 0x0cu8, 0x04u8, 0x02u8, 0x70u8, 0x2eu8]
+    );
+}
+
+#[test]
+fn set_target_out_of_range() {
+    let mut maestro = maestro();
+    let result = maestro.set_target(Channel::Channel0, 3967u16);
+
+    assert!(matches!(result, Err(Error::InvalidValue(3967u16))));
+    assert!(maestro.transport().written().is_empty());
+}
+
+#[test]
+fn set_targets_packet() {
+    let mut maestro = Builder::default()
+        .model(Model::MiniMaestro12)
+        .build_with(Memory::new())
+        .unwrap();
+    maestro
+        .set_targets(Channel::Channel1, &[4000u16, 6000u16])
+        .unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [
+            0xaau8, 0x0cu8, 0x1fu8, 0x02u8, 0x01u8, 0x20u8, 0x1fu8, 0x70u8,
+            0x2eu8
+        ]
+    );
+}
+
+#[test]
+fn set_targets_fallback() {
+    let mut maestro = compact_maestro();
+    maestro
+        .set_targets(Channel::Channel1, &[4000u16, 6000u16])
+        .unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [0x84u8, 0x01u8, 0x20u8, 0x1fu8, 0x84u8, 0x02u8, 0x70u8, 0x2eu8]
+    );
+}
+
+#[test]
+fn set_targets_out_of_range() {
+    let mut maestro = maestro();
+    let result = maestro.set_targets(Channel::Channel5, &[4000u16, 6000u16]);
+
+    assert!(matches!(result, Err(Error::InvalidChannel(6u8))));
+    let result = maestro.set_targets(Channel::Channel0, &[4000u16, 8001u16]);
+
+    assert!(matches!(result, Err(Error::InvalidValue(8001u16))));
+    assert!(maestro.transport().written().is_empty());
+}
+
+#[test]
+fn channel_checked_against_model() {
+    let mut maestro = maestro();
+    let result = maestro.set_target(Channel::Channel6, 6000u16);
+
+    assert!(matches!(result, Err(Error::InvalidChannel(6u8))));
+    let result = maestro.get_position(Channel::Channel6);
+
+    assert!(matches!(result, Err(Error::InvalidChannel(6u8))));
+    assert!(maestro.transport().written().is_empty());
+}
+
+#[test]
+fn mini_maestro_channel() {
+    let mut maestro = Builder::default()
+        .model(Model::MiniMaestro18)
+        .build_with(Memory::new())
+        .unwrap();
+    maestro.set_target(Channel::Channel17, 6000u16).unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [0xaau8, 0x0cu8, 0x04u8, 0x11u8, 0x70u8, 0x2eu8]
+    );
+}
+
+#[test]
+fn digital_output_packets() {
+    let mut maestro = Builder::default()
+        .protocol(Protocol::Compact)
+        .channel_mode(Channel::Channel3, ChannelMode::Output)
+        .build_with(Memory::new())
+        .unwrap();
+    maestro.set_digital_output(Channel::Channel3, true).unwrap();
+    maestro
+        .set_digital_output(Channel::Channel3, false)
+        .unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [0x84u8, 0x03u8, 0x70u8, 0x2eu8, 0x84u8, 0x03u8, 0x00u8, 0x00u8]
+    );
+}
+
+#[test]
+fn servo_commands_rejected_on_io_channels() {
+    let mut maestro = Builder::default()
+        .channel_mode(Channel::Channel1, ChannelMode::Input)
+        .channel_mode(Channel::Channel2, ChannelMode::Output)
+        .build_with(Memory::new())
+        .unwrap();
+
+    assert!(matches!(
+        maestro.set_target(Channel::Channel1, 6000u16),
+        Err(Error::InvalidChannelMode(1u8))
+    ));
+    assert!(matches!(
+        maestro.set_speed(Channel::Channel2, 10u16),
+        Err(Error::InvalidChannelMode(2u8))
+    ));
+    assert!(matches!(
+        maestro.set_targets(Channel::Channel0, &[6000u16; 3usize]),
+        Err(Error::InvalidChannelMode(1u8))
+    ));
+    assert!(matches!(
+        maestro.set_digital_output(Channel::Channel1, true),
+        Err(Error::InvalidChannelMode(1u8))
+    ));
+    assert!(matches!(
+        maestro.read_digital_input(Channel::Channel0),
+        Err(Error::InvalidChannelMode(0u8))
+    ));
+    assert!(maestro.transport().written().is_empty());
+}
+
+#[test]
+fn go_home_packet() {
+    let mut maestro = maestro();
+    maestro.go_home().unwrap();
+
+    assert_eq!(maestro.transport().written(), [0xaau8, 0x0cu8, 0x22u8]);
+}
+
+#[test]
+fn get_position_response() {
+    let mut maestro = maestro();
+    maestro.transport_mut().queue_response(&[0x70u8, 0x17u8]);
+    let position = maestro.get_position(Channel::Channel1).unwrap();
+
+    assert_eq!(position, 6000u16);
+    assert_eq!(
+        maestro.transport().written(),
+        [0xaau8, 0x0cu8, 0x10u8, 0x01u8]
+    );
+}
+
+#[test]
+fn get_errors_response() {
+    let mut maestro = maestro();
+    maestro.transport_mut().queue_response(&[0x08u8, 0x00u8]);
+    let errors = maestro.get_errors().unwrap();
+
+    assert_eq!(errors, vec![ErrorValues::SerCrcError]);
+}
+
+#[test]
+fn faulty_read() {
+    let mut maestro = maestro();
+    maestro.transport_mut().queue_response(&[0x70u8]);
+    let result = maestro.get_position(Channel::Channel1);
+
+    assert!(matches!(
+        result,
+        Err(Error::FaultyRead {
+            actual_count: 1usize,
+            expected_count: 2usize,
+        })
+    ));
+}
+
+#[test]
+fn faulty_read_single_byte_response() {
+    let mut maestro = maestro();
+    let result = maestro.script_status();
+
+    assert!(matches!(
+        result,
+        Err(Error::FaultyRead {
+            actual_count: 0usize,
+            expected_count: 1usize,
+        })
+    ));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "1 bytes were expected to be read but only 0 byte(s) were actually read."
+    );
+}
+
+#[test]
+fn compact_set_target_packet() {
+    let mut maestro = compact_maestro();
+    maestro.set_target(Channel::Channel2, 6000u16).unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [0x84u8, 0x02u8, 0x70u8, 0x2eu8]
+    );
+}
+
+#[test]
+fn compact_get_position() {
+    let mut maestro = compact_maestro();
+    maestro.transport_mut().queue_response(&[0x70u8, 0x17u8]);
+    let position = maestro.get_position(Channel::Channel1).unwrap();
+
+    assert_eq!(position, 6000u16);
+    assert_eq!(maestro.transport().written(), [0x90u8, 0x01u8]);
+}
+
+#[test]
+fn compact_go_home_packet() {
+    let mut maestro = compact_maestro();
+    maestro.go_home().unwrap();
+
+    assert_eq!(maestro.transport().written(), [0xa2u8]);
+}
+
+#[test]
+fn mini_ssc_position_packet() {
+    let mut maestro = maestro();
+    maestro
+        .mini_ssc()
+        .set_position(Channel::Channel3, 200u8)
+        .unwrap();
+
+    assert_eq!(maestro.transport().written(), [0xffu8, 0x03u8, 0xc8u8]);
+}
+
+#[test]
+fn mini_ssc_offset() {
+    let mut maestro = Builder::default()
+        .mini_ssc(MiniSscSettings::default().offset(250u8))
+        .build_with(Memory::new())
+        .unwrap();
+    maestro
+        .mini_ssc()
+        .set_position(Channel::Channel4, 0u8)
+        .unwrap();
+    let result = maestro.mini_ssc().set_position(Channel::Channel5, 0u8);
+
+    assert_eq!(maestro.transport().written(), [0xffu8, 0xfeu8, 0x00u8]);
+    assert!(matches!(result, Err(Error::MiniSscOutOfRange(255u16))));
+}
+
+#[test]
+fn mini_ssc_target_out_of_range() {
+    let mut maestro = maestro();
+    let result = maestro.mini_ssc().set_target(Channel::Channel0, 8000u16);
+
+    assert!(matches!(result, Err(Error::MiniSscOutOfRange(8000u16))));
+    assert!(maestro.transport().written().is_empty());
+}
+
+#[test]
+fn mini_ssc_empty_ranges() {
+    let mini_ssc = MiniSscSettings {
+        offset: 0u8,
+        ranges: vec![],
+    };
+    let mut maestro = Builder::default()
+        .mini_ssc(mini_ssc.clone())
+        .build_with(Memory::new())
+        .unwrap();
+    maestro
+        .mini_ssc()
+        .set_target(Channel::Channel2, 6000u16)
+        .unwrap();
+
+    assert_eq!(maestro.transport().written(), [0xffu8, 0x02u8, 0x7fu8]);
+    assert_eq!(
+        mini_ssc
+            .range(Channel::Channel2, 6000u16, 2000u16)
+            .ranges
+            .len(),
+        24usize
+    );
+}
+
+#[test]
+fn crc_set_target_packet() {
+    let mut maestro = Builder::default()
+        .crc(true)
+        .build_with(Memory::new())
+        .unwrap();
+    maestro.transport_mut().queue_response(&[0x00u8, 0x00u8]);
+    maestro.set_target(Channel::Channel2, 6000u16).unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [
+            0xaau8, 0x0cu8, 0x04u8, 0x02u8, 0x70u8, 0x2eu8, 0x11u8, 0xaau8,
+            0x0cu8, 0x21u8, 0x78u8
+        ]
+    );
+}
+
+#[test]
+fn crc_mismatch_reported() {
+    let mut maestro = Builder::default()
+        .protocol(Protocol::Compact)
+        .crc(true)
+        .build_with(Memory::new())
+        .unwrap();
+    // A CRC error along with a serial timeout.
+    maestro.transport_mut().queue_response(&[0x28u8, 0x00u8]);
+    let result = maestro.go_home();
+
+    assert!(matches!(result, Err(Error::CrcMismatch)));
+    maestro.transport_mut().queue_response(&[0x00u8, 0x00u8]);
+    assert_eq!(maestro.get_errors().unwrap(), vec![ErrorValues::SerTimeout]);
+}
+
+#[test]
+fn device_number_packet() {
+    let mut maestro = Builder::default()
+        .device_number(13u8)
+        .build_with(Memory::new())
+        .unwrap();
+    maestro.go_home().unwrap();
+
+    assert_eq!(maestro.transport().written(), [0xaau8, 0x0du8, 0x22u8]);
+}
+
+#[test]
+fn invalid_device_number() {
+    let result = Builder::default()
+        .device_number(0x80u8)
+        .build_with(Memory::new());
+
+    assert!(matches!(result, Err(Error::InvalidDeviceNumber(0x80u8))));
+}
+
+/// Every board on a daisy chain sees every byte sent down the line.
+#[derive(Debug)]
+struct Chain(Vec<Emulator>);
+
+impl Chain {
+    fn new(device_numbers: &[u8]) -> Self {
+        let emulators = device_numbers
+            .iter()
+            .map(|&device_number| {
+                let mut emulator = Emulator::new();
+                emulator.set_device_number(device_number);
+                emulator
+            })
+            .collect();
+        Self(emulators)
+    }
+}
+
+impl Transport for Chain {
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        let mut count = 0usize;
+        for emulator in self.0.iter_mut() {
+            count += emulator.read(&mut buf[count..])?;
+        }
+        Ok(count)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize> {
+        for emulator in self.0.iter_mut() {
+            emulator.write(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn set_read_timeout(&mut self, _: Duration) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+/// Flips the bits of the CRC byte of the next packet sent to the emulator.
+#[derive(Debug)]
+struct Corrupt {
+    emulator: Emulator,
+    armed: bool,
+}
+
+impl Transport for Corrupt {
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        self.emulator.read(buf)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize> {
+        let mut packet = buf.to_vec();
+        if let (true, Some(crc)) = (self.armed, packet.last_mut()) {
+            *crc ^= 0x7fu8;
+            self.armed = false;
+        };
+        self.emulator.write(&packet)
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn set_read_timeout(&mut self, _: Duration) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn crc_mismatch_reported_on_queries() {
+    let mut emulator = Emulator::new();
+    emulator.set_crc(true);
+    let transport = Corrupt {
+        emulator,
+        armed: false,
+    };
+    let mut maestro =
+        Builder::default().crc(true).build_with(transport).unwrap();
+    maestro.set_target(Channel::Channel0, 4000u16).unwrap();
+
+    maestro.transport_mut().armed = true;
+    let result = maestro.get_position(Channel::Channel0);
+    assert!(matches!(result, Err(Error::CrcMismatch)));
+    assert_eq!(maestro.get_position(Channel::Channel0).unwrap(), 4000u16);
+
+    maestro.transport_mut().armed = true;
+    assert!(matches!(maestro.get_errors(), Err(Error::CrcMismatch)));
+    assert_eq!(maestro.get_errors().unwrap(), vec![]);
+}
+
+#[test]
+fn bus_addresses_devices() {
+    let bus = MaestroBus::new(Chain::new(&[12u8, 13u8]));
+    let mut first = bus.device(Builder::default()).unwrap();
+    let mut second =
+        bus.device(Builder::default().device_number(13u8)).unwrap();
+    first.set_target(Channel::Channel0, 4000u16).unwrap();
+    second.set_target(Channel::Channel0, 8000u16).unwrap();
+
+    assert_eq!(first.get_position(Channel::Channel0).unwrap(), 4000u16);
+    assert_eq!(second.get_position(Channel::Channel0).unwrap(), 8000u16);
+    assert_eq!(
+        bus.lock().0[0usize].target(Channel::Channel0).unwrap(),
+        4000u16
+    );
+    assert_eq!(
+        bus.lock().0[1usize].target(Channel::Channel0).unwrap(),
+        8000u16
+    );
+}
+
+#[test]
+fn bus_serializes_commands() {
+    let bus = MaestroBus::new(Chain::new(&[1u8, 2u8, 3u8]));
+    let handles: Vec<_> = (1u8..=3u8)
+        .map(|device_number| {
+            let builder = Builder::default().device_number(device_number);
+            let mut maestro = bus.device(builder).unwrap();
+            thread::spawn(move || {
+                let target = 4000u16 + u16::from(device_number);
+                for _ in 0..100 {
+                    maestro.set_target(Channel::Channel1, target).unwrap();
+                    let position = maestro.get_position(Channel::Channel1);
+                    assert_eq!(position.unwrap(), target);
+                }
+                assert!(maestro.get_errors().unwrap().is_empty());
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .for_each(|handle| handle.join().unwrap());
+}
+
+#[test]
+fn restart_script_packets() {
+    let mut maestro = maestro();
+    maestro.restart_script_at(3u8).unwrap();
+    maestro.restart_script_with_parameter(1u8, 6000u16).unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [
+            0xaau8, 0x0cu8, 0x27u8, 0x03u8, 0xaau8, 0x0cu8, 0x28u8, 0x01u8,
+            0x70u8, 0x2eu8
+        ]
+    );
+}
+
+#[test]
+fn restart_script_out_of_range() {
+    let mut maestro = maestro();
+    let subroutine = maestro.restart_script_at(128u8);
+    let parameter = maestro.restart_script_with_parameter(0u8, 0x4000u16);
+
+    assert!(matches!(subroutine, Err(Error::InvalidSubroutine(128u8))));
+    assert!(matches!(parameter, Err(Error::InvalidParameter(0x4000u16))));
+    assert!(maestro.transport().written().is_empty());
+}
+
+#[test]
+fn restart_script_named() {
+    let mut maestro = maestro();
+    let program = script::compile("quit sub wave quit sub Sweep quit").unwrap();
+    maestro.restart_script_named(&program, "sweep").unwrap();
+    let unknown = maestro.restart_script_named(&program, "dance");
+
+    assert_eq!(
+        maestro.transport().written(),
+        [0xaau8, 0x0cu8, 0x27u8, 0x01u8]
+    );
+    assert!(matches!(
+        unknown,
+        Err(Error::UnknownSubroutine(name)) if name == "dance"
+    ));
+}
+
+#[test]
+fn script_status_response() {
+    let mut maestro = maestro();
+    maestro.transport_mut().queue_response(&[0x00u8, 0x01u8]);
+
+    assert_eq!(maestro.script_status().unwrap(), ScriptStatus::Running);
+    assert_eq!(maestro.script_status().unwrap(), ScriptStatus::Stopped);
+    assert_eq!(
+        maestro.transport().written(),
+        [0xaau8, 0x0cu8, 0x2eu8, 0xaau8, 0x0cu8, 0x2eu8]
+    );
+}
+
+#[test]
+fn is_moving_response() {
+    let mut maestro = maestro();
+    maestro.transport_mut().queue_response(&[0x01u8, 0x00u8]);
+
+    assert!(maestro.is_moving().unwrap());
+    assert!(!maestro.is_moving().unwrap());
+    assert_eq!(
+        maestro.transport().written(),
+        [0xaau8, 0x0cu8, 0x13u8, 0xaau8, 0x0cu8, 0x13u8]
+    );
+}
+
+fn realtime_maestro() -> Maestro<Emulator> {
+    let mut emulator = Emulator::new();
+    emulator.set_realtime(true);
+    Builder::default().build_with(emulator).unwrap()
+}
+
+#[test]
+fn wait_until_settled() {
+    let mut maestro = realtime_maestro();
+    maestro.set_target(Channel::Channel0, 4000u16).unwrap();
+    maestro.set_speed(Channel::Channel0, 100u16).unwrap();
+    maestro.set_target(Channel::Channel0, 6000u16).unwrap();
+
+    assert!(maestro.is_moving().unwrap());
+    maestro
+        .wait_until_settled(Duration::from_secs(1u64))
+        .unwrap();
+    assert_eq!(maestro.get_position(Channel::Channel0).unwrap(), 6000u16);
+}
+
+#[test]
+fn wait_until_settled_timeout() {
+    let mut maestro = realtime_maestro();
+    maestro.set_target(Channel::Channel0, 4000u16).unwrap();
+    maestro.set_speed(Channel::Channel0, 1u16).unwrap();
+    maestro.set_target(Channel::Channel0, 8000u16).unwrap();
+    let timeout = Duration::from_millis(50u64);
+    let result = maestro.wait_until_settled(timeout);
+
+    assert!(
+        matches!(result, Err(Error::Timeout(duration)) if duration == timeout)
+    );
+}
+
+#[test]
+fn wait_for_channel() {
+    let mut maestro = realtime_maestro();
+    maestro.set_target(Channel::Channel2, 4000u16).unwrap();
+    maestro.set_speed(Channel::Channel2, 50u16).unwrap();
+    maestro.set_target(Channel::Channel2, 8000u16).unwrap();
+    let position = maestro
+        .wait_for_channel(
+            Channel::Channel2,
+            8000u16,
+            0u16,
+            Duration::from_secs(1u64),
+        )
+        .unwrap();
+
+    assert_eq!(position, 8000u16);
+}
+
+#[test]
+fn analog_average() {
+    let mut maestro = Builder::default()
+        .channel_mode(Channel::Channel0, ChannelMode::Input)
+        .build_with(Memory::new())
+        .unwrap();
+    maestro
+        .transport_mut()
+        .queue_response(&[0x00u8, 0x00u8, 0xffu8, 0x03u8]);
+    let reading = maestro
+        .read_analog_averaged(Channel::Channel0, 2usize, Duration::ZERO)
+        .unwrap();
+
+    assert_eq!(reading.raw, 512u16);
+    assert_eq!(reading.volts, 2.5f64);
+}
+
+#[test]
+fn set_pwm_packet() {
+    let mut maestro = Builder::default()
+        .model(Model::MiniMaestro12)
+        .channel_mode(Channel::Channel8, ChannelMode::Output)
+        .build_with(Memory::new())
+        .unwrap();
+    let pwm = Pwm::from_frequency(10_000f64, 0.25f64).unwrap();
+    maestro.set_pwm(pwm).unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [0xaau8, 0x0cu8, 0x0au8, 0x30u8, 0x09u8, 0x40u8, 0x25u8]
+    );
+}
+
+#[test]
+fn set_pwm_unsupported() {
+    let mut maestro = maestro();
+    let pwm = Pwm::new(1200u16, 4800u16).unwrap();
+    let result = maestro.set_pwm(pwm);
+
+    assert!(matches!(result, Err(Error::UnsupportedCommand)));
+    assert!(maestro.transport().written().is_empty());
+}
+
+#[test]
+fn set_pwm_requires_output() {
+    let pwm = Pwm::new(1200u16, 4800u16).unwrap();
+    for (model, channel) in [
+        (Model::MiniMaestro12, 8u8),
+        (Model::MiniMaestro18, 12u8),
+        (Model::MiniMaestro24, 12u8),
+    ] {
+        let mut maestro = Builder::default()
+            .model(model)
+            .build_with(Memory::new())
+            .unwrap();
+        let result = maestro.set_pwm(pwm);
+
+        assert_eq!(model.pwm_channel().map(|pwm| pwm as u8), Some(channel));
+        assert!(
+            matches!(result, Err(Error::InvalidChannelMode(c)) if c == channel)
+        );
+        assert!(maestro.transport().written().is_empty());
+    }
+}
+
+#[test]
+fn pwm_limits() {
+    let pwm = Pwm::new(1200u16, 4800u16).unwrap();
+
+    assert_eq!(pwm.frequency(), 10_000f64);
+    assert_eq!(pwm.duty_cycle(), 0.25f64);
+    assert!(matches!(
+        Pwm::new(4801u16, 4800u16),
+        Err(Error::InvalidPwm {
+            on_time: 4801u16,
+            period: 4800u16
+        })
+    ));
+    assert!(Pwm::from_frequency(1_000f64, 0.5f64).is_err());
+    assert!(Pwm::from_frequency(10_000f64, 1.5f64).is_err());
+    assert!(Pwm::from_frequency(0f64, 0.5f64).is_err());
+}
+
+#[test]
+fn configured_target_limits() {
+    let mut maestro = Builder::default()
+        .protocol(Protocol::Compact)
+        .target_limits(Channel::Channel1, 2000u16, 10000u16)
+        .build_with(Memory::new())
+        .unwrap();
+    maestro.set_target(Channel::Channel1, 10000u16).unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [0x84u8, 0x01u8, 0x10u8, 0x4eu8]
+    );
+    assert!(matches!(
+        maestro.set_target(Channel::Channel1, 10001u16),
+        Err(Error::InvalidValue(10001u16))
+    ));
+    assert!(matches!(
+        maestro.set_target(Channel::Channel0, 10000u16),
+        Err(Error::InvalidValue(10000u16))
+    ));
+}
+
+#[test]
+fn invalid_target_limits() {
+    let result = Builder::default()
+        .target_limits(Channel::Channel1, 6000u16, 5000u16)
+        .build_with(Memory::new());
+
+    assert!(matches!(
+        result,
+        Err(Error::InvalidLimits {
+            min: 6000u16,
+            max: 5000u16
+        })
+    ));
+}
+
+#[test]
+fn release_packets() {
+    let mut maestro = Builder::default()
+        .protocol(Protocol::Compact)
+        .channel_mode(Channel::Channel1, ChannelMode::Input)
+        .build_with(Memory::new())
+        .unwrap();
+    maestro.release(Channel::Channel0).unwrap();
+
+    assert_eq!(
+        maestro.transport_mut().take_written(),
+        [0x84u8, 0x00u8, 0x00u8, 0x00u8]
+    );
+    maestro.release_all().unwrap();
+
+    let written = maestro.transport().written();
+    assert_eq!(written.len(), 20usize);
+    assert!(written.chunks(4usize).all(|packet| packet[0usize] == 0x84u8
+        && packet[1usize] != 0x01u8
+        && packet[2usize..] == [0x00u8, 0x00u8]));
+}
+
+#[test]
+fn unit_conversions() {
+    let pulse_width = PulseWidth::from_micros(1500.1f64).unwrap();
+    assert_eq!(pulse_width.qtr_micros(), 6000u16);
+    assert_eq!(pulse_width.to_duration(), Duration::from_micros(1500u64));
+    assert_eq!(
+        PulseWidth::from_duration(Duration::from_micros(992u64)).unwrap(),
+        3968u16
+    );
+    assert!(PulseWidth::from_qtr_micros(0x4000u16).is_err());
+    assert!(PulseWidth::from_micros(-1f64).is_err());
+
+    let speed = ServoSpeed::from_micros_per_second(1f64).unwrap();
+    assert_eq!(speed.raw(), 1u16);
+    assert_eq!(speed.micros_per_second(), Some(25f64));
+    assert_eq!(ServoSpeed::UNLIMITED.micros_per_second(), None);
+    assert!(ServoSpeed::from_micros_per_second(0f64).is_err());
+    assert!(ServoSpeed::from_raw(0x4000u16).is_err());
+
+    let acceleration =
+        ServoAccel::from_micros_per_second_squared(3125f64).unwrap();
+    assert_eq!(acceleration.raw(), 10u8);
+    assert!(ServoAccel::from_micros_per_second_squared(1e6f64).is_err());
+    assert_eq!(
+        acceleration.micros_per_second_squared_at(Duration::from_millis(10u64)),
+        acceleration.micros_per_second_squared()
+    );
+}
+
+#[test]
+fn typed_units_accepted() {
+    let mut maestro = compact_maestro();
+    maestro
+        .set_target(
+            Channel::Channel0,
+            PulseWidth::from_micros(1500f64).unwrap(),
+        )
+        .unwrap();
+    maestro
+        .set_speed(Channel::Channel0, ServoSpeed::from(40u16))
+        .unwrap();
+    maestro
+        .set_acceleration(Channel::Channel0, ServoAccel::from(4u8))
+        .unwrap();
+
+    assert_eq!(
+        maestro.transport().written(),
+        [
+            0x84u8, 0x00u8, 0x70u8, 0x2eu8, 0x87u8, 0x00u8, 0x28u8, 0x00u8,
+            0x89u8, 0x00u8, 0x04u8, 0x00u8
+        ]
+    );
+    assert!(matches!(
+        maestro.set_speed(Channel::Channel0, 0x4000u16),
+        Err(Error::UnitOutOfRange(_))
+    ));
+}
+
+#[test]
+fn calibration_mapping() {
+    let calibration = ServoCalibration::new(
+        PulseWidth::from_micros(1000f64).unwrap(),
+        PulseWidth::from_micros(2000f64).unwrap(),
+        PulseWidth::from_micros(1400f64).unwrap(),
+        90f64,
+    )
+    .unwrap();
+    assert_eq!(
+        calibration.to_pulse_width(-90f64).unwrap().micros(),
+        1000f64
+    );
+    assert_eq!(calibration.to_pulse_width(0f64).unwrap().micros(), 1400f64);
+    assert_eq!(calibration.to_pulse_width(45f64).unwrap().micros(), 1700f64);
+    assert_eq!(calibration.to_degrees(PulseWidth::from(6800u16)), 45f64);
+    assert_eq!(calibration.to_degrees(PulseWidth::OFF), -90f64);
+    assert!(matches!(
+        calibration.to_pulse_width(91f64),
+        Err(Error::UnitOutOfRange(_))
+    ));
+
+    let reversed = calibration.clone().reversed(true);
+    assert_eq!(reversed.to_pulse_width(90f64).unwrap().micros(), 1000f64);
+    assert_eq!(reversed.to_degrees(PulseWidth::from(4000u16)), 90f64);
+
+    let table = calibration
+        .with_table(vec![(0f64, -1f64), (60f64, 0.5f64), (180f64, 1f64)])
+        .unwrap();
+    assert_eq!(table.angles(), (0f64, 180f64));
+    assert_eq!(table.to_pulse_width(30f64).unwrap().micros(), 1300f64);
+    assert_eq!(table.to_pulse_width(120f64).unwrap().micros(), 1850f64);
+    assert_eq!(table.to_degrees(PulseWidth::from(7400u16)), 120f64);
+}
+
+#[test]
+fn invalid_calibrations() {
+    let (min, max) = (PulseWidth::from(4000u16), PulseWidth::from(8000u16));
+    assert!(ServoCalibration::new(min, max, max, 90f64).is_ok());
+    assert!(matches!(
+        ServoCalibration::new(max, min, max, 90f64),
+        Err(Error::InvalidCalibration)
+    ));
+    assert!(ServoCalibration::new(min, max, PulseWidth::OFF, 90f64).is_err());
+    assert!(ServoCalibration::new(min, max, min, 0f64).is_err());
+    assert!(ServoCalibration::default()
+        .with_table(vec![(0f64, 0f64), (0f64, 1f64)])
+        .is_err());
+    assert!(ServoCalibration::default()
+        .with_table(vec![(0f64, 0f64), (90f64, 1.5f64)])
+        .is_err());
+}
+
+#[test]
+fn angles() {
+    let calibration = ServoCalibration::default().reversed(true);
+    let mut maestro = Builder::default()
+        .calibration(Channel::Channel2, calibration)
+        .build_with(Emulator::new())
+        .unwrap();
+    maestro.set_angle(Channel::Channel0, 45f64).unwrap();
+    maestro.set_angle(Channel::Channel2, 45f64).unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel0).unwrap(), 7000u16);
+    assert_eq!(maestro.get_position(Channel::Channel2).unwrap(), 4984u16);
+    assert_eq!(maestro.get_angle(Channel::Channel0).unwrap(), 45f64);
+    assert_eq!(maestro.get_angle(Channel::Channel2).unwrap(), 45f64);
+    assert!(maestro.set_angle(Channel::Channel0, 120f64).is_err());
+}
+
+#[test]
+fn physical_rates() {
+    let mut maestro = compact_maestro();
+    let speed = maestro
+        .set_speed_deg_per_sec(Channel::Channel0, 60f64)
+        .unwrap();
+    assert_eq!((speed.value.raw(), speed.saturated), (13u16, false));
+    let acceleration = maestro
+        .set_accel_deg_per_sec2(Channel::Channel0, 360f64)
+        .unwrap();
+    assert_eq!(
+        (acceleration.value.raw(), acceleration.saturated),
+        (6u8, false)
+    );
+
+    assert_eq!(
+        maestro.transport_mut().take_written(),
+        [0x87u8, 0x00u8, 0x0du8, 0x00u8, 0x89u8, 0x00u8, 0x06u8, 0x00u8]
+    );
+    let speed = maestro
+        .set_speed_deg_per_sec(Channel::Channel0, 100_000f64)
+        .unwrap();
+    assert_eq!((speed.value.raw(), speed.saturated), (0x3fffu16, true));
+    let acceleration = maestro
+        .set_accel_deg_per_sec2(Channel::Channel0, 100_000f64)
+        .unwrap();
+    assert_eq!(
+        (acceleration.value.raw(), acceleration.saturated),
+        (255u8, true)
+    );
+    assert!(matches!(
+        maestro.set_speed_deg_per_sec(Channel::Channel0, 0f64),
+        Err(Error::UnitOutOfRange(_))
+    ));
+}
+
+#[test]
+fn physical_rates_scale_with_period() {
+    let mut maestro = Builder::default()
+        .protocol(Protocol::Compact)
+        .model(Model::MiniMaestro12)
+        .servo_period(Duration::from_millis(30u64))
+        .update_rate(Channel::Channel1, UpdateRate::Period)
+        .build_with(Memory::new())
+        .unwrap();
+    let half_period = maestro
+        .set_speed_deg_per_sec(Channel::Channel0, 60f64)
+        .unwrap();
+    let period = maestro
+        .set_speed_deg_per_sec(Channel::Channel1, 60f64)
+        .unwrap();
+    assert_eq!(half_period.value.raw(), 20u16);
+    assert_eq!(period.value.raw(), 40u16);
+
+    let acceleration = maestro
+        .set_accel_deg_per_sec2(Channel::Channel1, 360f64)
+        .unwrap();
+    assert_eq!(acceleration.value.raw(), 58u8);
+}
+
+#[test]
+fn invalid_servo_periods() {
+    let result = Builder::default()
+        .servo_period(Duration::from_millis(30u64))
+        .build_with(Memory::new());
+    assert!(matches!(result, Err(Error::InvalidPeriod(_))));
+
+    let result = Builder::default()
+        .model(Model::MiniMaestro18)
+        .servo_period(Duration::ZERO)
+        .build_with(Memory::new());
+    assert!(matches!(result, Err(Error::InvalidPeriod(_))));
+}
+
+fn micros(micros: f64) -> PulseWidth {
+    PulseWidth::from_micros(micros).unwrap()
+}
+
+/// ### Purpose:
+/// The largest speed and acceleration of a trajectory, sampled every 1ms.
+fn peak_rates<J>(trajectory: &J) -> (f64, f64)
+where
+    J: Trajectory,
+{
+    let step = Duration::from_millis(1u64);
+    let positions = (0u32..=trajectory.duration().as_millis() as u32 + 1u32)
+        .map(|i| trajectory.position(step * i))
+        .collect::<Vec<_>>();
+    let dt = step.as_secs_f64();
+    let speeds = positions
+        .windows(2usize)
+        .map(|w| (w[1usize] - w[0usize]) / dt)
+        .collect::<Vec<_>>();
+    let peak_speed = speeds.iter().fold(0f64, |peak, v| peak.max(v.abs()));
+    let peak_acceleration = speeds
+        .windows(2usize)
+        .map(|w| ((w[1usize] - w[0usize]) / dt).abs())
+        .fold(0f64, f64::max);
+    (peak_speed, peak_acceleration)
+}
+
+#[test]
+fn trapezoidal_profile() {
+    let trajectory =
+        Trapezoidal::new(micros(1000f64), micros(2000f64), 2000f64, 8000f64)
+            .unwrap();
+    assert_eq!(trajectory.duration(), Duration::from_millis(750u64));
+    assert_eq!(trajectory.position(Duration::ZERO), 1000f64);
+    assert_eq!(trajectory.position(Duration::from_millis(250u64)), 1250f64);
+    assert_eq!(trajectory.position(Duration::from_millis(500u64)), 1750f64);
+    assert_eq!(trajectory.position(Duration::from_secs(1u64)), 2000f64);
+
+    let (speed, acceleration) = peak_rates(&trajectory);
+    assert!(speed <= 2000f64 + 1e-6f64);
+    assert!(acceleration <= 8000f64 + 1e-6f64);
+
+    // Too short to reach the speed limit, and backwards.
+    let triangular =
+        Trapezoidal::new(micros(1100f64), micros(1000f64), 2000f64, 8000f64)
+            .unwrap();
+    let duration = triangular.duration().as_secs_f64();
+    assert!((duration - 2f64 * (100f64 / 8000f64).sqrt()).abs() < 1e-9f64);
+    let midpoint = triangular.position(triangular.duration() / 2u32);
+    assert!((midpoint - 1050f64).abs() < 1e-6f64);
+    assert_eq!(triangular.sample(triangular.duration()), 4000u16);
+}
+
+#[test]
+fn s_curve_profile() {
+    let trajectory =
+        SCurve::new(micros(1000f64), micros(2000f64), 2000f64, 8000f64)
+            .unwrap();
+    let trapezoidal =
+        Trapezoidal::new(micros(1000f64), micros(2000f64), 2000f64, 8000f64)
+            .unwrap();
+    assert!(trajectory.duration() > trapezoidal.duration());
+
+    let midpoint = trajectory.position(trajectory.duration() / 2u32);
+    assert!((midpoint - 1500f64).abs() < 1e-6f64);
+    assert_eq!(trajectory.position(trajectory.duration()), 2000f64);
+
+    let (speed, acceleration) = peak_rates(&trajectory);
+    assert!(speed <= 2000f64 + 1e-6f64);
+    assert!(acceleration <= 8000f64 * 1.01f64);
+}
+
+#[test]
+fn spline_through_waypoints() {
+    let waypoints = [
+        (Duration::from_millis(100u64), micros(1000f64)),
+        (Duration::from_millis(600u64), micros(1800f64)),
+        (Duration::from_millis(900u64), micros(1200f64)),
+        (Duration::from_millis(1500u64), micros(1500f64)),
+    ];
+    let spline = Spline::new(&waypoints).unwrap();
+    assert_eq!(spline.duration(), Duration::from_millis(1500u64));
+    assert_eq!(spline.position(Duration::ZERO), 1000f64);
+    waypoints.iter().for_each(|&(time, position)| {
+        assert!((spline.position(time) - position.micros()).abs() < 1e-9f64);
+    });
+
+    let line = Spline::new(&[
+        (Duration::ZERO, micros(1000f64)),
+        (Duration::from_secs(1u64), micros(1500f64)),
+        (Duration::from_secs(2u64), micros(2000f64)),
+    ])
+    .unwrap();
+    let position = line.position(Duration::from_millis(1500u64));
+    assert!((position - 1750f64).abs() < 1e-9f64);
+}
+
+#[test]
+fn invalid_trajectories() {
+    let (start, end) = (micros(1000f64), micros(2000f64));
+    assert!(matches!(
+        Trapezoidal::new(start, end, 0f64, 8000f64),
+        Err(Error::InvalidTrajectory)
+    ));
+    assert!(SCurve::new(start, end, 2000f64, f64::NAN).is_err());
+    assert!(Spline::new(&[(Duration::ZERO, start)]).is_err());
+    assert!(Spline::new(&[
+        (Duration::from_millis(10u64), start),
+        (Duration::from_millis(10u64), end),
+    ])
+    .is_err());
+}
+
+#[test]
+fn streamed_trajectory() {
+    let trajectory =
+        SCurve::new(micros(1000f64), micros(2000f64), 4000f64, 16000f64)
+            .unwrap();
+    let interval = Duration::from_millis(10u64);
+    let mut maestro = Builder::default()
+        .target_limits(Channel::Channel0, 4000u16, 8000u16)
+        .build_with(Emulator::new())
+        .unwrap();
+
+    let mut commanded = vec![];
+    for (time, target) in trajectory.samples(interval) {
+        maestro.set_target(Channel::Channel0, target).unwrap();
+        let emulator = maestro.transport_mut();
+        commanded.push((time, emulator.target(Channel::Channel0).unwrap()));
+        emulator.advance(interval);
+        assert_eq!(target, emulator.position(Channel::Channel0).unwrap());
+    }
+
+    let expected = trajectory
+        .samples(interval)
+        .map(|(time, target)| (time, target.qtr_micros()))
+        .collect::<Vec<_>>();
+    assert_eq!(commanded, expected);
+    assert_eq!(commanded.first(), Some(&(Duration::ZERO, 4000u16)));
+    assert_eq!(commanded.last(), Some(&(trajectory.duration(), 8000u16)));
+    assert!(commanded
+        .windows(2usize)
+        .all(|w| w[0usize].1 <= w[1usize].1));
+}
+
+#[test]
+fn follow_trajectory() {
+    let mut maestro = realtime_maestro();
+    let trajectory =
+        Trapezoidal::new(micros(1000f64), micros(1100f64), 2000f64, 8000f64)
+            .unwrap();
+    let started = std::time::Instant::now();
+    maestro
+        .follow(Channel::Channel0, &trajectory, Duration::from_millis(20u64))
+        .unwrap();
+
+    assert!(started.elapsed() >= trajectory.duration());
+    assert_eq!(
+        maestro.transport().target(Channel::Channel0).unwrap(),
+        4400u16
+    );
+}
+
+/// ### Purpose:
+/// Steps the emulator until every channel has settled, returning the time at
+/// which each one arrived at its target.
+fn arrival_times(
+    maestro: &mut Maestro<Emulator>,
+    channels: &[Channel],
+) -> Vec<Duration> {
+    let tick = Duration::from_millis(10u64);
+    let mut arrivals = vec![None; channels.len()];
+    let mut elapsed = Duration::ZERO;
+    while arrivals.iter().any(Option::is_none)
+        && elapsed < Duration::from_secs(5u64)
+    {
+        maestro.transport_mut().advance(tick);
+        elapsed += tick;
+        let emulator = maestro.transport();
+        channels.iter().zip(arrivals.iter_mut()).for_each(
+            |(&channel, arrival)| {
+                if arrival.is_none()
+                    && emulator.position(channel).unwrap()
+                        == emulator.target(channel).unwrap()
+                {
+                    *arrival = Some(elapsed);
+                };
+            },
+        );
+    }
+    arrivals.into_iter().map(Option::unwrap).collect()
+}
+
+#[test]
+fn synchronized_moves_arrive_together() {
+    let mut maestro = Builder::default().build_with(Emulator::new()).unwrap();
+    maestro
+        .set_targets(Channel::Channel0, &[6000u16, 6000u16, 4000u16])
+        .unwrap();
+    let channels = [Channel::Channel0, Channel::Channel1, Channel::Channel2];
+    let moves = [
+        (channels[0usize], 8000u16),
+        (channels[1usize], 5000u16),
+        (channels[2usize], 4400u16),
+    ];
+    let duration = Duration::from_millis(1000u64);
+    maestro.move_synchronized(&moves, duration).unwrap();
+
+    let emulator = maestro.transport();
+    assert!(channels
+        .iter()
+        .all(|&channel| emulator.acceleration(channel).unwrap() > 0u8));
+    assert!(
+        emulator.speed(Channel::Channel0).unwrap()
+            > emulator.speed(Channel::Channel1).unwrap()
+    );
+    let arrivals = arrival_times(&mut maestro, &channels);
+    let first = arrivals.iter().min().unwrap();
+    let last = arrivals.iter().max().unwrap();
+    assert!(*last - *first <= Duration::from_millis(30u64));
+    assert!(first.abs_diff(duration) <= Duration::from_millis(80u64));
+}
+
+#[test]
+fn synchronized_moves_without_acceleration() {
+    let mut maestro = Builder::default().build_with(Emulator::new()).unwrap();
+    maestro
+        .set_targets(Channel::Channel0, &[4000u16, 4000u16])
+        .unwrap();
+    let moves = [(Channel::Channel0, 8000u16), (Channel::Channel1, 6000u16)];
+    let duration = Duration::from_millis(100u64);
+    maestro.move_synchronized(&moves, duration).unwrap();
+
+    let emulator = maestro.transport();
+    assert_eq!(emulator.acceleration(Channel::Channel0).unwrap(), 0u8);
+    assert_eq!(emulator.speed(Channel::Channel0).unwrap(), 400u16);
+    assert_eq!(emulator.speed(Channel::Channel1).unwrap(), 200u16);
+    let arrivals =
+        arrival_times(&mut maestro, &[Channel::Channel0, Channel::Channel1]);
+    assert_eq!(arrivals, [duration, duration]);
+}
+
+#[test]
+fn synchronized_moves_validated_up_front() {
+    let mut maestro = Builder::default()
+        .channel_mode(Channel::Channel2, ChannelMode::Input)
+        .build_with(Memory::new())
+        .unwrap();
+    let duration = Duration::from_millis(500u64);
+    assert!(matches!(
+        maestro.move_synchronized(
+            &[(Channel::Channel0, 6000u16), (Channel::Channel1, 9000u16)],
+            duration
+        ),
+        Err(Error::InvalidValue(9000u16))
+    ));
+    assert!(matches!(
+        maestro.move_synchronized(&[(Channel::Channel2, 6000u16)], duration),
+        Err(Error::InvalidChannelMode(2u8))
+    ));
+    assert!(matches!(
+        maestro
+            .move_synchronized(&[(Channel::Channel0, 6000u16)], Duration::ZERO),
+        Err(Error::InvalidTrajectory)
+    ));
+    assert!(matches!(
+        maestro.move_synchronized(
+            &[
+                (Channel::Channel0, 6000u16),
+                (Channel::Channel1, 6000u16),
+                (Channel::Channel0, 7000u16)
+            ],
+            duration
+        ),
+        Err(Error::DuplicateChannel(0u8))
+    ));
+    let oversized = [(Channel::Channel0, 6000u16); 64usize];
+    assert!(matches!(
+        maestro.move_synchronized(&oversized, duration),
+        Err(Error::TooManyChannels {
+            count: 64usize,
+            channel_count: 6u8,
+        })
+    ));
+    assert!(maestro.transport().written().is_empty());
+}
+
+const SETTINGS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!--Pololu Maestro servo controller settings file-->
+<UscSettings version="1">
+  <ChannelSettings>
+    <Channel name="" mode="Servo" min="3968" max="8000" />
+  </ChannelSettings>
+  <Sequences>
+    <Sequence name="Open &amp; close">
+      <Frame name="Open" duration="100">4000 5000 0 0 0 0</Frame>
+      <Frame name="Close" duration="200">8000 7000 0 0 0 0</Frame>
+      <Frame name="Rest" duration="100">6000 6000 0 0 0 0</Frame>
+    </Sequence>
+    <Sequence name="Empty" />
+  </Sequences>
+  <Script ScriptDone="true" />
+</UscSettings>"#;
+
+fn frame(name: &str, millis: u64, targets: &[u16]) -> Frame {
+    Frame {
+        name: name.to_string(),
+        duration: Duration::from_millis(millis),
+        targets: targets.to_vec(),
+    }
+}
+
+#[test]
+fn sequences_from_settings() {
+    let sequences = sequence::from_settings_xml(SETTINGS).unwrap();
+    assert_eq!(sequences.len(), 2usize);
+    assert_eq!(sequences[0usize].name, "Open & close");
+    assert_eq!(
+        sequences[0usize].frames[1usize],
+        frame("Close", 200u64, &[8000u16, 7000u16, 0u16, 0u16, 0u16, 0u16])
+    );
+    assert_eq!(sequences[0usize].duration(), Duration::from_millis(400u64));
+    assert_eq!(
+        sequences[1usize],
+        Sequence {
+            name: "Empty".to_string(),
+            frames: vec![],
+        }
+    );
+
+    let exported = sequence::to_settings_xml(&sequences);
+    assert!(exported.contains("<Sequence name=\"Open &amp; close\">"));
+    assert_eq!(sequence::from_settings_xml(&exported).unwrap(), sequences);
+    assert!(sequence::from_settings_xml("<UscSettings />")
+        .unwrap()
+        .is_empty());
+    assert!(sequence::from_settings_xml("<Sequences/>")
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn invalid_sequences() {
+    let invalid = [
+        "<Sequences><Sequence><Frame duration=\"10\">4000 x</Frame></Sequence></Sequences>",
+        "<Sequences><Sequence><Frame>4000</Frame></Sequence></Sequences>",
+        "<Sequences><Frame duration=\"10\">4000</Frame></Sequences>",
+        "<Sequences><Sequence name=\"a\">",
+    ];
+    invalid.iter().for_each(|xml| {
+        assert!(matches!(
+            sequence::from_settings_xml(xml),
+            Err(Error::InvalidSequence(_))
+        ));
+    });
+}
+
+#[test]
+fn sequence_player() {
+    let sequence = sequence::from_settings_xml(SETTINGS)
+        .unwrap()
+        .remove(0usize);
+    let mut maestro = Builder::default()
+        .channel_mode(Channel::Channel2, ChannelMode::Input)
+        .build_with(Emulator::new())
+        .unwrap();
+    let mut player = Player::new(sequence);
+    let targets = |maestro: &Maestro<Emulator>| {
+        let emulator = maestro.transport();
+        (
+            emulator.target(Channel::Channel0).unwrap(),
+            emulator.target(Channel::Channel1).unwrap(),
+        )
+    };
+    let step = Duration::from_millis(50u64);
+
+    player.play();
+    player.advance(&mut maestro, Duration::ZERO).unwrap();
+    assert_eq!(targets(&maestro), (4000u16, 5000u16));
+    player.advance(&mut maestro, step).unwrap();
+    player.advance(&mut maestro, step).unwrap();
+    assert_eq!(targets(&maestro), (8000u16, 7000u16));
+
+    // Paused players hold their frame, but still send seeks.
+    player.pause();
+    player.advance(&mut maestro, step * 10u32).unwrap();
+    assert_eq!(player.position(), Duration::from_millis(100u64));
+    player.seek_frame(2usize);
+    player.advance(&mut maestro, step).unwrap();
+    assert_eq!(targets(&maestro), (6000u16, 6000u16));
+
+    player.play();
+    player.advance(&mut maestro, step * 2u32).unwrap();
+    assert!(!player.is_playing());
+    assert_eq!(player.position(), player.sequence().duration());
+
+    // Looping wraps around to the first frame.
+    player.set_looping(true);
+    player.play();
+    player.advance(&mut maestro, Duration::ZERO).unwrap();
+    assert_eq!(targets(&maestro), (4000u16, 5000u16));
+    player.advance(&mut maestro, step * 9u32).unwrap();
+    assert!(player.is_playing());
+    assert_eq!(player.position(), step);
+    assert_eq!(targets(&maestro), (4000u16, 5000u16));
+
+    player.seek(Duration::from_millis(150u64));
+    player.advance(&mut maestro, Duration::ZERO).unwrap();
+    assert_eq!(targets(&maestro), (8000u16, 7000u16));
+}
+
+#[test]
+fn sequence_player_rejects_invalid_targets() {
+    let sequence = Sequence {
+        name: "Out of range".to_string(),
+        frames: vec![frame("Frame 0", 10u64, &[9000u16])],
+    };
+    let mut maestro = maestro();
+    let mut player = Player::new(sequence);
+    assert!(matches!(
+        player.advance(&mut maestro, Duration::ZERO),
+        Err(Error::InvalidValue(9000u16))
+    ));
+    assert!(maestro.transport().written().is_empty());
+}
+
+#[test]
+fn run_sequence() {
+    let sequence = Sequence {
+        name: "Short".to_string(),
+        frames: vec![
+            frame("Frame 0", 20u64, &[4000u16]),
+            frame("Frame 1", 20u64, &[7000u16]),
+        ],
+    };
+    let mut maestro = realtime_maestro();
+    let mut player = Player::new(sequence);
+    let started = std::time::Instant::now();
+    player
+        .run(&mut maestro, Duration::from_millis(5u64))
+        .unwrap();
+
+    assert!(started.elapsed() >= Duration::from_millis(40u64));
+    assert_eq!(
+        maestro.transport().target(Channel::Channel0).unwrap(),
+        7000u16
+    );
+}
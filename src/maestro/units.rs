@@ -0,0 +1,341 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Typed units for pulse widths, speeds and accelerations.
+//!
+//! The Maestro works in three different raw units: targets and positions in
+//! quarter-us, speeds in `0.25us / 10ms` and accelerations in
+//! `0.25us / 10ms / 80ms`. The newtypes in this module keep those from being
+//! mixed up, and convert to and from physical units.
+//!
+//! Raw [`u16`]s (and [`u8`]s, for accelerations) still convert into these
+//! types as-is, so the [`crate::maestro::Maestro`] methods accept either.
+//!
+//! ### Notes:
+//! The physical units of speeds and accelerations depend on how often the
+//! Maestro updates the channel (see [`crate::maestro::constants::UpdateRate`]).
+//! `from_micros_per_second` and friends assume the default update interval of
+//! `10ms`; the `saturating_` constructors take the interval explicitly.
+//!
+//! ### Examples:
+//! ```rust
+//! # use std::time::Duration;
+//! # use raestro::maestro::units::PulseWidth;
+//! # use raestro::maestro::units::ServoSpeed;
+//! # fn main() -> raestro::Result<()> {
+//! let target = PulseWidth::from_duration(Duration::from_micros(1500))?;
+//! assert_eq!(target.qtr_micros(), 6000u16);
+//!
+//! // 1000us per second
+//! let speed = ServoSpeed::from_micros_per_second(1000f64)?;
+//! assert_eq!(speed.raw(), 40u16);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::maestro::constants::MAX_TARGET;
+
+/// ### Purpose:
+/// The speed (in us per second) of a single raw speed unit.
+const SPEED_UNIT: f64 = 25f64;
+
+/// ### Purpose:
+/// The acceleration (in us per second squared) of a single raw acceleration
+/// unit.
+const ACCEL_UNIT: f64 = 312.5f64;
+
+/// ### Purpose:
+/// The largest raw speed which fits in a command packet.
+pub(crate) const MAX_SPEED: u16 = 0x3fffu16;
+
+/// ### Purpose:
+/// The change in pulse width (in us) of a single raw speed unit, per update.
+const MICROS_PER_UPDATE: f64 = 0.25f64;
+
+/// ### Purpose:
+/// The number of updates a single raw acceleration unit applies over.
+const ACCEL_UPDATES: f64 = 8f64;
+
+/// ### Purpose:
+/// A physical value converted into the Maestro's raw units, and whether it
+/// had to be clamped to fit.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Converted<T> {
+    /// ### Purpose:
+    /// The converted value.
+    pub value: T,
+
+    /// ### Purpose:
+    /// Whether the requested value was larger than the raw field can
+    /// express, and was clamped to the field's largest value.
+    pub saturated: bool,
+}
+
+/// ### Purpose:
+/// A pulse width, as used for targets and positions.
+///
+/// ### Notes:
+/// Stored in quarter-us. A pulse width of `0` turns the channel off.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct PulseWidth(u16);
+
+impl PulseWidth {
+    /// ### Purpose:
+    /// The pulse width which turns a channel off.
+    pub const OFF: Self = Self(0u16);
+
+    /// ### Purpose:
+    /// Creates a pulse width from quarter-us.
+    pub fn from_qtr_micros(qtr_micros: u16) -> crate::Result<Self> {
+        (qtr_micros <= MAX_TARGET)
+            .then_some(Self(qtr_micros))
+            .ok_or(Error::UnitOutOfRange(f64::from(qtr_micros)))
+    }
+
+    /// ### Purpose:
+    /// Creates a pulse width from us, rounded to the nearest quarter-us.
+    pub fn from_micros(micros: f64) -> crate::Result<Self> {
+        let qtr_micros = (micros * 4f64).round();
+        (0f64..=f64::from(MAX_TARGET))
+            .contains(&qtr_micros)
+            .then_some(Self(qtr_micros as u16))
+            .ok_or(Error::UnitOutOfRange(micros))
+    }
+
+    /// ### Purpose:
+    /// Creates a pulse width from a duration, rounded to the nearest
+    /// quarter-us.
+    pub fn from_duration(duration: Duration) -> crate::Result<Self> {
+        Self::from_micros(duration.as_secs_f64() * 1_000_000f64)
+    }
+
+    /// ### Purpose:
+    /// This pulse width in quarter-us (i.e., the Maestro's raw units).
+    pub fn qtr_micros(self) -> u16 {
+        self.0
+    }
+
+    /// ### Purpose:
+    /// This pulse width in us.
+    pub fn micros(self) -> f64 {
+        f64::from(self.0) / 4f64
+    }
+
+    /// ### Purpose:
+    /// This pulse width as a duration.
+    pub fn to_duration(self) -> Duration {
+        Duration::from_nanos(u64::from(self.0) * 250u64)
+    }
+}
+
+impl From<u16> for PulseWidth {
+    /// ### Purpose:
+    /// Wraps a raw value in quarter-us.
+    ///
+    /// ### Notes:
+    /// The value is not checked here; the Maestro checks every target
+    /// against the channel's limits before sending it.
+    fn from(qtr_micros: u16) -> Self {
+        Self(qtr_micros)
+    }
+}
+
+impl From<PulseWidth> for u16 {
+    fn from(pulse_width: PulseWidth) -> Self {
+        pulse_width.0
+    }
+}
+
+impl PartialEq<u16> for PulseWidth {
+    fn eq(&self, other: &u16) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialOrd<u16> for PulseWidth {
+    fn partial_cmp(&self, other: &u16) -> Option<Ordering> {
+        self.0.partial_cmp(other)
+    }
+}
+
+/// ### Purpose:
+/// A speed limit, in units of `0.25us / 10ms`.
+///
+/// ### Notes:
+/// A speed of `0` means the channel's speed is unlimited.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct ServoSpeed(u16);
+
+impl ServoSpeed {
+    /// ### Purpose:
+    /// No speed limit.
+    pub const UNLIMITED: Self = Self(0u16);
+
+    /// ### Purpose:
+    /// Creates a speed limit from its raw value.
+    pub fn from_raw(raw: u16) -> crate::Result<Self> {
+        (raw <= MAX_SPEED)
+            .then_some(Self(raw))
+            .ok_or(Error::UnitOutOfRange(f64::from(raw)))
+    }
+
+    /// ### Purpose:
+    /// Creates a speed limit from us per second.
+    ///
+    /// ### Notes:
+    /// Rounded to the nearest raw unit (`25us/s`), but never down to `0`,
+    /// which would remove the limit entirely.
+    pub fn from_micros_per_second(speed: f64) -> crate::Result<Self> {
+        let raw = (speed / SPEED_UNIT).round().max(1f64);
+        (speed > 0f64 && raw <= f64::from(MAX_SPEED))
+            .then_some(Self(raw as u16))
+            .ok_or(Error::UnitOutOfRange(speed))
+    }
+
+    /// ### Purpose:
+    /// Creates a speed limit from us per second, at the given update
+    /// interval.
+    ///
+    /// ### Notes:
+    /// Rounded as in `from_micros_per_second`. Speeds which are too fast to
+    /// express are clamped to the largest raw speed and reported as
+    /// saturated, rather than rejected.
+    pub fn saturating_from_micros_per_second(
+        speed: f64,
+        update_interval: Duration,
+    ) -> crate::Result<Converted<Self>> {
+        let unit = MICROS_PER_UPDATE / update_interval.as_secs_f64();
+        if !(speed > 0f64 && unit.is_finite()) {
+            return Err(Error::UnitOutOfRange(speed));
+        };
+        let raw = (speed / unit).round().max(1f64);
+        let saturated = raw > f64::from(MAX_SPEED);
+        let value = Self(raw.min(f64::from(MAX_SPEED)) as u16);
+        Ok(Converted { value, saturated })
+    }
+
+    /// ### Purpose:
+    /// This speed limit in the Maestro's raw units.
+    pub fn raw(self) -> u16 {
+        self.0
+    }
+
+    /// ### Purpose:
+    /// This speed limit in us per second, or `None` if it is unlimited.
+    pub fn micros_per_second(self) -> Option<f64> {
+        (self.0 != 0u16).then(|| f64::from(self.0) * SPEED_UNIT)
+    }
+}
+
+impl From<u16> for ServoSpeed {
+    /// ### Purpose:
+    /// Wraps a raw speed.
+    fn from(raw: u16) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<ServoSpeed> for u16 {
+    fn from(speed: ServoSpeed) -> Self {
+        speed.0
+    }
+}
+
+/// ### Purpose:
+/// An acceleration limit, in units of `0.25us / 10ms / 80ms`.
+///
+/// ### Notes:
+/// An acceleration of `0` means the channel's acceleration is unlimited.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct ServoAccel(u8);
+
+impl ServoAccel {
+    /// ### Purpose:
+    /// No acceleration limit.
+    pub const UNLIMITED: Self = Self(0u8);
+
+    /// ### Purpose:
+    /// Creates an acceleration limit from us per second squared.
+    ///
+    /// ### Notes:
+    /// Rounded to the nearest raw unit (`312.5us/s^2`), but never down to
+    /// `0`, which would remove the limit entirely.
+    pub fn from_micros_per_second_squared(
+        acceleration: f64,
+    ) -> crate::Result<Self> {
+        let raw = (acceleration / ACCEL_UNIT).round().max(1f64);
+        (acceleration > 0f64 && raw <= f64::from(u8::MAX))
+            .then_some(Self(raw as u8))
+            .ok_or(Error::UnitOutOfRange(acceleration))
+    }
+
+    /// ### Purpose:
+    /// Creates an acceleration limit from us per second squared, at the
+    /// given update interval.
+    ///
+    /// ### Notes:
+    /// Rounded as in `from_micros_per_second_squared`. Accelerations which
+    /// are too fast to express are clamped to the largest raw acceleration
+    /// and reported as saturated, rather than rejected.
+    pub fn saturating_from_micros_per_second_squared(
+        acceleration: f64,
+        update_interval: Duration,
+    ) -> crate::Result<Converted<Self>> {
+        let interval = update_interval.as_secs_f64();
+        let unit = MICROS_PER_UPDATE / (interval * interval * ACCEL_UPDATES);
+        if !(acceleration > 0f64 && unit.is_finite()) {
+            return Err(Error::UnitOutOfRange(acceleration));
+        };
+        let raw = (acceleration / unit).round().max(1f64);
+        let saturated = raw > f64::from(u8::MAX);
+        let value = Self(raw.min(f64::from(u8::MAX)) as u8);
+        Ok(Converted { value, saturated })
+    }
+
+    /// ### Purpose:
+    /// This acceleration limit in the Maestro's raw units.
+    pub fn raw(self) -> u8 {
+        self.0
+    }
+
+    /// ### Purpose:
+    /// This acceleration limit in us per second squared, or `None` if it is
+    /// unlimited.
+    pub fn micros_per_second_squared(self) -> Option<f64> {
+        (self.0 != 0u8).then(|| f64::from(self.0) * ACCEL_UNIT)
+    }
+
+    /// ### Purpose:
+    /// This acceleration limit in us per second squared at the given update
+    /// interval, or `None` if it is unlimited.
+    pub fn micros_per_second_squared_at(
+        self,
+        update_interval: Duration,
+    ) -> Option<f64> {
+        let interval = update_interval.as_secs_f64();
+        let unit = MICROS_PER_UPDATE / (interval * interval * ACCEL_UPDATES);
+        (self.0 != 0u8).then(|| f64::from(self.0) * unit)
+    }
+}
+
+impl From<u8> for ServoAccel {
+    /// ### Purpose:
+    /// Wraps a raw acceleration.
+    fn from(raw: u8) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<ServoAccel> for u8 {
+    fn from(acceleration: ServoAccel) -> Self {
+        acceleration.0
+    }
+}
@@ -8,9 +8,11 @@
 #[cfg(test)]
 mod tests;
 
+use crate::maestro::internals::CRC7_POLY;
+
 /// Given a `u8`, clears the top bit by applying a
 /// mask to it.
-pub(super) fn mask_byte(byte: u8) -> u8 {
+pub(crate) fn mask_byte(byte: u8) -> u8 {
     let top_mask: u8 = 0x7fu8;
     byte & top_mask
 }
@@ -22,15 +24,15 @@ pub(super) fn mask_byte(byte: u8) -> u8 {
 ///
 /// Given a 16-bit integer, execute the following:
 /// 1. take low order bits 0 to 6, pad with a 0 in
-/// the 7th position. This is the lower byte. 2.
-/// take upper order bits 7 to 13, shift it down 7
-/// bits, pad with a 0 in the 7th position. This
-/// is the higher byte.
+///    the 7th position. This is the lower byte.
+/// 2. take upper order bits 7 to 13, shift it
+///    down 7 bits, pad with a 0 in the 7th
+///    position. This is the higher byte.
 ///
 /// # Note
 /// This leaves the top 2 bits unused. This is as
 /// is required by the Pololu-Protocol.
-pub(super) fn microsec_to_target(microsec: u16) -> (u8, u8) {
+pub(crate) fn microsec_to_target(microsec: u16) -> (u8, u8) {
     let down_shift = 7usize;
 
     let lower = mask_byte(microsec as u8);
@@ -38,3 +40,18 @@ pub(super) fn microsec_to_target(microsec: u16) -> (u8, u8) {
 
     (lower, upper)
 }
+
+/// Computes the Maestro's 7-bit CRC over the
+/// given command packet.
+///
+/// Each byte is processed least significant bit
+/// first, using the polynomial `x^7 + x^3 + 1`
+/// (Section 5.d of the Maestro manual).
+pub(crate) fn crc7(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |crc, &byte| {
+        (0usize..8usize).fold(crc ^ byte, |crc, _| match crc & 0x01u8 {
+            0u8 => crc >> 1usize,
+            _ => (crc ^ CRC7_POLY) >> 1usize,
+        })
+    })
+}
@@ -38,3 +38,20 @@ fn simple_short_to_target_test() -> () {
 
     assert_eq!(microsec_to_target(target), expected);
 }
+
+#[test]
+fn crc7_test() {
+    // The example given in Section 5.d of the Maestro manual.
+    let packet: [u8; 2usize] = [0x83u8, 0x01u8];
+
+    assert_eq!(crc7(&packet), 0x17u8);
+}
+
+#[test]
+fn crc7_appended_test() {
+    let mut packet = vec![0xaau8, 0x0cu8, 0x04u8, 0x02u8, 0x70u8, 0x2eu8];
+    let crc = crc7(&packet);
+    packet.push(crc);
+
+    assert_eq!(crc7(&packet), 0x00u8);
+}
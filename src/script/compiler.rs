@@ -0,0 +1,461 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Compiles Maestro script source into bytecode.
+
+use std::collections::HashMap;
+
+use crate::errors::Error;
+use crate::script::opcode;
+use crate::script::opcode::Opcode;
+use crate::script::Program;
+use crate::script::Subroutine;
+
+/// ### Purpose:
+/// A single word of script source, and where it was found.
+#[derive(Copy, Clone, Debug)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl Token<'_> {
+    fn error(&self, message: String) -> Error {
+        Error::ScriptSyntax {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+}
+
+/// ### Purpose:
+/// A control structure which has been opened, but not yet closed.
+#[derive(Debug)]
+enum Block<'a> {
+    /// A `BEGIN`, with its start address and the `WHILE` jumps out of it
+    /// which still need patching.
+    Begin {
+        token: Token<'a>,
+        start: u16,
+        whiles: Vec<usize>,
+    },
+
+    /// An `IF` (or `ELSE`), with the jump which still needs patching.
+    If {
+        token: Token<'a>,
+        jump: usize,
+        has_else: bool,
+    },
+}
+
+/// ### Purpose:
+/// What a jump or call address needs to be filled in with.
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Target {
+    Label,
+    Subroutine,
+}
+
+/// ### Purpose:
+/// An address which can only be filled in once the whole script has been
+/// compiled.
+#[derive(Debug)]
+struct Fixup<'a> {
+    token: Token<'a>,
+    at: usize,
+    target: Target,
+}
+
+#[derive(Default)]
+struct Compiler<'a> {
+    numbers: HashMap<String, usize>,
+    bytecode: Vec<u8>,
+    labels: HashMap<String, u16>,
+    subroutines: Vec<Subroutine>,
+    fixups: Vec<Fixup<'a>>,
+    blocks: Vec<Block<'a>>,
+}
+
+/// ### Purpose:
+/// Compiles Maestro script source into a [`Program`].
+///
+/// ### Notes:
+/// See [`crate::script`] for the language. Errors are reported as
+/// [`Error::ScriptSyntax`], with the (1-based) line and column of the word
+/// which caused them.
+///
+/// Like the Maestro Control Center, subroutines `0` to `127` are called by a
+/// single byte (see [`opcode::SHORT_CALL`]), and any others through
+/// [`Opcode::Call`].
+pub fn compile(source: &str) -> crate::Result<Program> {
+    let tokens = tokenize(source);
+    let mut compiler = Compiler {
+        numbers: number_subroutines(&tokens),
+        ..Compiler::default()
+    };
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        compiler.word(token, &mut tokens)?;
+    }
+    compiler.finish()
+}
+
+/// ### Purpose:
+/// Numbers the subroutines in the order they are defined, so that calls can
+/// be compiled before the subroutine they call.
+fn number_subroutines(tokens: &[Token<'_>]) -> HashMap<String, usize> {
+    let mut numbers = HashMap::new();
+    tokens
+        .windows(2usize)
+        .filter(|pair| pair[0usize].text.eq_ignore_ascii_case("SUB"))
+        .for_each(|pair| {
+            let number = numbers.len();
+            numbers
+                .entry(pair[1usize].text.to_lowercase())
+                .or_insert(number);
+        });
+    numbers
+}
+
+/// ### Purpose:
+/// Splits source into whitespace-separated words, dropping `#` comments.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    source
+        .lines()
+        .enumerate()
+        .flat_map(|(index, line)| {
+            let code = line.split('#').next().unwrap_or_default();
+            let mut tokens = vec![];
+            let mut start = None;
+            for (offset, c) in code.char_indices().chain([(code.len(), ' ')]) {
+                match (c.is_whitespace(), start) {
+                    (false, None) => start = Some(offset),
+                    (true, Some(begin)) => {
+                        tokens.push(Token {
+                            text: &code[begin..offset],
+                            line: index + 1usize,
+                            column: code[..begin].chars().count() + 1usize,
+                        });
+                        start = None;
+                    },
+                    _ => (),
+                };
+            }
+            tokens
+        })
+        .collect()
+}
+
+impl<'a> Compiler<'a> {
+    fn word<I>(&mut self, token: Token<'a>, tokens: &mut I) -> crate::Result<()>
+    where
+        I: Iterator<Item = Token<'a>>,
+    {
+        let text = token.text;
+        if let Some(value) = parse_number(text) {
+            let value = value.ok_or_else(|| {
+                token.error(format!("{text} is not a valid number"))
+            })?;
+            return self.literal(token, value);
+        };
+        if let Some(label) = text.strip_suffix(':') {
+            check_name(token, label)?;
+            let address = self.address(token)?;
+            let previous = self.labels.insert(label.to_lowercase(), address);
+            return match previous {
+                Some(_) => {
+                    Err(token
+                        .error(format!("label {label} is already defined")))
+                },
+                None => Ok(()),
+            };
+        };
+        match text.to_uppercase().as_str() {
+            "BEGIN" => {
+                let start = self.address(token)?;
+                self.blocks.push(Block::Begin {
+                    token,
+                    start,
+                    whiles: vec![],
+                });
+            },
+            "WHILE" => {
+                if !matches!(self.blocks.last(), Some(Block::Begin { .. })) {
+                    return Err(token
+                        .error("WHILE must be inside of a BEGIN".to_string()));
+                };
+                let jump = self.jump(Opcode::JumpZ);
+                if let Some(Block::Begin { whiles, .. }) =
+                    self.blocks.last_mut()
+                {
+                    whiles.push(jump);
+                };
+            },
+            "REPEAT" => match self.blocks.pop() {
+                Some(Block::Begin { start, whiles, .. }) => {
+                    let jump = self.jump(Opcode::Jump);
+                    self.patch(jump, start);
+                    let end = self.address(token)?;
+                    whiles.into_iter().for_each(|at| self.patch(at, end));
+                },
+                _ => {
+                    return Err(
+                        token.error("REPEAT without a BEGIN".to_string())
+                    )
+                },
+            },
+            "IF" => {
+                let jump = self.jump(Opcode::JumpZ);
+                self.blocks.push(Block::If {
+                    token,
+                    jump,
+                    has_else: false,
+                });
+            },
+            "ELSE" => match self.blocks.pop() {
+                Some(Block::If {
+                    jump,
+                    has_else: false,
+                    ..
+                }) => {
+                    let skip = self.jump(Opcode::Jump);
+                    let address = self.address(token)?;
+                    self.patch(jump, address);
+                    self.blocks.push(Block::If {
+                        token,
+                        jump: skip,
+                        has_else: true,
+                    });
+                },
+                _ => return Err(token.error("ELSE without an IF".to_string())),
+            },
+            "ENDIF" => match self.blocks.pop() {
+                Some(Block::If { jump, .. }) => {
+                    let address = self.address(token)?;
+                    self.patch(jump, address);
+                },
+                _ => return Err(token.error("ENDIF without an IF".to_string())),
+            },
+            "GOTO" => {
+                let label = next_name(token, tokens)?;
+                let at = self.jump(Opcode::Jump);
+                self.fixups.push(Fixup {
+                    token: label,
+                    at,
+                    target: Target::Label,
+                });
+            },
+            "SUB" => {
+                if let Some(block) = self.blocks.last() {
+                    let open = match block {
+                        Block::Begin { token, .. }
+                        | Block::If { token, .. } => token,
+                    };
+                    return Err(token.error(format!(
+                        "SUB cannot be inside of the {} on line {}",
+                        open.text.to_uppercase(),
+                        open.line
+                    )));
+                };
+                let name = next_name(token, tokens)?;
+                let address = self.address(token)?;
+                if self.subroutine(name.text).is_some() {
+                    return Err(name.error(format!(
+                        "subroutine {} is already defined",
+                        name.text
+                    )));
+                };
+                self.subroutines.push(Subroutine {
+                    name: name.text.to_string(),
+                    address,
+                });
+            },
+            _ => match Opcode::from_keyword(text) {
+                Some(opcode) => self.bytecode.push(opcode as u8),
+                None => {
+                    check_name(token, text)?;
+                    let number = self
+                        .numbers
+                        .get(&text.to_lowercase())
+                        .and_then(|&number| u8::try_from(number).ok())
+                        .and_then(|number| {
+                            opcode::SHORT_CALL.checked_add(number)
+                        });
+                    match number {
+                        Some(call) => self.bytecode.push(call),
+                        None => {
+                            let at = self.jump(Opcode::Call);
+                            self.fixups.push(Fixup {
+                                token,
+                                at,
+                                target: Target::Subroutine,
+                            });
+                        },
+                    };
+                },
+            },
+        };
+        Ok(())
+    }
+
+    /// ### Purpose:
+    /// Checks that every block was closed, appends a final `QUIT` and fills
+    /// in every jump and call.
+    fn finish(mut self) -> crate::Result<Program> {
+        if let Some(block) = self.blocks.first() {
+            let (token, closer) = match block {
+                Block::Begin { token, .. } => (token, "REPEAT"),
+                Block::If { token, .. } => (token, "ENDIF"),
+            };
+            return Err(token.error(format!(
+                "{} without an {closer}",
+                token.text.to_uppercase()
+            )));
+        };
+        self.bytecode.push(Opcode::Quit as u8);
+        let fixups = std::mem::take(&mut self.fixups);
+        for Fixup { token, at, target } in fixups {
+            let address = match target {
+                Target::Label => {
+                    self.labels.get(&token.text.to_lowercase()).copied()
+                },
+                Target::Subroutine => self
+                    .subroutine(token.text)
+                    .map(|index| self.subroutines[index].address),
+            };
+            let address = address.ok_or_else(|| {
+                let kind = match target {
+                    Target::Label => "label",
+                    Target::Subroutine => "subroutine (or command)",
+                };
+                token.error(format!("undefined {kind} {}", token.text))
+            })?;
+            self.patch(at, address);
+        }
+        Ok(Program {
+            bytecode: self.bytecode,
+            subroutines: self.subroutines,
+        })
+    }
+
+    /// ### Purpose:
+    /// Emits the shortest literal instruction which holds the given value.
+    fn literal(&mut self, token: Token<'_>, value: i32) -> crate::Result<()> {
+        match value {
+            0i32..=0xffi32 => {
+                self.bytecode.push(Opcode::Literal8 as u8);
+                self.bytecode.push(value as u8);
+            },
+            -0x8000i32..=0xffffi32 => {
+                self.bytecode.push(Opcode::Literal as u8);
+                self.bytecode.extend((value as u16).to_le_bytes());
+            },
+            _ => {
+                return Err(token.error(format!(
+                    "{value} does not fit in a 16-bit literal"
+                )))
+            },
+        };
+        Ok(())
+    }
+
+    /// ### Purpose:
+    /// Emits a jump (or call) instruction with a blank address, returning
+    /// the position of that address.
+    fn jump(&mut self, opcode: Opcode) -> usize {
+        self.bytecode.push(opcode as u8);
+        let at = self.bytecode.len();
+        self.bytecode.extend([0u8, 0u8]);
+        at
+    }
+
+    fn patch(&mut self, at: usize, address: u16) {
+        self.bytecode[at..at + 2usize].copy_from_slice(&address.to_le_bytes());
+    }
+
+    /// ### Purpose:
+    /// The address of the next instruction.
+    fn address(&self, token: Token<'_>) -> crate::Result<u16> {
+        u16::try_from(self.bytecode.len())
+            .map_err(|_| token.error("the script is too long".to_string()))
+    }
+
+    fn subroutine(&self, name: &str) -> Option<usize> {
+        self.subroutines
+            .iter()
+            .position(|subroutine| subroutine.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// ### Purpose:
+/// Parses a decimal or `0x` hexadecimal number, with an optional leading
+/// `-`.
+///
+/// ### Notes:
+/// Returns `None` if the word is not a number at all, and `Some(None)` if it
+/// looks like a number but cannot be parsed.
+fn parse_number(text: &str) -> Option<Option<i32>> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i32::from_str_radix(hex, 16u32),
+        None => digits.parse::<i32>(),
+    };
+    Some(
+        value
+            .ok()
+            .map(|value| if negative { -value } else { value }),
+    )
+}
+
+/// ### Purpose:
+/// Checks that a label or subroutine name is a valid identifier which is
+/// not already a keyword.
+fn check_name(token: Token<'_>, name: &str) -> crate::Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(token.error(format!("unknown word {name}")));
+    };
+    let reserved = [
+        "BEGIN", "WHILE", "REPEAT", "IF", "ELSE", "ENDIF", "GOTO", "SUB",
+    ];
+    match reserved
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(name))
+        || Opcode::from_keyword(name).is_some()
+    {
+        true => Err(token.error(format!("{name} is a reserved word"))),
+        false => Ok(()),
+    }
+}
+
+/// ### Purpose:
+/// Takes the name following a `GOTO` or `SUB`.
+fn next_name<'a, I>(
+    keyword: Token<'a>,
+    tokens: &mut I,
+) -> crate::Result<Token<'a>>
+where
+    I: Iterator<Item = Token<'a>>,
+{
+    let name = tokens.next().ok_or_else(|| {
+        keyword.error(format!("{} needs a name", keyword.text.to_uppercase()))
+    })?;
+    check_name(name, name.text)?;
+    Ok(name)
+}
@@ -0,0 +1,511 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Turns Maestro script bytecode back into script source.
+//!
+//! Jumps laid out the way the compiler lays out `begin ... while ... repeat`
+//! and `if ... else ... endif` are turned back into those structures; any
+//! other jumps become labels and `goto`s. Subroutines without a name are named
+//! after their address (i.e., `sub_0009`), as are labels (i.e.,
+//! `label_0012`).
+//!
+//! Disassembling bytecode produced by [`crate::script::compile`] gives source
+//! which compiles back into the very same bytecode. Other bytecode (i.e.,
+//! using `LITERAL_N`) gives source which compiles into equivalent bytecode.
+//!
+//! ### Examples:
+//! ```rust
+//! # use raestro::script;
+//! # use raestro::script::disassembler;
+//! # fn main() -> raestro::Result<()> {
+//! let program = script::compile("1 if 4000 else 8000 endif 0 servo")?;
+//! let source = disassembler::disassemble(&program)?;
+//!
+//! assert_eq!(source, "1\nif\n    4000\nelse\n    8000\nendif\n0 servo\n");
+//! assert_eq!(script::compile(&source)?, program);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::errors::Error;
+use crate::script::opcode;
+use crate::script::opcode::Opcode;
+use crate::script::Program;
+use crate::script::Subroutine;
+
+/// ### Purpose:
+/// The text each nesting level is indented by.
+const INDENT: &str = "    ";
+
+/// ### Purpose:
+/// A single decoded instruction.
+#[derive(Clone, Debug)]
+struct Instruction {
+    address: u16,
+    opcode: Opcode,
+    values: Vec<i16>,
+    target: Option<u16>,
+}
+
+/// ### Purpose:
+/// A control structure recovered from the bytecode's jumps.
+#[derive(Clone, Debug)]
+enum Block {
+    /// `begin` at `start`, `while` at each of `whiles`, and `repeat` at
+    /// `repeat`.
+    Loop {
+        start: u16,
+        whiles: Vec<u16>,
+        repeat: u16,
+    },
+
+    /// `if` at `start`, an optional `else` and `endif` at `end`.
+    If {
+        start: u16,
+        otherwise: Option<u16>,
+        end: u16,
+    },
+}
+
+impl Block {
+    fn start(&self) -> u16 {
+        match *self {
+            Self::Loop { start, .. } | Self::If { start, .. } => start,
+        }
+    }
+
+    /// ### Purpose:
+    /// The address right after the block.
+    fn end(&self) -> u16 {
+        match *self {
+            Self::Loop { repeat, .. } => repeat + 3u16,
+            Self::If { end, .. } => end,
+        }
+    }
+
+    /// ### Purpose:
+    /// The jumps which make up the block (other than its opening jump).
+    fn markers(&self) -> Vec<u16> {
+        match self {
+            Self::Loop { whiles, repeat, .. } => {
+                whiles.iter().copied().chain([*repeat]).collect()
+            },
+            Self::If { otherwise, .. } => otherwise.iter().copied().collect(),
+        }
+    }
+
+    /// ### Purpose:
+    /// Every jump instruction the block is made of.
+    fn jumps(&self) -> Vec<u16> {
+        let mut jumps = self.markers();
+        if let Self::If { start, .. } = *self {
+            jumps.push(start);
+        };
+        jumps
+    }
+
+    fn contains(&self, address: u16) -> bool {
+        (self.start()..self.end()).contains(&address)
+    }
+
+    /// ### Purpose:
+    /// Whether this block can be written alongside another one.
+    fn nests_with(&self, other: &Self) -> bool {
+        let (start, end) = (self.start(), self.end());
+        let (other_start, other_end) = (other.start(), other.end());
+        let disjoint = end <= other_start || other_end <= start;
+        let inside = |outer: &Self, inner: &Self| {
+            (outer.start(), outer.end()) != (inner.start(), inner.end())
+                && outer.start() <= inner.start()
+                && inner.end() <= outer.end()
+                && !outer.markers().iter().any(|&at| inner.contains(at))
+        };
+        let shared = self.jumps().iter().any(|at| other.jumps().contains(at));
+        !shared && (disjoint || inside(self, other) || inside(other, self))
+    }
+}
+
+/// ### Purpose:
+/// Builds the subroutine table of the given bytecode, from the subroutine
+/// addresses stored alongside it.
+///
+/// ### Notes:
+/// The bytecode only calls subroutines `0` to `127` by number (see
+/// [`opcode::SHORT_CALL`]), so their addresses must be given, in order (as
+/// the Maestro Control Center uploads them with the script). Those
+/// subroutines keep their numbers, which are the ones to use with
+/// [`crate::maestro::Maestro::restart_script_at`]. Any subroutines which are
+/// only called through [`Opcode::Call`] follow them, in order of address.
+///
+/// Every subroutine is named after its address (i.e., `sub_0009`). Fails
+/// with [`Error::InvalidBytecode`] if the bytecode calls a subroutine which
+/// is not in the table, or if a subroutine does not start on an instruction.
+pub fn subroutines(
+    bytecode: &[u8],
+    addresses: &[u16],
+) -> crate::Result<Vec<Subroutine>> {
+    let called: BTreeSet<u16> = decode(bytecode, addresses)?
+        .into_iter()
+        .filter(|instruction| instruction.opcode == Opcode::Call)
+        .filter_map(|instruction| instruction.target)
+        .filter(|target| !addresses.contains(target))
+        .collect();
+    let subroutines = addresses
+        .iter()
+        .copied()
+        .chain(called)
+        .map(|address| Subroutine {
+            name: format!("sub_{address:04x}"),
+            address,
+        })
+        .collect();
+    Ok(subroutines)
+}
+
+/// ### Purpose:
+/// Disassembles a program into script source.
+///
+/// ### Notes:
+/// Subroutines are named after the program's subroutine table. Any
+/// subroutines which are called but not in the table are named after their
+/// address. The final `quit` the compiler appends is left out.
+pub fn disassemble(program: &Program) -> crate::Result<String> {
+    let addresses: Vec<u16> = program
+        .subroutines
+        .iter()
+        .map(|subroutine| subroutine.address)
+        .collect();
+    let instructions = decode(&program.bytecode, &addresses)?;
+    let mut names: HashMap<u16, String> = HashMap::new();
+    program
+        .subroutines
+        .iter()
+        .chain(&subroutines(&program.bytecode, &addresses)?)
+        .for_each(|subroutine| {
+            names
+                .entry(subroutine.address)
+                .or_insert_with(|| subroutine.name.clone());
+        });
+    let blocks = structure(&instructions, &names);
+    let kinds: HashMap<u16, &str> = blocks
+        .iter()
+        .flat_map(|block| match block {
+            Block::Loop { whiles, repeat, .. } => whiles
+                .iter()
+                .map(|&at| (at, "while"))
+                .chain([(*repeat, "repeat")])
+                .collect::<Vec<_>>(),
+            Block::If {
+                start, otherwise, ..
+            } => otherwise
+                .iter()
+                .map(|&at| (at, "else"))
+                .chain([(*start, "if")])
+                .collect(),
+        })
+        .collect();
+    let labels: BTreeSet<u16> = instructions
+        .iter()
+        .filter(|instruction| {
+            matches!(instruction.opcode, Opcode::Jump | Opcode::JumpZ)
+                && !kinds.contains_key(&instruction.address)
+        })
+        .filter_map(|instruction| instruction.target)
+        .collect();
+
+    let mut writer = Writer::default();
+    let end = program.bytecode.len() as u16;
+    let last = instructions.len().saturating_sub(1usize);
+    for (index, instruction) in instructions.iter().enumerate() {
+        let address = instruction.address;
+        writer.open(address, &blocks, &names, &labels);
+        match (instruction.opcode, kinds.get(&address)) {
+            (Opcode::Quit, _) if index == last && address + 1u16 == end => (),
+            (_, Some(&kind)) => writer.structure(kind),
+            (Opcode::Jump, None) => {
+                let target = instruction.target.unwrap_or_default();
+                writer.line(format!("goto label_{target:04x}"));
+            },
+            (Opcode::JumpZ, None) => {
+                let target = instruction.target.unwrap_or_default();
+                writer.structure("if");
+                writer.structure("else");
+                writer.line(format!("goto label_{target:04x}"));
+                writer.structure("endif");
+            },
+            (Opcode::Call, None) => {
+                let target = instruction.target.unwrap_or_default();
+                writer.word(names[&target].clone());
+                writer.flush();
+            },
+            (
+                Opcode::Literal
+                | Opcode::Literal8
+                | Opcode::LiteralN
+                | Opcode::Literal8N,
+                None,
+            ) => instruction
+                .values
+                .iter()
+                .for_each(|value| writer.word(value.to_string())),
+            (opcode, None) => {
+                let word = match opcode.symbol() {
+                    Some(symbol) => symbol.to_string(),
+                    None => opcode.name().to_lowercase(),
+                };
+                writer.word(word);
+                writer.flush();
+            },
+        };
+    }
+    writer.open(end, &blocks, &names, &labels);
+    writer.flush();
+    Ok(writer.source)
+}
+
+/// ### Purpose:
+/// Decodes bytecode into its instructions, given the addresses of its
+/// subroutines.
+///
+/// ### Notes:
+/// Single-byte calls are decoded as [`Opcode::Call`], with the address of the
+/// subroutine they call as their target.
+///
+/// Fails with [`Error::InvalidBytecode`] at the first unknown opcode,
+/// truncated instruction, call to a subroutine which is not in the table, or
+/// jump (or call) into the middle of an instruction.
+fn decode(
+    bytecode: &[u8],
+    addresses: &[u16],
+) -> crate::Result<Vec<Instruction>> {
+    if bytecode.len() > u16::MAX as usize {
+        return Err(Error::InvalidBytecode(u16::MAX));
+    };
+    let mut instructions = vec![];
+    let mut offset = 0usize;
+    while offset < bytecode.len() {
+        let address = offset as u16;
+        let invalid = || Error::InvalidBytecode(address);
+        if let Some(subroutine) = opcode::short_call(bytecode[offset]) {
+            let target = addresses
+                .get(usize::from(subroutine))
+                .copied()
+                .ok_or_else(invalid)?;
+            instructions.push(Instruction {
+                address,
+                opcode: Opcode::Call,
+                values: vec![],
+                target: Some(target),
+            });
+            offset += 1usize;
+            continue;
+        };
+        let opcode =
+            Opcode::try_from(bytecode[offset]).map_err(|_| invalid())?;
+        let next = bytecode.get(offset + 1usize).copied().unwrap_or_default();
+        let size = opcode.operand_size(next);
+        let operands = bytecode
+            .get(offset + 1usize..offset + 1usize + size)
+            .ok_or_else(invalid)?;
+        let word = |at: usize| {
+            i16::from_le_bytes([operands[at], operands[at + 1usize]])
+        };
+        let (values, target) = match opcode {
+            Opcode::Literal => (vec![word(0usize)], None),
+            Opcode::Literal8 => (vec![i16::from(operands[0usize])], None),
+            Opcode::LiteralN => {
+                let values = (1usize..size).step_by(2usize).map(word).collect();
+                (values, None)
+            },
+            Opcode::Literal8N => {
+                let values = operands[1usize..]
+                    .iter()
+                    .map(|&value| i16::from(value))
+                    .collect();
+                (values, None)
+            },
+            Opcode::Jump | Opcode::JumpZ | Opcode::Call => {
+                (vec![], Some(word(0usize) as u16))
+            },
+            _ => (vec![], None),
+        };
+        instructions.push(Instruction {
+            address,
+            opcode,
+            values,
+            target,
+        });
+        offset += 1usize + size;
+    }
+    let boundaries: BTreeSet<u16> = instructions
+        .iter()
+        .map(|instruction| instruction.address)
+        .chain([bytecode.len() as u16])
+        .collect();
+    match instructions.iter().find(|instruction| {
+        instruction
+            .target
+            .is_some_and(|target| !boundaries.contains(&target))
+    }) {
+        Some(instruction) => Err(Error::InvalidBytecode(instruction.address)),
+        None => Ok(instructions),
+    }
+}
+
+/// ### Purpose:
+/// Recovers the control structures laid out by the compiler, keeping only
+/// those which can be written back out (i.e., which nest properly and do not
+/// contain the start of a subroutine).
+fn structure(
+    instructions: &[Instruction],
+    subroutines: &HashMap<u16, String>,
+) -> Vec<Block> {
+    let by_address: BTreeMap<u16, &Instruction> = instructions
+        .iter()
+        .map(|instruction| (instruction.address, instruction))
+        .collect();
+    let jumps = |opcode: Opcode| {
+        instructions
+            .iter()
+            .filter(move |instruction| instruction.opcode == opcode)
+            .filter_map(|instruction| {
+                Some((instruction.address, instruction.target?))
+            })
+    };
+    let mut candidates: Vec<Block> = jumps(Opcode::Jump)
+        .filter(|&(repeat, start)| start <= repeat)
+        .map(|(repeat, start)| Block::Loop {
+            start,
+            whiles: jumps(Opcode::JumpZ)
+                .filter(|&(at, target)| {
+                    (start..repeat).contains(&at) && target == repeat + 3u16
+                })
+                .map(|(at, _)| at)
+                .collect(),
+            repeat,
+        })
+        .collect();
+    candidates.extend(
+        jumps(Opcode::JumpZ)
+            .filter(|&(start, target)| start < target)
+            .flat_map(|(start, target)| {
+                // Both readings are tried, `else` first: a plain `if` whose
+                // body ends in a forward `goto` looks just like an `else`.
+                let otherwise = target
+                    .checked_sub(3u16)
+                    .filter(|&at| at > start)
+                    .and_then(|at| by_address.get(&at).copied())
+                    .filter(|jump| jump.opcode == Opcode::Jump)
+                    .and_then(|jump| {
+                        let end = jump.target.filter(|&end| end >= target)?;
+                        Some(Block::If {
+                            start,
+                            otherwise: Some(jump.address),
+                            end,
+                        })
+                    });
+                otherwise.into_iter().chain([Block::If {
+                    start,
+                    otherwise: None,
+                    end: target,
+                }])
+            }),
+    );
+    candidates.sort_by_key(|block| (block.start(), u16::MAX - block.end()));
+    candidates.into_iter().fold(vec![], |mut blocks, block| {
+        let splits_subroutine = subroutines
+            .keys()
+            .any(|&address| block.start() < address && address < block.end());
+        if !splits_subroutine
+            && blocks.iter().all(|other| block.nests_with(other))
+        {
+            blocks.push(block);
+        };
+        blocks
+    })
+}
+
+/// ### Purpose:
+/// Builds up indented source, a line at a time.
+#[derive(Default)]
+struct Writer {
+    source: String,
+    line: Vec<String>,
+    depth: usize,
+}
+
+impl Writer {
+    fn word(&mut self, word: String) {
+        self.line.push(word);
+    }
+
+    fn flush(&mut self) {
+        if !self.line.is_empty() {
+            let words = std::mem::take(&mut self.line).join(" ");
+            let _ =
+                writeln!(self.source, "{}{words}", INDENT.repeat(self.depth));
+        };
+    }
+
+    fn line(&mut self, line: String) {
+        self.flush();
+        self.word(line);
+        self.flush();
+    }
+
+    /// ### Purpose:
+    /// Writes a structure keyword, indenting the lines after it as needed.
+    fn structure(&mut self, keyword: &str) {
+        self.flush();
+        if matches!(keyword, "else" | "endif" | "repeat") {
+            self.depth = self.depth.saturating_sub(1usize);
+        };
+        self.line(keyword.to_string());
+        if matches!(keyword, "begin" | "if" | "else") {
+            self.depth += 1usize;
+        };
+    }
+
+    /// ### Purpose:
+    /// Writes everything which comes right before the given address: the
+    /// `endif`s closing there, then any subroutine, label or `begin`s.
+    fn open(
+        &mut self,
+        address: u16,
+        blocks: &[Block],
+        subroutines: &HashMap<u16, String>,
+        labels: &BTreeSet<u16>,
+    ) {
+        blocks
+            .iter()
+            .rev()
+            .filter(|block| {
+                matches!(block, Block::If { end, .. } if *end == address)
+            })
+            .for_each(|_| self.structure("endif"));
+        if let Some(name) = subroutines.get(&address) {
+            self.flush();
+            self.depth = 0usize;
+            self.line(format!("sub {name}"));
+            self.depth = 1usize;
+        };
+        if labels.contains(&address) {
+            self.line(format!("label_{address:04x}:"));
+        };
+        blocks
+            .iter()
+            .filter(|block| {
+                matches!(block, Block::Loop { start, .. } if *start == address)
+            })
+            .for_each(|_| self.structure("begin"));
+    }
+}
@@ -0,0 +1,625 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Runs compiled Maestro scripts offline, against an [`Emulator`].
+//!
+//! The [`Interpreter`] executes a [`Program`]'s bytecode the way the Maestro
+//! does, driving the emulator's channels. Scripts are held to the emulated
+//! model's stack depths (see [`Model::script_stack_depth`] and
+//! [`Model::script_call_stack_depth`]), and a script which overflows or
+//! underflows a stack, or whose program counter leaves the bytecode, is
+//! stopped and raises the matching [`ErrorValues`] on the emulator (just as
+//! the board reports them through `get_errors`).
+//!
+//! ### Examples:
+//! ```rust
+//! # use std::time::Duration;
+//! # use raestro::maestro::constants::Channel;
+//! # use raestro::script;
+//! # use raestro::script::interpreter::Interpreter;
+//! # use raestro::sim::Emulator;
+//! # fn main() -> raestro::Result<()> {
+//! let program = script::compile("4000 0 servo 100 delay 8000 0 servo quit")?;
+//! let mut emulator = Emulator::new();
+//! let mut interpreter = Interpreter::new(program);
+//!
+//! interpreter.run(&mut emulator, Duration::from_millis(50));
+//! assert_eq!(emulator.target(Channel::Channel0)?, 4000u16);
+//!
+//! interpreter.run(&mut emulator, Duration::from_millis(100));
+//! assert_eq!(emulator.target(Channel::Channel0)?, 8000u16);
+//! assert!(!interpreter.is_running());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use derive_more::Display;
+
+use crate::errors::Error;
+use crate::maestro::constants::Channel;
+use crate::maestro::constants::ErrorValues;
+use crate::maestro::constants::Model;
+use crate::script::opcode;
+use crate::script::opcode::Opcode;
+use crate::script::Program;
+use crate::sim::Command;
+use crate::sim::Emulator;
+
+/// ### Purpose:
+/// How long each instruction is taken to run for.
+///
+/// ### Notes:
+/// This is only an approximation of the Maestro's speed, but it stops scripts
+/// which loop without delaying from running forever.
+const INSTRUCTION_TIME: Duration = Duration::from_micros(100u64);
+
+/// ### Purpose:
+/// A single executed instruction, for tracing scripts.
+///
+/// ### Notes:
+/// Displays as the instruction's address and name, followed by the data stack
+/// (bottom first) after it ran.
+#[derive(Clone, PartialEq, Debug, Display)]
+#[display(fmt = "{:#06x} {} {:?}", address, "opcode.name()", stack)]
+pub struct Step {
+    /// The address of the instruction.
+    pub address: u16,
+
+    /// The instruction.
+    pub opcode: Opcode,
+
+    /// The data stack (bottom first) after the instruction ran.
+    pub stack: Vec<i16>,
+}
+
+/// ### Purpose:
+/// An offline interpreter for compiled Maestro scripts.
+///
+/// ### Notes:
+/// Scripts run in simulated time: [`Interpreter::run`] executes instructions
+/// and advances the emulator in step with them, honouring `delay`s.
+/// [`Interpreter::step`] executes a single instruction instead, without
+/// advancing time at all (any pending `delay` is skipped).
+///
+/// Commands given channels which do not exist on the emulated model are
+/// ignored (`get_position` pushes `0`). Division (or `mod`) by `0` pushes
+/// `0`. `peek` and `poke` address the data stack, from the bottom.
+#[derive(Clone, Debug)]
+pub struct Interpreter {
+    program: Program,
+    program_counter: u16,
+    stack: Vec<i16>,
+    call_stack: Vec<u16>,
+    running: bool,
+    fault: Option<ErrorValues>,
+    delay: Duration,
+    clock: Duration,
+    led: bool,
+    serial: Vec<u8>,
+}
+
+impl Interpreter {
+    /// ### Purpose:
+    /// Creates an interpreter which runs the given program from its start.
+    pub fn new(program: Program) -> Self {
+        Self {
+            program,
+            program_counter: 0u16,
+            stack: vec![],
+            call_stack: vec![],
+            running: true,
+            fault: None,
+            delay: Duration::ZERO,
+            clock: Duration::ZERO,
+            led: false,
+            serial: vec![],
+        }
+    }
+
+    /// ### Purpose:
+    /// The program being run.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// ### Purpose:
+    /// Restarts the script from its start, with empty stacks.
+    pub fn restart(&mut self) {
+        self.restart_from(0u16);
+    }
+
+    /// ### Purpose:
+    /// Restarts the script at the given subroutine (numbered in the order
+    /// they are defined), with empty stacks.
+    ///
+    /// ### Notes:
+    /// Like on the Maestro, a subroutine started this way raises
+    /// [`ErrorValues::ScriptCallStackError`] if it ends with `return`.
+    pub fn restart_at(&mut self, subroutine: u8) -> crate::Result<()> {
+        let address = self
+            .program
+            .subroutines
+            .get(subroutine as usize)
+            .map(|subroutine| subroutine.address)
+            .ok_or(Error::InvalidSubroutine(subroutine))?;
+        self.restart_from(address);
+        Ok(())
+    }
+
+    /// ### Purpose:
+    /// Restarts the script at the given subroutine, after pushing the given
+    /// parameter onto the data stack.
+    pub fn restart_at_with_parameter(
+        &mut self,
+        subroutine: u8,
+        parameter: i16,
+    ) -> crate::Result<()> {
+        self.restart_at(subroutine)?;
+        self.stack.push(parameter);
+        Ok(())
+    }
+
+    /// ### Purpose:
+    /// Stops the script.
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    /// ### Purpose:
+    /// Whether the script is still running.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// ### Purpose:
+    /// The error which stopped the script, if any.
+    pub fn fault(&self) -> Option<ErrorValues> {
+        self.fault
+    }
+
+    /// ### Purpose:
+    /// The address of the next instruction.
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    /// ### Purpose:
+    /// The data stack, bottom first.
+    pub fn stack(&self) -> &[i16] {
+        &self.stack
+    }
+
+    /// ### Purpose:
+    /// The return addresses of every subroutine currently being run,
+    /// outermost first.
+    pub fn call_stack(&self) -> &[u16] {
+        &self.call_stack
+    }
+
+    /// ### Purpose:
+    /// Whether the script has turned the red LED on.
+    pub fn led(&self) -> bool {
+        self.led
+    }
+
+    /// ### Purpose:
+    /// Every byte the script has sent out of the TTL serial port.
+    pub fn serial(&self) -> &[u8] {
+        &self.serial
+    }
+
+    /// ### Purpose:
+    /// Runs the script for the given duration of simulated time, advancing
+    /// the emulator along with it.
+    ///
+    /// ### Notes:
+    /// The emulator is advanced by the whole duration, even if the script
+    /// stops part-way through.
+    pub fn run(&mut self, emulator: &mut Emulator, duration: Duration) {
+        self.trace(emulator, duration, |_| ());
+    }
+
+    /// ### Purpose:
+    /// Runs the script like [`Interpreter::run`], passing every instruction
+    /// executed to the given function.
+    pub fn trace<F>(
+        &mut self,
+        emulator: &mut Emulator,
+        duration: Duration,
+        mut trace: F,
+    ) where
+        F: FnMut(&Step),
+    {
+        let mut remaining = duration;
+        while self.running && !remaining.is_zero() {
+            let elapsed = match self.delay.is_zero() {
+                true => {
+                    let (step, delay) = self.execute(emulator);
+                    trace(&step);
+                    self.delay = delay;
+                    INSTRUCTION_TIME.min(remaining)
+                },
+                false => {
+                    let elapsed = self.delay.min(remaining);
+                    self.delay -= elapsed;
+                    elapsed
+                },
+            };
+            self.wait(emulator, elapsed);
+            remaining -= elapsed;
+        }
+        self.wait(emulator, remaining);
+    }
+
+    /// ### Purpose:
+    /// Executes the next instruction, returning it, or `None` if the script
+    /// is not running.
+    pub fn step(&mut self, emulator: &mut Emulator) -> Option<Step> {
+        self.running.then(|| {
+            let (step, _) = self.execute(emulator);
+            self.delay = Duration::ZERO;
+            step
+        })
+    }
+
+    fn restart_from(&mut self, address: u16) {
+        self.program_counter = address;
+        self.stack.clear();
+        self.call_stack.clear();
+        self.running = true;
+        self.fault = None;
+        self.delay = Duration::ZERO;
+    }
+
+    fn wait(&mut self, emulator: &mut Emulator, duration: Duration) {
+        emulator.advance(duration);
+        self.clock += duration;
+    }
+
+    /// ### Purpose:
+    /// Executes the next instruction, returning it along with how long it
+    /// asked to delay for.
+    fn execute(&mut self, emulator: &mut Emulator) -> (Step, Duration) {
+        let address = self.program_counter;
+        let (opcode, delay) = match self.fetch() {
+            Ok((opcode, target)) => {
+                let delay = self.instruction(opcode, target, emulator);
+                (
+                    opcode,
+                    delay.unwrap_or_else(|error| self.raise(emulator, error)),
+                )
+            },
+            Err(error) => (Opcode::Quit, self.raise(emulator, error)),
+        };
+        let step = Step {
+            address,
+            opcode,
+            stack: self.stack.clone(),
+        };
+        (step, delay)
+    }
+
+    fn raise(
+        &mut self,
+        emulator: &mut Emulator,
+        error: ErrorValues,
+    ) -> Duration {
+        emulator.raise(error);
+        self.fault = Some(error);
+        self.running = false;
+        Duration::ZERO
+    }
+
+    /// ### Purpose:
+    /// Reads the next opcode, moving the program counter past it.
+    ///
+    /// ### Notes:
+    /// Single-byte calls are read as [`Opcode::Call`], along with the address
+    /// of the subroutine they call (from the program's subroutine table).
+    fn fetch(&mut self) -> Result<(Opcode, Option<u16>), ErrorValues> {
+        let byte = self.byte()?;
+        match opcode::short_call(byte) {
+            Some(subroutine) => {
+                let address = self
+                    .program
+                    .subroutines
+                    .get(usize::from(subroutine))
+                    .map(|subroutine| subroutine.address)
+                    .ok_or(ErrorValues::ScriptPcError)?;
+                Ok((Opcode::Call, Some(address)))
+            },
+            None => Opcode::try_from(byte)
+                .map(|opcode| (opcode, None))
+                .map_err(|_| ErrorValues::ScriptPcError),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, ErrorValues> {
+        let byte = self
+            .program
+            .bytecode
+            .get(self.program_counter as usize)
+            .copied()
+            .ok_or(ErrorValues::ScriptPcError)?;
+        self.program_counter = self.program_counter.wrapping_add(1u16);
+        Ok(byte)
+    }
+
+    fn word(&mut self) -> Result<u16, ErrorValues> {
+        let low = self.byte()?;
+        let high = self.byte()?;
+        Ok(u16::from_le_bytes([low, high]))
+    }
+
+    fn push(&mut self, model: Model, value: i16) -> Result<(), ErrorValues> {
+        if self.stack.len() >= model.script_stack_depth() as usize {
+            return Err(ErrorValues::ScriptStackError);
+        };
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<i16, ErrorValues> {
+        self.stack.pop().ok_or(ErrorValues::ScriptStackError)
+    }
+
+    /// ### Purpose:
+    /// Pops the top two values, top last.
+    fn pop2(&mut self) -> Result<(i16, i16), ErrorValues> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+        Ok((a, b))
+    }
+
+    /// ### Purpose:
+    /// The index (from the bottom) of the value `n` below the top.
+    fn depth_index(&self, n: i16) -> Result<usize, ErrorValues> {
+        usize::try_from(n)
+            .ok()
+            .and_then(|n| self.stack.len().checked_sub(n + 1usize))
+            .ok_or(ErrorValues::ScriptStackError)
+    }
+
+    /// ### Purpose:
+    /// Runs a single instruction (after its opcode, and the target of a
+    /// single-byte call, have been fetched), returning how long it asked to
+    /// delay for.
+    fn instruction(
+        &mut self,
+        opcode: Opcode,
+        target: Option<u16>,
+        emulator: &mut Emulator,
+    ) -> Result<Duration, ErrorValues> {
+        let model = emulator.model();
+        let to_channel = |value: i16| {
+            u8::try_from(value)
+                .ok()
+                .and_then(|channel| Channel::new(channel, model).ok())
+        };
+        let binary = |this: &mut Self, f: fn(i16, i16) -> i16| {
+            let (a, b) = this.pop2()?;
+            this.push(model, f(a, b))
+        };
+        let unary = |this: &mut Self, f: fn(i16) -> i16| {
+            let a = this.pop()?;
+            this.push(model, f(a))
+        };
+        match opcode {
+            Opcode::Quit => self.running = false,
+            Opcode::Literal => {
+                let value = self.word()?;
+                self.push(model, value as i16)?;
+            },
+            Opcode::Literal8 => {
+                let value = self.byte()?;
+                self.push(model, i16::from(value))?;
+            },
+            Opcode::LiteralN => {
+                for _ in 0u8..self.byte()? {
+                    let value = self.word()?;
+                    self.push(model, value as i16)?;
+                }
+            },
+            Opcode::Literal8N => {
+                for _ in 0u8..self.byte()? {
+                    let value = self.byte()?;
+                    self.push(model, i16::from(value))?;
+                }
+            },
+            Opcode::Return => {
+                self.program_counter = self
+                    .call_stack
+                    .pop()
+                    .ok_or(ErrorValues::ScriptCallStackError)?;
+            },
+            Opcode::Jump => self.program_counter = self.word()?,
+            Opcode::JumpZ => {
+                let address = self.word()?;
+                if self.pop()? == 0i16 {
+                    self.program_counter = address;
+                };
+            },
+            Opcode::Delay => {
+                let millis = self.pop()? as u16;
+                return Ok(Duration::from_millis(u64::from(millis)));
+            },
+            Opcode::GetMs => {
+                let millis = self.clock.as_millis() as u16;
+                self.push(model, millis as i16)?;
+            },
+            Opcode::Depth => {
+                let depth = self.stack.len() as i16;
+                self.push(model, depth)?;
+            },
+            Opcode::Drop => {
+                self.pop()?;
+            },
+            Opcode::Dup => {
+                let a = self.pop()?;
+                self.push(model, a)?;
+                self.push(model, a)?;
+            },
+            Opcode::Over => {
+                let index = self.depth_index(1i16)?;
+                self.push(model, self.stack[index])?;
+            },
+            Opcode::Pick => {
+                let n = self.pop()?;
+                let index = self.depth_index(n)?;
+                self.push(model, self.stack[index])?;
+            },
+            Opcode::Swap => {
+                let (a, b) = self.pop2()?;
+                self.push(model, b)?;
+                self.push(model, a)?;
+            },
+            Opcode::Rot => {
+                let index = self.depth_index(2i16)?;
+                let value = self.stack.remove(index);
+                self.stack.push(value);
+            },
+            Opcode::Roll => {
+                let n = self.pop()?;
+                let index = self.depth_index(n)?;
+                let value = self.stack.remove(index);
+                self.stack.push(value);
+            },
+            Opcode::BitwiseNot => unary(self, |a| !a)?,
+            Opcode::BitwiseAnd => binary(self, |a, b| a & b)?,
+            Opcode::BitwiseOr => binary(self, |a, b| a | b)?,
+            Opcode::BitwiseXor => binary(self, |a, b| a ^ b)?,
+            Opcode::ShiftRight => {
+                binary(self, |a, b| a.wrapping_shr(b as u32))?
+            },
+            Opcode::ShiftLeft => binary(self, |a, b| a.wrapping_shl(b as u32))?,
+            Opcode::LogicalNot => unary(self, |a| i16::from(a == 0i16))?,
+            Opcode::LogicalAnd => {
+                binary(self, |a, b| i16::from(a != 0i16 && b != 0i16))?
+            },
+            Opcode::LogicalOr => {
+                binary(self, |a, b| i16::from(a != 0i16 || b != 0i16))?
+            },
+            Opcode::Negate => unary(self, i16::wrapping_neg)?,
+            Opcode::Plus => binary(self, i16::wrapping_add)?,
+            Opcode::Minus => binary(self, i16::wrapping_sub)?,
+            Opcode::Times => binary(self, i16::wrapping_mul)?,
+            Opcode::Divide => {
+                binary(self, |a, b| a.checked_div(b).unwrap_or_default())?
+            },
+            Opcode::Mod => {
+                binary(self, |a, b| a.checked_rem(b).unwrap_or_default())?
+            },
+            Opcode::Positive => unary(self, |a| i16::from(a > 0i16))?,
+            Opcode::Negative => unary(self, |a| i16::from(a < 0i16))?,
+            Opcode::Nonzero => unary(self, |a| i16::from(a != 0i16))?,
+            Opcode::Equals => binary(self, |a, b| i16::from(a == b))?,
+            Opcode::NotEquals => binary(self, |a, b| i16::from(a != b))?,
+            Opcode::Min => binary(self, i16::min)?,
+            Opcode::Max => binary(self, i16::max)?,
+            Opcode::LessThan => binary(self, |a, b| i16::from(a < b))?,
+            Opcode::GreaterThan => binary(self, |a, b| i16::from(a > b))?,
+            Opcode::Servo => {
+                let (target, channel) = self.pop2()?;
+                if let Some(channel) = to_channel(channel) {
+                    emulator.execute(Command::SetTarget {
+                        channel: channel as u8,
+                        target: target as u16,
+                    });
+                };
+            },
+            Opcode::Servo8Bit => {
+                let (position, channel) = self.pop2()?;
+                let range = to_channel(channel).and_then(|channel| {
+                    let range =
+                        emulator.mini_ssc().ranges.get(channel as usize);
+                    Some((channel, *range?))
+                });
+                if let Some((channel, range)) = range {
+                    let position = position.clamp(0i16, 254i16) as u8;
+                    emulator.execute(Command::SetTarget {
+                        channel: channel as u8,
+                        target: range.to_target(position),
+                    });
+                };
+            },
+            Opcode::Speed => {
+                let (speed, channel) = self.pop2()?;
+                if let Some(channel) = to_channel(channel) {
+                    emulator.execute(Command::SetSpeed {
+                        channel: channel as u8,
+                        speed: speed as u16,
+                    });
+                };
+            },
+            Opcode::Acceleration => {
+                let (acceleration, channel) = self.pop2()?;
+                if let Some(channel) = to_channel(channel) {
+                    emulator.execute(Command::SetAcceleration {
+                        channel: channel as u8,
+                        acceleration: acceleration as u16,
+                    });
+                };
+            },
+            Opcode::GetPosition => {
+                let channel = self.pop()?;
+                let position = to_channel(channel)
+                    .and_then(|channel| emulator.position(channel).ok())
+                    .unwrap_or_default();
+                self.push(model, position as i16)?;
+            },
+            Opcode::GetMovingState => {
+                self.push(model, i16::from(emulator.is_moving()))?;
+            },
+            Opcode::LedOn => self.led = true,
+            Opcode::LedOff => self.led = false,
+            Opcode::Pwm => {
+                let (on_time, period) = self.pop2()?;
+                if model.supports_pwm() {
+                    emulator.execute(Command::SetPwm {
+                        on_time: on_time as u16,
+                        period: period as u16,
+                    });
+                };
+            },
+            Opcode::Peek => {
+                let index = self.pop()?;
+                let value = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| self.stack.get(index).copied())
+                    .ok_or(ErrorValues::ScriptStackError)?;
+                self.push(model, value)?;
+            },
+            Opcode::Poke => {
+                let (value, index) = self.pop2()?;
+                let slot = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| self.stack.get_mut(index))
+                    .ok_or(ErrorValues::ScriptStackError)?;
+                *slot = value;
+            },
+            Opcode::SerialSendByte => {
+                let byte = self.pop()?;
+                self.serial.push(byte as u8);
+            },
+            Opcode::Call => {
+                let address = match target {
+                    Some(address) => address,
+                    None => self.word()?,
+                };
+                let depth = model.script_call_stack_depth() as usize;
+                if self.call_stack.len() >= depth {
+                    return Err(ErrorValues::ScriptCallStackError);
+                };
+                self.call_stack.push(self.program_counter);
+                self.program_counter = address;
+            },
+        };
+        Ok(Duration::ZERO)
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Tooling for the Maestro's built-in scripting language.
+//!
+//! Maestro scripts are stack-based: numbers are pushed onto the stack, and
+//! commands (`servo`, `delay`, `+`, `get_position`, ...) pop their arguments
+//! off of it and push their results back. [`compile`] turns script source
+//! into the bytecode the Maestro executes.
+//!
+//! The compiler understands:
+//! - decimal and `0x` hexadecimal numbers (from `-32768` to `65535`);
+//! - every command keyword (case-insensitively) along with the arithmetic
+//!   and comparison symbols (`+`, `<`, `>>`, ...);
+//! - `begin ... while ... repeat` loops, and `if ... else ... endif`;
+//! - labels (`name:`), which can be jumped to with `goto name`;
+//! - subroutines (`sub name ... return`), which are called by name;
+//! - comments, from `#` to the end of the line.
+//!
+//! A `quit` is appended to the end of every script, so that running off the
+//! end of it stops the script. Like on the Maestro, subroutines should be
+//! defined after the main program, which should end with `quit` or loop
+//! forever.
+//!
+//! ### Examples:
+//! ```rust
+//! # use raestro::script;
+//! # fn main() -> raestro::Result<()> {
+//! let program = script::compile(
+//!     "
+//!     begin
+//!         4000 0 servo
+//!         500 delay
+//!         8000 0 servo
+//!         500 delay
+//!     repeat
+//!     ",
+//! )?;
+//!
+//! // LITERAL 4000
+//! assert_eq!(program.bytecode[..3usize], [0x20u8, 0xa0u8, 0x0fu8]);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod compiler;
+pub mod disassembler;
+pub mod interpreter;
+pub mod opcode;
+
+#[cfg(test)]
+mod tests;
+
+pub use compiler::compile;
+
+/// ### Purpose:
+/// A compiled Maestro script.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Program {
+    /// The bytecode of the whole script.
+    pub bytecode: Vec<u8>,
+
+    /// The subroutines the script defines, in the order they are defined.
+    pub subroutines: Vec<Subroutine>,
+}
+
+/// ### Purpose:
+/// A subroutine defined by a script.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Subroutine {
+    /// The subroutine's name, as it was written.
+    pub name: String,
+
+    /// The address of the subroutine's first instruction.
+    pub address: u16,
+}
+
+impl Program {
+    /// ### Purpose:
+    /// Wraps compiled bytecode, along with the addresses of its subroutines
+    /// (see [`disassembler::subroutines`]).
+    pub fn from_bytecode(
+        bytecode: Vec<u8>,
+        addresses: &[u16],
+    ) -> crate::Result<Self> {
+        let subroutines = disassembler::subroutines(&bytecode, addresses)?;
+        Ok(Self {
+            bytecode,
+            subroutines,
+        })
+    }
+
+    /// ### Purpose:
+    /// Finds the number of the given subroutine (case-insensitively), as
+    /// used by [`crate::maestro::Maestro::restart_script_at`].
+    ///
+    /// ### Examples:
+    /// ```rust
+    /// # use raestro::script;
+    /// # fn main() -> raestro::Result<()> {
+    /// let program = script::compile("sub wave 4000 0 servo return")?;
+    ///
+    /// assert_eq!(program.subroutine("WAVE"), Some(0u8));
+    /// assert_eq!(program.subroutine("sweep"), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subroutine(&self, name: &str) -> Option<u8> {
+        self.subroutines
+            .iter()
+            .position(|subroutine| subroutine.name.eq_ignore_ascii_case(name))
+            .and_then(|index| u8::try_from(index).ok())
+    }
+}
@@ -0,0 +1,417 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! The opcodes of the Maestro's script bytecode.
+
+/// ### Purpose:
+/// Every instruction the Maestro's script engine can execute.
+///
+/// ### Notes:
+/// The values are those of Pololu's bytecode: `QUIT` is `0x00`, and the rest
+/// are numbered from `0x20` (`LITERAL`) to `0x55` (`CALL`).
+///
+/// Most instructions are a single byte. The literals and the control-flow
+/// instructions are followed by operands (see [`Opcode::operand_size`]).
+/// Multi-byte operands are little-endian.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[repr(u8)]
+pub enum Opcode {
+    /// Stops the script.
+    Quit = 0x00u8,
+
+    /// Pushes the following signed 16-bit value.
+    Literal = 0x20u8,
+
+    /// Pushes the following unsigned 8-bit value.
+    Literal8 = 0x21u8,
+
+    /// Pushes the following count of signed 16-bit values.
+    LiteralN = 0x22u8,
+
+    /// Pushes the following count of unsigned 8-bit values.
+    Literal8N = 0x23u8,
+
+    /// Returns from a subroutine.
+    Return = 0x24u8,
+
+    /// Jumps to the following address.
+    Jump = 0x25u8,
+
+    /// Pops a value, and jumps to the following address if it is `0`.
+    JumpZ = 0x26u8,
+
+    /// Pops a duration (in ms) and waits for it.
+    Delay = 0x27u8,
+
+    /// Pushes the low 16 bits of the number of ms since the Maestro started.
+    GetMs = 0x28u8,
+
+    /// Pushes the number of values on the stack.
+    Depth = 0x29u8,
+
+    /// Pops a value.
+    Drop = 0x2Au8,
+
+    /// Duplicates the top value.
+    Dup = 0x2Bu8,
+
+    /// Copies the second value onto the top.
+    Over = 0x2Cu8,
+
+    /// Pops `n`, and copies the value `n` below the top onto the top.
+    Pick = 0x2Du8,
+
+    /// Swaps the top two values.
+    Swap = 0x2Eu8,
+
+    /// Moves the third value to the top.
+    Rot = 0x2Fu8,
+
+    /// Pops `n`, and moves the value `n` below the top onto the top.
+    Roll = 0x30u8,
+
+    /// Bitwise complement of the top value.
+    BitwiseNot = 0x31u8,
+
+    /// Bitwise AND of the top two values.
+    BitwiseAnd = 0x32u8,
+
+    /// Bitwise OR of the top two values.
+    BitwiseOr = 0x33u8,
+
+    /// Bitwise XOR of the top two values.
+    BitwiseXor = 0x34u8,
+
+    /// Arithmetic shift right of the second value by the top value.
+    ShiftRight = 0x35u8,
+
+    /// Shift left of the second value by the top value.
+    ShiftLeft = 0x36u8,
+
+    /// `1` if the top value is `0`, `0` otherwise.
+    LogicalNot = 0x37u8,
+
+    /// `1` if both of the top two values are non-zero, `0` otherwise.
+    LogicalAnd = 0x38u8,
+
+    /// `1` if either of the top two values is non-zero, `0` otherwise.
+    LogicalOr = 0x39u8,
+
+    /// Negates the top value.
+    Negate = 0x3Au8,
+
+    /// Adds the top two values.
+    Plus = 0x3Bu8,
+
+    /// Subtracts the top value from the second value.
+    Minus = 0x3Cu8,
+
+    /// Multiplies the top two values.
+    Times = 0x3Du8,
+
+    /// Divides the second value by the top value.
+    Divide = 0x3Eu8,
+
+    /// The remainder of the second value divided by the top value.
+    Mod = 0x3Fu8,
+
+    /// `1` if the top value is positive, `0` otherwise.
+    Positive = 0x40u8,
+
+    /// `1` if the top value is negative, `0` otherwise.
+    Negative = 0x41u8,
+
+    /// `1` if the top value is non-zero, `0` otherwise.
+    Nonzero = 0x42u8,
+
+    /// `1` if the top two values are equal, `0` otherwise.
+    Equals = 0x43u8,
+
+    /// `1` if the top two values are different, `0` otherwise.
+    NotEquals = 0x44u8,
+
+    /// The smaller of the top two values.
+    Min = 0x45u8,
+
+    /// The larger of the top two values.
+    Max = 0x46u8,
+
+    /// `1` if the second value is less than the top value, `0` otherwise.
+    LessThan = 0x47u8,
+
+    /// `1` if the second value is greater than the top value, `0` otherwise.
+    GreaterThan = 0x48u8,
+
+    /// Pops a channel and a target (in quarter-us), and sets the target.
+    Servo = 0x49u8,
+
+    /// Pops a channel and an 8-bit target, and sets the target through the
+    /// channel's Mini SSC range.
+    Servo8Bit = 0x4Au8,
+
+    /// Pops a channel and a speed, and sets the speed limit.
+    Speed = 0x4Bu8,
+
+    /// Pops a channel and an acceleration, and sets the acceleration limit.
+    Acceleration = 0x4Cu8,
+
+    /// Pops a channel, and pushes its position.
+    GetPosition = 0x4Du8,
+
+    /// Pushes `1` if any servo is still moving, `0` otherwise.
+    GetMovingState = 0x4Eu8,
+
+    /// Turns the red LED on.
+    LedOn = 0x4Fu8,
+
+    /// Turns the red LED off.
+    LedOff = 0x50u8,
+
+    /// Pops an on-time and a period, and sets the PWM output.
+    Pwm = 0x51u8,
+
+    /// Pops an address, and pushes the value at that address.
+    Peek = 0x52u8,
+
+    /// Pops an address and a value, and writes the value to that address.
+    Poke = 0x53u8,
+
+    /// Pops a byte, and sends it out of the TTL serial port.
+    SerialSendByte = 0x54u8,
+
+    /// Calls the subroutine at the following address.
+    Call = 0x55u8,
+}
+
+/// ### Purpose:
+/// The first of the single-byte calls.
+///
+/// ### Notes:
+/// Subroutines `0` to `127` are called by the single byte `0x80` plus their
+/// number, and are found through the program's subroutine table. Any others
+/// are called through [`Opcode::Call`] and their address.
+pub const SHORT_CALL: u8 = 0x80u8;
+
+/// ### Purpose:
+/// The number of the subroutine the given byte calls, if it is a
+/// single-byte call (see [`SHORT_CALL`]).
+pub fn short_call(byte: u8) -> Option<u8> {
+    byte.checked_sub(SHORT_CALL)
+}
+
+/// ### Purpose:
+/// Every opcode, in order.
+pub(crate) const OPCODES: [Opcode; 55usize] = [
+    Opcode::Quit,
+    Opcode::Literal,
+    Opcode::Literal8,
+    Opcode::LiteralN,
+    Opcode::Literal8N,
+    Opcode::Return,
+    Opcode::Jump,
+    Opcode::JumpZ,
+    Opcode::Delay,
+    Opcode::GetMs,
+    Opcode::Depth,
+    Opcode::Drop,
+    Opcode::Dup,
+    Opcode::Over,
+    Opcode::Pick,
+    Opcode::Swap,
+    Opcode::Rot,
+    Opcode::Roll,
+    Opcode::BitwiseNot,
+    Opcode::BitwiseAnd,
+    Opcode::BitwiseOr,
+    Opcode::BitwiseXor,
+    Opcode::ShiftRight,
+    Opcode::ShiftLeft,
+    Opcode::LogicalNot,
+    Opcode::LogicalAnd,
+    Opcode::LogicalOr,
+    Opcode::Negate,
+    Opcode::Plus,
+    Opcode::Minus,
+    Opcode::Times,
+    Opcode::Divide,
+    Opcode::Mod,
+    Opcode::Positive,
+    Opcode::Negative,
+    Opcode::Nonzero,
+    Opcode::Equals,
+    Opcode::NotEquals,
+    Opcode::Min,
+    Opcode::Max,
+    Opcode::LessThan,
+    Opcode::GreaterThan,
+    Opcode::Servo,
+    Opcode::Servo8Bit,
+    Opcode::Speed,
+    Opcode::Acceleration,
+    Opcode::GetPosition,
+    Opcode::GetMovingState,
+    Opcode::LedOn,
+    Opcode::LedOff,
+    Opcode::Pwm,
+    Opcode::Peek,
+    Opcode::Poke,
+    Opcode::SerialSendByte,
+    Opcode::Call,
+];
+
+impl Opcode {
+    /// ### Purpose:
+    /// The keyword this instruction is written as in script source.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "QUIT",
+            Self::Literal => "LITERAL",
+            Self::Literal8 => "LITERAL8",
+            Self::LiteralN => "LITERAL_N",
+            Self::Literal8N => "LITERAL8_N",
+            Self::Return => "RETURN",
+            Self::Jump => "JUMP",
+            Self::JumpZ => "JUMP_Z",
+            Self::Delay => "DELAY",
+            Self::GetMs => "GET_MS",
+            Self::Depth => "DEPTH",
+            Self::Drop => "DROP",
+            Self::Dup => "DUP",
+            Self::Over => "OVER",
+            Self::Pick => "PICK",
+            Self::Swap => "SWAP",
+            Self::Rot => "ROT",
+            Self::Roll => "ROLL",
+            Self::BitwiseNot => "BITWISE_NOT",
+            Self::BitwiseAnd => "BITWISE_AND",
+            Self::BitwiseOr => "BITWISE_OR",
+            Self::BitwiseXor => "BITWISE_XOR",
+            Self::ShiftRight => "SHIFT_RIGHT",
+            Self::ShiftLeft => "SHIFT_LEFT",
+            Self::LogicalNot => "LOGICAL_NOT",
+            Self::LogicalAnd => "LOGICAL_AND",
+            Self::LogicalOr => "LOGICAL_OR",
+            Self::Negate => "NEGATE",
+            Self::Plus => "PLUS",
+            Self::Minus => "MINUS",
+            Self::Times => "TIMES",
+            Self::Divide => "DIVIDE",
+            Self::Mod => "MOD",
+            Self::Positive => "POSITIVE",
+            Self::Negative => "NEGATIVE",
+            Self::Nonzero => "NONZERO",
+            Self::Equals => "EQUALS",
+            Self::NotEquals => "NOT_EQUALS",
+            Self::Min => "MIN",
+            Self::Max => "MAX",
+            Self::LessThan => "LESS_THAN",
+            Self::GreaterThan => "GREATER_THAN",
+            Self::Servo => "SERVO",
+            Self::Servo8Bit => "SERVO_8BIT",
+            Self::Speed => "SPEED",
+            Self::Acceleration => "ACCELERATION",
+            Self::GetPosition => "GET_POSITION",
+            Self::GetMovingState => "GET_MOVING_STATE",
+            Self::LedOn => "LED_ON",
+            Self::LedOff => "LED_OFF",
+            Self::Pwm => "PWM",
+            Self::Peek => "PEEK",
+            Self::Poke => "POKE",
+            Self::SerialSendByte => "SERIAL_SEND_BYTE",
+            Self::Call => "CALL",
+        }
+    }
+
+    /// ### Purpose:
+    /// The symbol this instruction can also be written as in script source,
+    /// if it has one.
+    pub fn symbol(self) -> Option<&'static str> {
+        let symbol = match self {
+            Self::BitwiseNot => "~",
+            Self::BitwiseAnd => "&",
+            Self::BitwiseOr => "|",
+            Self::BitwiseXor => "^",
+            Self::ShiftRight => ">>",
+            Self::ShiftLeft => "<<",
+            Self::LogicalNot => "!",
+            Self::LogicalAnd => "&&",
+            Self::LogicalOr => "||",
+            Self::Plus => "+",
+            Self::Minus => "-",
+            Self::Times => "*",
+            Self::Divide => "/",
+            Self::Mod => "%",
+            Self::Equals => "==",
+            Self::NotEquals => "!=",
+            Self::LessThan => "<",
+            Self::GreaterThan => ">",
+            _ => return None,
+        };
+        Some(symbol)
+    }
+
+    /// ### Purpose:
+    /// Whether this instruction can be written directly in script source.
+    ///
+    /// ### Notes:
+    /// Literals, jumps and calls are only ever generated by the compiler
+    /// (from numbers, control structures and subroutine names).
+    pub fn is_keyword(self) -> bool {
+        !matches!(
+            self,
+            Self::Literal
+                | Self::Literal8
+                | Self::LiteralN
+                | Self::Literal8N
+                | Self::Jump
+                | Self::JumpZ
+                | Self::Call
+        )
+    }
+
+    /// ### Purpose:
+    /// Looks up the instruction written as the given keyword or symbol
+    /// (keywords are case-insensitive).
+    pub fn from_keyword(word: &str) -> Option<Self> {
+        OPCODES.iter().copied().find(|opcode| {
+            opcode.is_keyword()
+                && (opcode.name().eq_ignore_ascii_case(word)
+                    || opcode.symbol() == Some(word))
+        })
+    }
+
+    /// ### Purpose:
+    /// The number of operand bytes which follow this instruction, given the
+    /// byte right after it.
+    ///
+    /// ### Notes:
+    /// Only the `N` literals depend on that byte (their value count).
+    pub fn operand_size(self, next: u8) -> usize {
+        match self {
+            Self::Literal8 => 1usize,
+            Self::Literal | Self::Jump | Self::JumpZ | Self::Call => 2usize,
+            Self::LiteralN => 1usize + 2usize * usize::from(next),
+            Self::Literal8N => 1usize + usize::from(next),
+            _ => 0usize,
+        }
+    }
+}
+
+impl TryFrom<u8> for Opcode {
+    type Error = u8;
+
+    /// ### Purpose:
+    /// Converts a bytecode byte back into an [`Opcode`], returning the byte
+    /// itself if it is not an instruction.
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        OPCODES
+            .iter()
+            .copied()
+            .find(|&opcode| opcode as u8 == byte)
+            .ok_or(byte)
+    }
+}
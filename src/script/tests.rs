@@ -0,0 +1,725 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::maestro::constants::Channel;
+use crate::maestro::constants::ErrorValues;
+use crate::maestro::constants::Model;
+use crate::script::compile;
+use crate::script::disassembler::disassemble;
+use crate::script::disassembler::subroutines;
+use crate::script::interpreter::Interpreter;
+use crate::script::opcode::Opcode;
+use crate::script::Program;
+use crate::script::Subroutine;
+use crate::sim::Emulator;
+
+use Opcode::*;
+
+fn bytes(code: &[Op]) -> Vec<u8> {
+    code.iter()
+        .flat_map(|op| match *op {
+            Op::Code(opcode) => vec![opcode as u8],
+            Op::Byte(byte) => vec![byte],
+            Op::Word(word) => word.to_le_bytes().to_vec(),
+        })
+        .collect()
+}
+
+#[derive(Copy, Clone)]
+enum Op {
+    Code(Opcode),
+    Byte(u8),
+    Word(u16),
+}
+
+use Op::Byte;
+use Op::Code;
+use Op::Word;
+
+fn assert_error(source: &str, line: usize, column: usize) {
+    match compile(source) {
+        Err(Error::ScriptSyntax {
+            line: l, column: c, ..
+        }) => assert_eq!((l, c), (line, column), "{source}"),
+        result => {
+            panic!("expected a syntax error from {source:?}, got {result:?}")
+        },
+    };
+}
+
+/// ### Purpose:
+/// Runs the given source until it stops (or for at most a second), returning
+/// the final stack.
+fn evaluate(source: &str) -> Vec<i16> {
+    let mut emulator = Emulator::new();
+    let mut interpreter = Interpreter::new(compile(source).unwrap());
+    interpreter.run(&mut emulator, Duration::from_secs(1u64));
+    assert!(!interpreter.is_running(), "{source}");
+    assert_eq!(interpreter.fault(), None, "{source}");
+    interpreter.stack().to_vec()
+}
+
+fn fault(source: &str, model: Model) -> Option<ErrorValues> {
+    let mut emulator = Emulator::with_model(model);
+    let mut interpreter = Interpreter::new(compile(source).unwrap());
+    interpreter.run(&mut emulator, Duration::from_secs(1u64));
+    assert_eq!(
+        emulator.errors(),
+        interpreter.fault().into_iter().collect::<Vec<_>>()
+    );
+    interpreter.fault()
+}
+
+#[test]
+fn pololu_bytecode() {
+    // Assembled by hand from Pololu's opcode table.
+    let program = compile("begin 4000 0 servo 500 delay repeat").unwrap();
+    let expected = [
+        0x20u8, 0xa0u8, 0x0fu8, // 4000
+        0x21u8, 0x00u8, // 0
+        0x49u8, // SERVO
+        0x20u8, 0xf4u8, 0x01u8, // 500
+        0x27u8, // DELAY
+        0x25u8, 0x00u8, 0x00u8, // REPEAT (JUMP 0x0000)
+        0x00u8, // QUIT
+    ];
+
+    assert_eq!(program.bytecode, expected);
+
+    let program =
+        compile("begin wave repeat sub wave 4000 0 servo return").unwrap();
+    let expected = [
+        0x80u8, // wave (subroutine 0)
+        0x25u8, 0x00u8, 0x00u8, // REPEAT (JUMP 0x0000)
+        0x20u8, 0xa0u8, 0x0fu8, // 4000
+        0x21u8, 0x00u8, // 0
+        0x49u8, // SERVO
+        0x24u8, // RETURN
+        0x00u8, // QUIT
+    ];
+
+    assert_eq!(program.bytecode, expected);
+    assert_eq!(program.subroutines[0usize].address, 4u16);
+}
+
+#[test]
+fn long_calls() {
+    let definitions: String = (0usize..129usize)
+        .map(|n| format!("sub s{n} return "))
+        .collect();
+    let program = compile(&format!("s127 s128 quit {definitions}")).unwrap();
+    let address = program.subroutines[128usize].address;
+
+    assert_eq!(program.bytecode[0usize], 0xffu8);
+    assert_eq!(
+        program.bytecode[1usize..4usize],
+        [Call as u8, address as u8, (address >> 8u16) as u8]
+    );
+}
+
+#[test]
+fn literals_and_commands() {
+    let program =
+        compile("0 255 256 -1 0x1f40 65535 + < servo GET_Position").unwrap();
+    let expected = bytes(&[
+        Code(Literal8),
+        Byte(0u8),
+        Code(Literal8),
+        Byte(255u8),
+        Code(Literal),
+        Word(256u16),
+        Code(Literal),
+        Word(0xffffu16),
+        Code(Literal),
+        Word(8000u16),
+        Code(Literal),
+        Word(0xffffu16),
+        Code(Plus),
+        Code(LessThan),
+        Code(Servo),
+        Code(GetPosition),
+        Code(Quit),
+    ]);
+
+    assert_eq!(program.bytecode, expected);
+    assert!(program.subroutines.is_empty());
+}
+
+#[test]
+fn loops() {
+    let source = "
+        begin        # 0
+            dup      # 0
+        while        # 1
+            1 -      # 4
+        repeat       # 7
+        quit         # 10
+    ";
+    let expected = bytes(&[
+        Code(Dup),
+        Code(JumpZ),
+        Word(10u16),
+        Code(Literal8),
+        Byte(1u8),
+        Code(Minus),
+        Code(Jump),
+        Word(0u16),
+        Code(Quit),
+        Code(Quit),
+    ]);
+
+    assert_eq!(compile(source).unwrap().bytecode, expected);
+}
+
+#[test]
+fn conditionals() {
+    let source = "
+        1 if 2 else 3 endif
+        4 if 5 endif
+    ";
+    let expected = bytes(&[
+        Code(Literal8),
+        Byte(1u8),
+        Code(JumpZ),
+        Word(10u16),
+        Code(Literal8),
+        Byte(2u8),
+        Code(Jump),
+        Word(12u16),
+        Code(Literal8),
+        Byte(3u8),
+        Code(Literal8),
+        Byte(4u8),
+        Code(JumpZ),
+        Word(19u16),
+        Code(Literal8),
+        Byte(5u8),
+        Code(Quit),
+    ]);
+
+    assert_eq!(compile(source).unwrap().bytecode, expected);
+}
+
+#[test]
+fn subroutines_and_labels() {
+    let source = "
+        start:
+            wave wave
+            goto start
+        sub wave
+            4000 0 servo
+            return
+    ";
+    let program = compile(source).unwrap();
+    let expected = bytes(&[
+        Byte(0x80u8),
+        Byte(0x80u8),
+        Code(Jump),
+        Word(0u16),
+        Code(Literal),
+        Word(4000u16),
+        Code(Literal8),
+        Byte(0u8),
+        Code(Servo),
+        Code(Return),
+        Code(Quit),
+    ]);
+
+    assert_eq!(program.bytecode, expected);
+    assert_eq!(
+        program.subroutines,
+        vec![Subroutine {
+            name: "wave".to_string(),
+            address: 5u16,
+        }]
+    );
+    assert_eq!(program.subroutine("Wave"), Some(0u8));
+}
+
+#[test]
+fn syntax_errors() {
+    assert_error("1 2\n  bogus!", 2usize, 3usize);
+    assert_error("0x10000", 1usize, 1usize);
+    assert_error("1 1x", 1usize, 3usize);
+    assert_error("  repeat", 1usize, 3usize);
+    assert_error("begin\n1 while\n# nothing\n  endif", 4usize, 3usize);
+    assert_error("\n  if 1 else 2 else", 2usize, 15usize);
+    assert_error("begin 1 if", 1usize, 1usize);
+    assert_error("goto nowhere", 1usize, 6usize);
+    assert_error("missing", 1usize, 1usize);
+    assert_error("a: a:", 1usize, 4usize);
+    assert_error("sub a return sub A return", 1usize, 18usize);
+    assert_error("1 if sub a return endif", 1usize, 6usize);
+    assert_error("sub servo return", 1usize, 5usize);
+    assert_error("goto", 1usize, 1usize);
+}
+
+#[test]
+fn arithmetic_and_stack_manipulation() {
+    assert_eq!(evaluate("7 3 - 4 * 5 /"), vec![3i16]);
+    assert_eq!(
+        evaluate("-7 2 mod 7 0 / 1 0x7fff +"),
+        vec![-1i16, 0i16, -32768i16]
+    );
+    assert_eq!(
+        evaluate("1 2 < 2 1 < 3 3 == 0 logical_not"),
+        vec![1i16, 0i16, 1i16, 1i16]
+    );
+    assert_eq!(
+        evaluate("6 3 & 6 3 | 6 3 ^ 1 4 << -16 2 >> ~"),
+        vec![2i16, 7i16, 5i16, 16i16, 3i16]
+    );
+    assert_eq!(
+        evaluate("1 2 3 rot 4 5 swap over"),
+        vec![2i16, 3i16, 1i16, 5i16, 4i16, 5i16]
+    );
+    assert_eq!(
+        evaluate("1 2 3 2 pick 4 3 roll depth"),
+        vec![1i16, 3i16, 1i16, 4i16, 2i16, 5i16]
+    );
+    assert_eq!(
+        evaluate("5 6 7 0 peek 9 1 poke drop"),
+        vec![5i16, 9i16, 7i16]
+    );
+    assert_eq!(evaluate("3 begin dup while 1 - repeat"), vec![0i16]);
+    assert_eq!(
+        evaluate("0 if 1 else 2 endif 5 negative if 3 endif"),
+        vec![2i16]
+    );
+    assert_eq!(
+        evaluate("2 square square quit sub square dup * return"),
+        vec![16i16]
+    );
+}
+
+#[test]
+fn drives_the_emulator() {
+    let source = "
+        10 0 speed
+        begin
+            4000 0 servo 500 delay
+            8000 0 servo 500 delay
+            0 get_position 1 serial_send_byte
+        repeat
+    ";
+    let mut emulator = Emulator::new();
+    let mut interpreter = Interpreter::new(compile(source).unwrap());
+
+    interpreter.run(&mut emulator, Duration::from_millis(10u64));
+    assert_eq!(emulator.target(Channel::Channel0).unwrap(), 4000u16);
+    assert_eq!(emulator.speed(Channel::Channel0).unwrap(), 10u16);
+
+    interpreter.run(&mut emulator, Duration::from_millis(600u64));
+    assert_eq!(emulator.target(Channel::Channel0).unwrap(), 8000u16);
+    assert_eq!(
+        emulator.position(Channel::Channel0).unwrap(),
+        4000u16 + 110u16
+    );
+
+    interpreter.run(&mut emulator, Duration::from_millis(500u64));
+    assert_eq!(emulator.target(Channel::Channel0).unwrap(), 4000u16);
+    assert!(interpreter.is_running());
+    assert_eq!(interpreter.stack(), &[4500i16]);
+    assert_eq!(interpreter.serial(), &[1u8]);
+}
+
+#[test]
+fn servo_8bit() {
+    let mut emulator = Emulator::new();
+    let source = "254 1 servo_8bit 127 6 servo_8bit 127 300 servo_8bit quit";
+    let mut interpreter = Interpreter::new(compile(source).unwrap());
+    interpreter.run(&mut emulator, Duration::from_millis(10u64));
+
+    assert_eq!(
+        emulator.target(Channel::Channel1).unwrap(),
+        6000u16 + 1905u16
+    );
+    assert!(!interpreter.is_running());
+    assert_eq!(interpreter.fault(), None);
+}
+
+#[test]
+fn script_errors() {
+    let push_33 = "begin 1 repeat";
+    assert_eq!(
+        fault(push_33, Model::MicroMaestro6),
+        Some(ErrorValues::ScriptStackError)
+    );
+    assert_eq!(
+        fault(push_33, Model::MiniMaestro12),
+        Some(ErrorValues::ScriptStackError)
+    );
+    assert_eq!(
+        fault("drop", Model::MicroMaestro6),
+        Some(ErrorValues::ScriptStackError)
+    );
+
+    let mut emulator = Emulator::new();
+    let mut interpreter = Interpreter::new(compile("begin 1 repeat").unwrap());
+    (0usize..32usize).for_each(|_| {
+        interpreter.step(&mut emulator);
+        interpreter.step(&mut emulator);
+    });
+    assert_eq!(interpreter.stack().len(), 32usize);
+    assert_eq!(interpreter.fault(), None);
+
+    let nested = "
+        0 deeper quit
+        sub deeper
+            1 + dup 11 < if deeper endif
+            return
+    ";
+    assert_eq!(
+        fault(nested, Model::MicroMaestro6),
+        Some(ErrorValues::ScriptCallStackError)
+    );
+    assert_eq!(fault(nested, Model::MiniMaestro12), None);
+    assert_eq!(
+        fault("return", Model::MicroMaestro6),
+        Some(ErrorValues::ScriptCallStackError)
+    );
+
+    let truncated = Program {
+        bytecode: vec![Opcode::Literal as u8, 0u8],
+        subroutines: vec![],
+    };
+    let mut interpreter = Interpreter::new(truncated);
+    interpreter.run(&mut emulator, Duration::from_millis(10u64));
+    assert_eq!(interpreter.fault(), Some(ErrorValues::ScriptPcError));
+    assert_eq!(
+        fault("begin goto end repeat end:", Model::MicroMaestro6),
+        None
+    );
+
+    let invalid = Program {
+        bytecode: vec![0x01u8],
+        subroutines: vec![],
+    };
+    let mut interpreter = Interpreter::new(invalid);
+    assert!(interpreter.step(&mut emulator).is_some());
+    assert_eq!(interpreter.fault(), Some(ErrorValues::ScriptPcError));
+    assert!(interpreter.step(&mut emulator).is_none());
+}
+
+#[test]
+fn subroutine_restarts() {
+    let source = "
+        quit
+        sub double
+            2 * quit
+        sub finish
+            return
+    ";
+    let mut emulator = Emulator::new();
+    let mut interpreter = Interpreter::new(compile(source).unwrap());
+
+    interpreter.restart_at_with_parameter(0u8, 21i16).unwrap();
+    interpreter.run(&mut emulator, Duration::from_millis(10u64));
+    assert_eq!(interpreter.stack(), &[42i16]);
+    assert_eq!(interpreter.fault(), None);
+
+    interpreter.restart_at(1u8).unwrap();
+    assert!(interpreter.stack().is_empty());
+    interpreter.run(&mut emulator, Duration::from_millis(10u64));
+    assert_eq!(interpreter.fault(), Some(ErrorValues::ScriptCallStackError));
+
+    assert!(matches!(
+        interpreter.restart_at(2u8),
+        Err(Error::InvalidSubroutine(2u8))
+    ));
+}
+
+#[test]
+fn single_byte_calls() {
+    let mut emulator = Emulator::new();
+    let program = Program {
+        bytecode: bytes(&[
+            Code(Literal8),
+            Byte(1u8),
+            Byte(0x80u8),
+            Byte(0x80u8),
+            Code(Quit),
+            Code(Literal8),
+            Byte(2u8),
+            Code(Times),
+            Code(Return),
+        ]),
+        subroutines: vec![Subroutine {
+            name: "double".to_string(),
+            address: 5u16,
+        }],
+    };
+    let mut interpreter = Interpreter::new(program);
+    interpreter.run(&mut emulator, Duration::from_millis(10u64));
+
+    assert_eq!(interpreter.stack(), &[4i16]);
+    assert_eq!(interpreter.fault(), None);
+
+    // Subroutine 1 is not in the table.
+    let missing = Program {
+        bytecode: vec![0x81u8],
+        subroutines: vec![],
+    };
+    let mut interpreter = Interpreter::new(missing);
+    interpreter.run(&mut emulator, Duration::from_millis(10u64));
+    assert_eq!(interpreter.fault(), Some(ErrorValues::ScriptPcError));
+}
+
+#[test]
+fn tracing() {
+    let mut emulator = Emulator::new();
+    let mut interpreter = Interpreter::new(compile("1 2 + quit").unwrap());
+    let mut steps = vec![];
+    interpreter.trace(&mut emulator, Duration::from_millis(10u64), |step| {
+        steps.push(step.to_string())
+    });
+
+    assert_eq!(
+        steps,
+        vec![
+            "0x0000 LITERAL8 [1]",
+            "0x0002 LITERAL8 [1, 2]",
+            "0x0004 PLUS [3]",
+            "0x0005 QUIT [3]",
+        ]
+    );
+}
+
+/// ### Purpose:
+/// Checks that the given source survives being disassembled and compiled
+/// again, returning the disassembly.
+fn round_trip(source: &str) -> String {
+    let program = compile(source).unwrap();
+    let disassembly = disassemble(&program).unwrap();
+    assert_eq!(compile(&disassembly).unwrap(), program, "{disassembly}");
+    disassembly
+}
+
+#[test]
+fn disassembly_round_trips() {
+    let source = "
+        start:
+        begin
+            0 get_position 6000 less_than
+            while
+            begin
+                dup
+                while
+                1 - dup if led_on else led_off endif
+            repeat
+            drop
+            -5 0x7fff + 0 speed
+            wave
+        repeat
+        goto start
+        sub wave
+            3 begin dup while 1 - wave_once repeat drop
+            return
+        sub wave_once
+            4000 0 servo 250 delay 8000 0 servo 250 delay
+            return
+    ";
+    let disassembly = round_trip(source);
+    let expected = "\
+begin
+    begin
+        0 get_position
+        6000 <
+        while
+        begin
+            dup
+            while
+            1 -
+            dup
+            if
+                led_on
+            else
+                led_off
+            endif
+        repeat
+        drop
+        -5 32767 +
+        0 speed
+        wave
+    repeat
+repeat
+sub wave
+    3
+    begin
+        dup
+        while
+        1 -
+        wave_once
+    repeat
+    drop
+    return
+sub wave_once
+    4000 0 servo
+    250 delay
+    8000 0 servo
+    250 delay
+    return
+";
+    assert_eq!(disassembly, expected);
+
+    round_trip("1 if 2 if 3 endif else 4 if 5 else 6 endif endif");
+    round_trip("begin 1 while 2 if goto out endif repeat out: 7");
+    round_trip("a: 1 if goto a endif begin b: goto b repeat");
+    round_trip("");
+}
+
+#[test]
+fn disassembly_of_other_bytecode() {
+    // A conditional jump that no structure explains, and literal lists.
+    let program = Program {
+        bytecode: bytes(&[
+            Code(LiteralN),
+            Byte(2u8),
+            Word(1000u16),
+            Word(0xfffeu16),
+            Code(Literal8N),
+            Byte(1u8),
+            Byte(7u8),
+            Code(JumpZ),
+            Word(0u16),
+            Code(Call),
+            Word(15u16),
+            Code(Return),
+        ]),
+        subroutines: vec![],
+    };
+    let disassembly = disassemble(&program).unwrap();
+
+    let expected = "\
+label_0000:
+1000 -2 7
+if
+else
+    goto label_0000
+endif
+sub_000f
+sub sub_000f
+    return
+";
+    assert_eq!(disassembly, expected);
+
+    // The literal lists and the jump grow, but the names carry over.
+    let recompiled = compile(&disassembly).unwrap();
+    assert_eq!(recompiled.subroutine("sub_000f"), Some(0u8));
+    assert!(recompiled.bytecode.len() > program.bytecode.len());
+}
+
+#[test]
+fn subroutine_tables() {
+    let program =
+        compile("a b quit sub a return sub unused quit sub b return").unwrap();
+    let addresses: Vec<u16> = program
+        .subroutines
+        .iter()
+        .map(|subroutine| subroutine.address)
+        .collect();
+    let found = subroutines(&program.bytecode, &addresses).unwrap();
+
+    assert_eq!(
+        found
+            .iter()
+            .map(|subroutine| subroutine.address)
+            .collect::<Vec<_>>(),
+        addresses
+    );
+    assert_eq!(found[1usize].name, format!("sub_{:04x}", addresses[1usize]));
+    assert_eq!(
+        Program::from_bytecode(program.bytecode.clone(), &addresses)
+            .unwrap()
+            .subroutines,
+        found
+    );
+    assert_eq!(program.subroutine("B"), Some(2u8));
+}
+
+#[test]
+fn disassembly_of_single_byte_calls() {
+    let program = Program {
+        bytecode: bytes(&[
+            Code(Literal8),
+            Byte(1u8),
+            Byte(0x80u8),
+            Byte(0x81u8),
+            Code(Quit),
+            Code(Return),
+            Code(Literal8),
+            Byte(2u8),
+            Code(Times),
+            Code(Return),
+        ]),
+        subroutines: vec![
+            Subroutine {
+                name: "first".to_string(),
+                address: 5u16,
+            },
+            Subroutine {
+                name: "second".to_string(),
+                address: 6u16,
+            },
+        ],
+    };
+    let disassembly = disassemble(&program).unwrap();
+
+    let expected = "\
+1 first
+second
+quit
+sub first
+    return
+sub second
+    2 *
+    return
+";
+    assert_eq!(disassembly, expected);
+    assert_eq!(
+        subroutines(&program.bytecode, &[5u16, 6u16])
+            .unwrap()
+            .iter()
+            .map(|subroutine| subroutine.name.as_str())
+            .collect::<Vec<_>>(),
+        ["sub_0005", "sub_0006"]
+    );
+    assert!(matches!(
+        subroutines(&program.bytecode, &[5u16]),
+        Err(Error::InvalidBytecode(3u16))
+    ));
+}
+
+#[test]
+fn invalid_bytecode() {
+    let invalid = |bytecode: Vec<u8>| {
+        let program = Program {
+            bytecode,
+            subroutines: vec![],
+        };
+        match disassemble(&program) {
+            Err(Error::InvalidBytecode(address)) => address,
+            result => panic!("expected invalid bytecode, got {result:?}"),
+        }
+    };
+
+    assert_eq!(invalid(vec![Quit as u8, 0x01u8]), 1u16);
+    assert_eq!(invalid(vec![Quit as u8, 0x80u8]), 1u16);
+    assert_eq!(invalid(vec![Quit as u8, Literal as u8, 0u8]), 1u16);
+    assert_eq!(
+        invalid(vec![Literal8 as u8, 0u8, Jump as u8, 1u8, 0u8]),
+        2u16
+    );
+    assert_eq!(invalid(vec![Call as u8, 4u8, 0u8]), 0u16);
+    assert!(matches!(
+        Program::from_bytecode(vec![LiteralN as u8, 1u8, 0u8], &[]),
+        Err(Error::InvalidBytecode(0u16))
+    ));
+}
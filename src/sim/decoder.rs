@@ -0,0 +1,328 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+use derive_more::Display;
+
+use crate::maestro::internals;
+use crate::maestro::internals::CommandFlags;
+use crate::maestro::utils::crc7;
+
+/// ### Purpose:
+/// A single command, as decoded from the serial byte stream.
+///
+/// ### Notes:
+/// Channels are kept as the raw bytes that were received, since an emulated
+/// board must be able to observe (and reject) out-of-range channels.
+#[derive(Clone, PartialEq, Debug, Display)]
+pub enum Command {
+    #[allow(missing_docs)]
+    #[display(fmt = "SetTarget(channel: {}, target: {})", channel, target)]
+    SetTarget { channel: u8, target: u16 },
+
+    #[allow(missing_docs)]
+    #[display(fmt = "SetSpeed(channel: {}, speed: {})", channel, speed)]
+    SetSpeed { channel: u8, speed: u16 },
+
+    #[allow(missing_docs)]
+    #[display(
+        fmt = "SetAcceleration(channel: {}, acceleration: {})",
+        channel,
+        acceleration
+    )]
+    SetAcceleration { channel: u8, acceleration: u16 },
+
+    #[allow(missing_docs)]
+    #[display(fmt = "SetPwm(on_time: {}, period: {})", on_time, period)]
+    SetPwm { on_time: u16, period: u16 },
+
+    #[allow(missing_docs)]
+    #[display(fmt = "GetPosition(channel: {})", channel)]
+    GetPosition { channel: u8 },
+
+    #[allow(missing_docs)]
+    #[display(fmt = "GetMovingState")]
+    GetMovingState,
+
+    #[allow(missing_docs)]
+    #[display(
+        fmt = "SetMultipleTargets(first_channel: {}, targets: {:?})",
+        first_channel,
+        targets
+    )]
+    SetMultipleTargets {
+        first_channel: u8,
+        targets: Vec<u16>,
+    },
+
+    #[allow(missing_docs)]
+    #[display(fmt = "GetErrors")]
+    GetErrors,
+
+    #[allow(missing_docs)]
+    #[display(fmt = "GoHome")]
+    GoHome,
+
+    #[allow(missing_docs)]
+    #[display(fmt = "StopScript")]
+    StopScript,
+
+    #[allow(missing_docs)]
+    #[display(fmt = "RestartScript(subroutine: {})", subroutine)]
+    RestartScript { subroutine: u8 },
+
+    #[allow(missing_docs)]
+    #[display(
+        fmt = "RestartScriptWithParameter(subroutine: {}, parameter: {})",
+        subroutine,
+        parameter
+    )]
+    RestartScriptWithParameter { subroutine: u8, parameter: u16 },
+
+    #[allow(missing_docs)]
+    #[display(fmt = "GetScriptStatus")]
+    GetScriptStatus,
+
+    #[allow(missing_docs)]
+    #[display(fmt = "MiniSsc(servo: {}, position: {})", servo, position)]
+    MiniSsc { servo: u8, position: u8 },
+}
+
+/// ### Purpose:
+/// A fully decoded command packet.
+#[derive(Clone, PartialEq, Debug)]
+pub(super) struct Packet {
+    /// The addressed device number, or `None` for the compact protocol.
+    pub(super) device: Option<u8>,
+    pub(super) command: Command,
+}
+
+/// ### Purpose:
+/// Everything the decoder can report after consuming a byte.
+#[derive(Clone, PartialEq, Debug)]
+pub(super) enum Event {
+    Packet(Packet),
+    ProtocolError,
+    CrcError,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum State {
+    Idle,
+    Device,
+    Command {
+        device: u8,
+    },
+    Data {
+        device: Option<u8>,
+        flag: CommandFlags,
+    },
+    Crc,
+    MiniSsc,
+}
+
+/// ### Purpose:
+/// An incremental decoder for the compact and Pololu protocols.
+///
+/// ### Notes:
+/// Bytes with their top bit set always begin a new packet. If such a byte
+/// interrupts an unfinished packet, a protocol error is reported and the
+/// unfinished packet is dropped, as the Maestro itself does. The only
+/// exception is the Mini SSC protocol, whose two data bytes are taken as-is.
+///
+/// In CRC mode, every compact and Pololu packet must be followed by its CRC
+/// byte. Packets whose CRC does not match are reported and dropped.
+#[derive(Debug)]
+pub(super) struct Decoder {
+    pub(super) crc: bool,
+    state: State,
+    data: Vec<u8>,
+    raw: Vec<u8>,
+    pending: Option<Packet>,
+}
+
+impl Decoder {
+    pub(super) fn new() -> Self {
+        Self {
+            crc: false,
+            state: State::Idle,
+            data: Vec::with_capacity(internals::BUFFER_SIZE),
+            raw: Vec::with_capacity(internals::BUFFER_SIZE),
+            pending: None,
+        }
+    }
+
+    /// ### Purpose:
+    /// Consumes a single byte, pushing any resulting events into `events`.
+    pub(super) fn decode(&mut self, byte: u8, events: &mut Vec<Event>) {
+        if let State::MiniSsc = self.state {
+            self.data.push(byte);
+            if let [servo, position] = self.data[..] {
+                self.state = State::Idle;
+                events.push(Event::Packet(Packet {
+                    device: None,
+                    command: Command::MiniSsc { servo, position },
+                }));
+            };
+            return;
+        };
+        let is_command_byte = byte & 0x80u8 != 0u8;
+        if !is_command_byte {
+            self.raw.push(byte);
+        };
+        match (self.state, is_command_byte) {
+            (State::Idle, true) => self.start(byte, events),
+            (_, true) => {
+                events.push(Event::ProtocolError);
+                self.start(byte, events);
+            },
+            (State::Idle, false) => events.push(Event::ProtocolError),
+            (State::Crc, false) => {
+                self.state = State::Idle;
+                let packet = self.pending.take();
+                let event = match (crc7(&self.raw), packet) {
+                    (0u8, Some(packet)) => Event::Packet(packet),
+                    _ => Event::CrcError,
+                };
+                events.push(event);
+            },
+            (State::Device, false) => {
+                self.state = State::Command { device: byte }
+            },
+            (State::Command { device }, false) => {
+                self.begin(Some(device), byte | 0x80u8, events)
+            },
+            (State::Data { device, flag }, false) => {
+                self.data.push(byte);
+                self.try_finish(device, flag, events);
+            },
+            (State::MiniSsc, _) => unreachable!("handled above"),
+        }
+    }
+
+    fn start(&mut self, byte: u8, events: &mut Vec<Event>) {
+        self.raw.clear();
+        self.raw.push(byte);
+        match byte {
+            internals::SYNC => self.state = State::Device,
+            internals::MINI_SSC => {
+                self.data.clear();
+                self.state = State::MiniSsc;
+            },
+            _ => self.begin(None, byte, events),
+        }
+    }
+
+    fn begin(&mut self, device: Option<u8>, byte: u8, events: &mut Vec<Event>) {
+        self.data.clear();
+        match CommandFlags::try_from(byte) {
+            Ok(flag) => {
+                self.state = State::Data { device, flag };
+                self.try_finish(device, flag, events);
+            },
+            Err(_) => {
+                self.state = State::Idle;
+                events.push(Event::ProtocolError);
+            },
+        }
+    }
+
+    fn try_finish(
+        &mut self,
+        device: Option<u8>,
+        flag: CommandFlags,
+        events: &mut Vec<Event>,
+    ) {
+        let Self { data, .. } = self;
+        if data.len() < data_length(flag, data) {
+            return;
+        };
+        let word = |index: usize| {
+            u16::from(data[index]) | (u16::from(data[index + 1usize]) << 7usize)
+        };
+        let command = match flag {
+            CommandFlags::SetTarget => Command::SetTarget {
+                channel: data[0usize],
+                target: word(1usize),
+            },
+            CommandFlags::SetSpeed => Command::SetSpeed {
+                channel: data[0usize],
+                speed: word(1usize),
+            },
+            CommandFlags::SetAcceleration => Command::SetAcceleration {
+                channel: data[0usize],
+                acceleration: word(1usize),
+            },
+            CommandFlags::SetPwm => Command::SetPwm {
+                on_time: word(0usize),
+                period: word(2usize),
+            },
+            CommandFlags::GetPosition => Command::GetPosition {
+                channel: data[0usize],
+            },
+            CommandFlags::GetMovingState => Command::GetMovingState,
+            CommandFlags::SetMultipleTargets => Command::SetMultipleTargets {
+                first_channel: data[1usize],
+                targets: (2usize..data.len())
+                    .step_by(2usize)
+                    .map(word)
+                    .collect(),
+            },
+            CommandFlags::GetErrors => Command::GetErrors,
+            CommandFlags::GoHome => Command::GoHome,
+            CommandFlags::StopScript => Command::StopScript,
+            CommandFlags::RestartScriptAtSubRoutine => Command::RestartScript {
+                subroutine: data[0usize],
+            },
+            CommandFlags::RestartScriptAtSubRoutineWithParameter => {
+                Command::RestartScriptWithParameter {
+                    subroutine: data[0usize],
+                    parameter: word(1usize),
+                }
+            },
+            CommandFlags::GetScriptStatus => Command::GetScriptStatus,
+        };
+        let packet = Packet { device, command };
+        match self.crc {
+            true => {
+                self.state = State::Crc;
+                self.pending = Some(packet);
+            },
+            false => {
+                self.state = State::Idle;
+                events.push(Event::Packet(packet));
+            },
+        }
+    }
+}
+
+/// ### Purpose:
+/// The number of data bytes which follow the given command byte.
+///
+/// ### Notes:
+/// The length of a Set Multiple Targets packet depends on its first data byte
+/// (the number of targets), so the data received so far is needed as well.
+fn data_length(flag: CommandFlags, data: &[u8]) -> usize {
+    match flag {
+        CommandFlags::SetPwm => 4usize,
+        CommandFlags::SetMultipleTargets => match data.first() {
+            Some(&count) => 2usize + 2usize * usize::from(count),
+            None => 1usize,
+        },
+        CommandFlags::SetTarget
+        | CommandFlags::SetSpeed
+        | CommandFlags::SetAcceleration
+        | CommandFlags::RestartScriptAtSubRoutineWithParameter => 3usize,
+        CommandFlags::GetPosition | CommandFlags::RestartScriptAtSubRoutine => {
+            1usize
+        },
+        CommandFlags::GetMovingState
+        | CommandFlags::GetErrors
+        | CommandFlags::GoHome
+        | CommandFlags::StopScript
+        | CommandFlags::GetScriptStatus => 0usize,
+    }
+}
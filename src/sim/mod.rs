@@ -0,0 +1,446 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! An in-process emulator of the Maestro board.
+//!
+//! The [`Emulator`] decodes the same byte stream that a
+//! [`crate::maestro::Maestro`] emits (both the compact and the Pololu
+//! protocols, as well as Mini SSC), keeps track of each channel's target,
+//! speed and acceleration, and ramps each channel's position towards its
+//! target as simulated time is advanced.
+//!
+//! ### Examples:
+//! ```rust
+//! # use std::time::Duration;
+//! # use raestro::maestro::builder::Builder;
+//! # use raestro::maestro::constants::Channel;
+//! # use raestro::maestro::Maestro;
+//! # use raestro::sim::Emulator;
+//! # fn main() -> raestro::Result<()> {
+//! let mut maestro: Maestro<Emulator> =
+//!     Builder::default().build_with(Emulator::new())?;
+//!
+//! maestro.set_target(Channel::Channel0, 4000u16)?;
+//! maestro.set_speed(Channel::Channel0, 10u16)?;
+//! maestro.set_target(Channel::Channel0, 6000u16)?;
+//!
+//! // 10 quarter-us per 10ms, for 100ms.
+//! maestro.transport_mut().advance(Duration::from_millis(100));
+//! assert_eq!(maestro.get_position(Channel::Channel0)?, 4100u16);
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+mod decoder;
+mod servo;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+
+pub use self::decoder::Command;
+use self::decoder::Decoder;
+use self::decoder::Event;
+use self::servo::Servo;
+use crate::errors::Error;
+use crate::maestro::constants::Channel;
+use crate::maestro::constants::ErrorValues;
+use crate::maestro::constants::Model;
+use crate::maestro::internals;
+use crate::maestro::mini_ssc::MiniSscSettings;
+use crate::maestro::pwm::Pwm;
+use crate::transport::Transport;
+
+/// ### Purpose:
+/// The length of a single simulation step.
+const TICK: Duration = Duration::from_millis(10u64);
+
+/// ### Purpose:
+/// An emulated Maestro board.
+///
+/// ### Notes:
+/// By default, simulated time only moves forward through
+/// [`Emulator::advance`], which keeps tests deterministic. Alternatively, the
+/// emulator can follow the wall clock (see [`Emulator::set_realtime`]).
+///
+/// The emulator does not run scripts; restarting a script is accepted, but
+/// the script is always reported as stopped (see
+/// [`crate::script::interpreter`] for running scripts offline).
+///
+/// The inspection methods (i.e., [`Emulator::position`]) return
+/// [`Error::InvalidChannel`] if given a channel which does not exist on the
+/// emulated model (see [`Channel::new`]).
+#[derive(Debug)]
+pub struct Emulator {
+    model: Model,
+    device_number: u8,
+    mini_ssc: MiniSscSettings,
+    servos: Vec<Servo>,
+    pwm: Option<Pwm>,
+    errors: u16,
+    decoder: Decoder,
+    responses: VecDeque<u8>,
+    elapsed: Duration,
+    last_update: Option<Instant>,
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Emulator {
+    /// ### Purpose:
+    /// Creates a new emulated Micro Maestro with all channels turned off.
+    pub fn new() -> Self {
+        Self::with_model(Model::default())
+    }
+
+    /// ### Purpose:
+    /// Creates a new emulator of the given model with all channels turned
+    /// off.
+    pub fn with_model(model: Model) -> Self {
+        Self {
+            model,
+            device_number: internals::DEVICE_NUMBER,
+            mini_ssc: MiniSscSettings::default(),
+            servos: vec![Servo::default(); model.channel_count() as usize],
+            pwm: None,
+            errors: 0u16,
+            decoder: Decoder::new(),
+            responses: VecDeque::new(),
+            elapsed: Duration::ZERO,
+            last_update: None,
+        }
+    }
+
+    /// ### Purpose:
+    /// The model of board being emulated.
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// ### Purpose:
+    /// Sets the device number this emulator answers to in the Pololu
+    /// protocol.
+    pub fn set_device_number(&mut self, device_number: u8) {
+        self.device_number = device_number;
+    }
+
+    /// ### Purpose:
+    /// Makes the emulator follow the wall clock.
+    ///
+    /// ### Notes:
+    /// When enabled, time is advanced by however long it has been since the
+    /// last time bytes were received, right before new bytes are processed.
+    pub fn set_realtime(&mut self, realtime: bool) {
+        self.last_update = realtime.then(Instant::now);
+    }
+
+    /// ### Purpose:
+    /// Enables or disables CRC mode.
+    ///
+    /// ### Notes:
+    /// In CRC mode, every compact and Pololu command packet must end with its
+    /// CRC byte. Packets with an incorrect CRC byte are discarded and raise
+    /// [`ErrorValues::SerCrcError`].
+    pub fn set_crc(&mut self, crc: bool) {
+        self.decoder.crc = crc;
+    }
+
+    /// ### Purpose:
+    /// Sets the Mini SSC settings this emulator maps Mini SSC commands with.
+    pub fn set_mini_ssc(&mut self, mut mini_ssc: MiniSscSettings) {
+        mini_ssc.resize();
+        self.mini_ssc = mini_ssc;
+    }
+
+    /// ### Purpose:
+    /// Sets the target that the given channel is sent to on `GoHome`.
+    ///
+    /// ### Notes:
+    /// Defaults to `0` (i.e., the channel is turned off).
+    pub fn set_home(
+        &mut self,
+        channel: Channel,
+        home: u16,
+    ) -> crate::Result<()> {
+        let servo = self
+            .servos
+            .get_mut(channel as usize)
+            .ok_or(Error::InvalidChannel(channel as u8))?;
+        servo.home = home;
+        Ok(())
+    }
+
+    /// ### Purpose:
+    /// Configures the given channel as an input, reading the given level.
+    ///
+    /// ### Notes:
+    /// The level is what `GetPosition` responds with (i.e., `0` to `1023`
+    /// for an analog input). Input channels ignore any targets sent to them.
+    pub fn set_input(
+        &mut self,
+        channel: Channel,
+        level: u16,
+    ) -> crate::Result<()> {
+        let servo = self
+            .servos
+            .get_mut(channel as usize)
+            .ok_or(Error::InvalidChannel(channel as u8))?;
+        servo.input = Some(level);
+        Ok(())
+    }
+
+    /// ### Purpose:
+    /// Feeds the given bytes into the emulator, as if they had been received
+    /// over its RX line.
+    ///
+    /// ### Notes:
+    /// Returns every command addressed to this emulator, in the order they
+    /// were executed. Responses to any "get" commands are queued up to be
+    /// read back through [`Transport::read`].
+    pub fn receive(&mut self, bytes: &[u8]) -> Vec<Command> {
+        if let Some(last_update) = self.last_update {
+            let now = Instant::now();
+            self.advance(now - last_update);
+            self.last_update = Some(now);
+        };
+        let mut events = vec![];
+        bytes
+            .iter()
+            .for_each(|&byte| self.decoder.decode(byte, &mut events));
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Packet(packet) => {
+                    let addressed = packet
+                        .device
+                        .is_none_or(|device| device == self.device_number);
+                    addressed.then(|| {
+                        self.execute(packet.command.clone());
+                        packet.command
+                    })
+                },
+                Event::ProtocolError => {
+                    self.raise(ErrorValues::SerProtocolError);
+                    None
+                },
+                Event::CrcError => {
+                    self.raise(ErrorValues::SerCrcError);
+                    None
+                },
+            })
+            .collect()
+    }
+
+    /// ### Purpose:
+    /// Advances simulated time by the given duration.
+    ///
+    /// ### Notes:
+    /// Channels are stepped in whole 10ms ticks; any remainder is carried
+    /// over to the next call.
+    pub fn advance(&mut self, duration: Duration) {
+        self.elapsed += duration;
+        while self.elapsed >= TICK {
+            self.elapsed -= TICK;
+            self.servos.iter_mut().for_each(Servo::tick);
+        }
+    }
+
+    /// ### Purpose:
+    /// The current (ramped) position of the given channel, in quarter-us.
+    pub fn position(&self, channel: Channel) -> crate::Result<u16> {
+        self.servo(channel).map(Servo::position)
+    }
+
+    /// ### Purpose:
+    /// The current target of the given channel, in quarter-us.
+    pub fn target(&self, channel: Channel) -> crate::Result<u16> {
+        self.servo(channel).map(|servo| servo.target)
+    }
+
+    /// ### Purpose:
+    /// The current speed limit of the given channel.
+    pub fn speed(&self, channel: Channel) -> crate::Result<u16> {
+        self.servo(channel).map(|servo| servo.speed)
+    }
+
+    /// ### Purpose:
+    /// The current acceleration limit of the given channel.
+    pub fn acceleration(&self, channel: Channel) -> crate::Result<u8> {
+        self.servo(channel).map(|servo| servo.acceleration)
+    }
+
+    /// ### Purpose:
+    /// The signal on the general-purpose PWM output, if it has been turned
+    /// on.
+    pub fn pwm(&self) -> Option<Pwm> {
+        self.pwm
+    }
+
+    /// ### Purpose:
+    /// Whether any channel has not yet reached its target.
+    pub fn is_moving(&self) -> bool {
+        self.servos.iter().any(Servo::is_moving)
+    }
+
+    /// ### Purpose:
+    /// The errors which have been raised since they were last read.
+    pub fn errors(&self) -> Vec<ErrorValues> {
+        ErrorValues::from_data(self.errors)
+    }
+
+    /// ### Purpose:
+    /// Raises the given error, as the board itself would.
+    pub(crate) fn raise(&mut self, error: ErrorValues) {
+        self.errors |= 1u16 << (error as u16);
+    }
+
+    fn respond(&mut self, data: u16) {
+        self.responses.extend(data.to_le_bytes());
+    }
+
+    /// ### Purpose:
+    /// The state of the given channel, for the inspection methods.
+    ///
+    /// ### Notes:
+    /// Unlike `servo_mut`, this does not raise an error on the emulated
+    /// board.
+    fn servo(&self, channel: Channel) -> crate::Result<&Servo> {
+        self.servos
+            .get(channel as usize)
+            .ok_or(Error::InvalidChannel(channel as u8))
+    }
+
+    fn servo_mut(&mut self, channel: u8) -> Option<&mut Servo> {
+        let channel = channel as usize;
+        if channel >= self.servos.len() {
+            self.raise(ErrorValues::SerProtocolError);
+        };
+        self.servos.get_mut(channel)
+    }
+
+    /// ### Purpose:
+    /// The Mini SSC settings the emulator maps 8-bit targets with.
+    pub(crate) fn mini_ssc(&self) -> &MiniSscSettings {
+        &self.mini_ssc
+    }
+
+    /// ### Purpose:
+    /// Executes a single command, as if it had been received.
+    pub(crate) fn execute(&mut self, command: Command) {
+        match command {
+            Command::SetTarget { channel, target } => {
+                if let Some(servo) = self.servo_mut(channel) {
+                    servo.set_target(target);
+                };
+            },
+            Command::SetSpeed { channel, speed } => {
+                if let Some(servo) = self.servo_mut(channel) {
+                    servo.speed = speed;
+                };
+            },
+            Command::SetAcceleration {
+                channel,
+                acceleration,
+            } => {
+                if let Some(servo) = self.servo_mut(channel) {
+                    servo.acceleration = acceleration.min(255u16) as u8;
+                };
+            },
+            Command::SetPwm { on_time, period } => {
+                // The Micro Maestro does not know this command at all.
+                if !self.model.supports_pwm() {
+                    self.raise(ErrorValues::SerProtocolError);
+                    return;
+                };
+                self.pwm = (on_time != 0u16).then_some(Pwm { on_time, period });
+            },
+            Command::GetPosition { channel } => {
+                if let Some(position) =
+                    self.servo_mut(channel).map(|servo| servo.position())
+                {
+                    self.respond(position);
+                };
+            },
+            Command::SetMultipleTargets {
+                first_channel,
+                targets,
+            } => {
+                // The Micro Maestro does not know this command at all.
+                if !self.model.supports_set_multiple_targets() {
+                    self.raise(ErrorValues::SerProtocolError);
+                    return;
+                };
+                (first_channel..)
+                    .zip(targets)
+                    .for_each(|(channel, target)| {
+                        if let Some(servo) = self.servo_mut(channel) {
+                            servo.set_target(target);
+                        };
+                    });
+            },
+            Command::GetMovingState => {
+                let moving = self.is_moving() as u8;
+                self.responses.push_back(moving);
+            },
+            Command::GetErrors => {
+                let errors = std::mem::take(&mut self.errors);
+                self.respond(errors);
+            },
+            Command::GoHome => self
+                .servos
+                .iter_mut()
+                .for_each(|servo| servo.set_target(servo.home)),
+            Command::StopScript
+            | Command::RestartScript { .. }
+            | Command::RestartScriptWithParameter { .. } => (),
+            Command::GetScriptStatus => self.responses.push_back(1u8),
+            Command::MiniSsc { servo, position } => {
+                // Servo numbers outside of this board's window belong to
+                // other devices on the line, so they are silently ignored.
+                let index = self
+                    .mini_ssc
+                    .channel_index(servo)
+                    .filter(|&index| index < self.servos.len());
+                if let Some(index) = index {
+                    let target =
+                        self.mini_ssc.ranges[index].to_target(position);
+                    self.servos[index].set_target(target);
+                };
+            },
+        }
+    }
+}
+
+impl Transport for Emulator {
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        let count = buf.len().min(self.responses.len());
+        buf.iter_mut()
+            .zip(self.responses.drain(..count))
+            .for_each(|(slot, byte)| *slot = byte);
+        Ok(count)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize> {
+        self.receive(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn set_read_timeout(&mut self, _: Duration) -> crate::Result<()> {
+        Ok(())
+    }
+}
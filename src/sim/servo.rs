@@ -0,0 +1,93 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+/// ### Purpose:
+/// The emulated state of a single channel.
+///
+/// ### Notes:
+/// Positions and targets are in quarter-us. Velocities are in the Maestro's
+/// speed units (quarter-us per 10ms), and the board is stepped one 10ms tick
+/// at a time. A target of `0` turns the channel off; turning a channel back on
+/// makes it jump straight to its new target, since the Maestro has no idea
+/// where the servo actually is.
+///
+/// Input channels ignore their targets entirely, and always report their
+/// input level as their position.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Servo {
+    pub(super) target: u16,
+    pub(super) speed: u16,
+    pub(super) acceleration: u8,
+    pub(super) home: u16,
+    pub(super) input: Option<u16>,
+    position: f64,
+    velocity: f64,
+}
+
+impl Servo {
+    pub(super) fn set_target(&mut self, target: u16) {
+        if self.input.is_some() {
+            return;
+        };
+        if target == 0u16 || self.target == 0u16 {
+            self.position = f64::from(target);
+            self.velocity = 0f64;
+        };
+        self.target = target;
+    }
+
+    pub(super) fn position(&self) -> u16 {
+        match self.input {
+            Some(level) => level,
+            None => self.position.round() as u16,
+        }
+    }
+
+    pub(super) fn is_moving(&self) -> bool {
+        self.input.is_none() && self.position() != self.target
+    }
+
+    /// ### Purpose:
+    /// Advances this channel by a single 10ms tick.
+    ///
+    /// ### Notes:
+    /// With an acceleration limit, the velocity changes by
+    /// `acceleration / 8` every tick (i.e., by `acceleration` every 80ms),
+    /// and is capped so that the servo can always decelerate in time to stop
+    /// exactly on its target.
+    pub(super) fn tick(&mut self) {
+        let distance = f64::from(self.target) - self.position;
+        if distance == 0f64 {
+            self.velocity = 0f64;
+            return;
+        };
+        let direction = distance.signum();
+        let max_speed = match self.speed {
+            0u16 => f64::INFINITY,
+            speed => f64::from(speed),
+        };
+        self.velocity = match self.acceleration {
+            0u8 => direction * max_speed,
+            acceleration => {
+                let acceleration = f64::from(acceleration) / 8f64;
+                let stopping_speed =
+                    (2f64 * acceleration * distance.abs()).sqrt();
+                let desired = direction * max_speed.min(stopping_speed);
+                let change = (desired - self.velocity)
+                    .clamp(-acceleration, acceleration);
+                self.velocity + change
+            },
+        };
+        match self.velocity * direction >= distance.abs() {
+            true => {
+                self.position = f64::from(self.target);
+                self.velocity = 0f64;
+            },
+            false => self.position += self.velocity,
+        };
+    }
+}
@@ -0,0 +1,417 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::maestro::builder::Builder;
+use crate::maestro::constants::Channel;
+use crate::maestro::constants::ChannelMode;
+use crate::maestro::constants::ErrorValues;
+use crate::maestro::constants::Model;
+use crate::maestro::constants::Protocol;
+use crate::maestro::mini_ssc::MiniSscSettings;
+use crate::maestro::pwm::Pwm;
+use crate::maestro::Maestro;
+use crate::sim::Command;
+use crate::sim::Emulator;
+use crate::transport::Transport;
+
+fn maestro() -> Maestro<Emulator> {
+    Builder::default().build_with(Emulator::new()).unwrap()
+}
+
+#[test]
+fn first_target_jumps() {
+    let mut maestro = maestro();
+    maestro.set_speed(Channel::Channel0, 1u16).unwrap();
+    maestro.set_target(Channel::Channel0, 6000u16).unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel0).unwrap(), 6000u16);
+}
+
+#[test]
+fn speed_ramp() {
+    let mut maestro = maestro();
+    maestro.set_target(Channel::Channel1, 4000u16).unwrap();
+    maestro.set_speed(Channel::Channel1, 20u16).unwrap();
+    maestro.set_target(Channel::Channel1, 5000u16).unwrap();
+    maestro
+        .transport_mut()
+        .advance(Duration::from_millis(255u64));
+
+    assert_eq!(maestro.get_position(Channel::Channel1).unwrap(), 4500u16);
+    assert!(maestro.transport().is_moving());
+
+    maestro
+        .transport_mut()
+        .advance(Duration::from_millis(245u64));
+
+    assert_eq!(maestro.get_position(Channel::Channel1).unwrap(), 5000u16);
+    assert!(!maestro.transport().is_moving());
+}
+
+#[test]
+fn acceleration_ramp() {
+    let mut maestro = maestro();
+    maestro.set_target(Channel::Channel2, 4000u16).unwrap();
+    maestro.set_acceleration(Channel::Channel2, 8u8).unwrap();
+    maestro.set_target(Channel::Channel2, 8000u16).unwrap();
+    let mut positions = vec![];
+    for _ in 0..3 {
+        maestro
+            .transport_mut()
+            .advance(Duration::from_millis(10u64));
+        positions.push(maestro.get_position(Channel::Channel2).unwrap());
+    }
+
+    // The velocity grows by 1 quarter-us per 10ms every tick.
+    assert_eq!(positions, vec![4001u16, 4003u16, 4006u16]);
+
+    maestro.transport_mut().advance(Duration::from_secs(5u64));
+
+    assert_eq!(maestro.get_position(Channel::Channel2).unwrap(), 8000u16);
+}
+
+#[test]
+fn go_home() {
+    let mut maestro = maestro();
+    maestro
+        .transport_mut()
+        .set_home(Channel::Channel3, 6000u16)
+        .unwrap();
+    maestro.set_target(Channel::Channel3, 4000u16).unwrap();
+    maestro.set_target(Channel::Channel4, 4000u16).unwrap();
+    maestro.go_home().unwrap();
+    maestro
+        .transport_mut()
+        .advance(Duration::from_millis(10u64));
+
+    assert_eq!(
+        maestro.transport().position(Channel::Channel3).unwrap(),
+        6000u16
+    );
+    assert_eq!(
+        maestro.transport().position(Channel::Channel4).unwrap(),
+        0u16
+    );
+}
+
+#[test]
+fn compact_protocol() {
+    let mut emulator = Emulator::new();
+    let commands = emulator.receive(&[0x84u8, 0x05u8, 0x70u8, 0x2eu8, 0x93u8]);
+
+    assert_eq!(
+        commands,
+        vec![
+            Command::SetTarget {
+                channel: 5u8,
+                target: 6000u16
+            },
+            Command::GetMovingState,
+        ]
+    );
+    let mut buf = [0u8; 1usize];
+    emulator.read(&mut buf).unwrap();
+    assert_eq!(buf, [0x00u8]);
+}
+
+#[test]
+fn other_device_ignored() {
+    let mut emulator = Emulator::new();
+    let commands = emulator.receive(&[0xaau8, 0x0du8, 0x22u8]);
+
+    assert!(commands.is_empty());
+}
+
+#[test]
+fn protocol_error() {
+    let mut maestro = maestro();
+    maestro
+        .transport_mut()
+        .receive(&[0xaau8, 0x0cu8, 0x04u8, 0x00u8]);
+    let errors = maestro.get_errors().unwrap();
+
+    assert_eq!(errors, vec![ErrorValues::SerProtocolError]);
+    assert!(maestro.get_errors().unwrap().is_empty());
+}
+
+#[test]
+fn set_multiple_targets() {
+    let mut maestro = Builder::default()
+        .model(Model::MiniMaestro12)
+        .crc(true)
+        .build_with(Emulator::with_model(Model::MiniMaestro12))
+        .unwrap();
+    maestro.transport_mut().set_crc(true);
+    maestro
+        .set_targets(Channel::Channel3, &[4000u16, 5000u16, 6000u16])
+        .unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel3).unwrap(), 4000u16);
+    assert_eq!(maestro.get_position(Channel::Channel5).unwrap(), 6000u16);
+    assert!(maestro.get_errors().unwrap().is_empty());
+}
+
+#[test]
+fn set_targets_fallback() {
+    let mut maestro = Builder::default()
+        .crc(true)
+        .build_with(Emulator::new())
+        .unwrap();
+    maestro.transport_mut().set_crc(true);
+    maestro
+        .set_targets(Channel::Channel4, &[4000u16, 6000u16])
+        .unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel4).unwrap(), 4000u16);
+    assert_eq!(maestro.get_position(Channel::Channel5).unwrap(), 6000u16);
+    assert!(maestro.get_errors().unwrap().is_empty());
+}
+
+#[test]
+fn set_multiple_targets_unsupported() {
+    let mut emulator = Emulator::new();
+    let commands = emulator.receive(&[0x9fu8, 0x01u8, 0x00u8, 0x70u8, 0x2eu8]);
+
+    assert_eq!(
+        commands,
+        vec![Command::SetMultipleTargets {
+            first_channel: 0u8,
+            targets: vec![6000u16],
+        }]
+    );
+    assert_eq!(emulator.target(Channel::Channel0).unwrap(), 0u16);
+    assert_eq!(emulator.errors(), vec![ErrorValues::SerProtocolError]);
+}
+
+#[test]
+fn mini_maestro_channels() {
+    let mut emulator = Emulator::with_model(Model::MiniMaestro24);
+    emulator.receive(&[0x84u8, 0x17u8, 0x70u8, 0x2eu8]);
+
+    assert_eq!(emulator.target(Channel::Channel23).unwrap(), 6000u16);
+    assert!(emulator.errors().is_empty());
+    emulator.receive(&[0x84u8, 0x18u8, 0x70u8, 0x2eu8]);
+
+    assert_eq!(emulator.errors(), vec![ErrorValues::SerProtocolError]);
+}
+
+#[test]
+fn inspecting_missing_channel() {
+    let mut emulator = Emulator::new();
+
+    assert!(matches!(
+        emulator.position(Channel::Channel6),
+        Err(Error::InvalidChannel(6u8))
+    ));
+    assert!(matches!(
+        emulator.set_input(Channel::Channel6, 1023u16),
+        Err(Error::InvalidChannel(6u8))
+    ));
+    assert!(emulator.errors().is_empty());
+}
+
+#[test]
+fn digital_input() {
+    let mut emulator = Emulator::new();
+    emulator.set_input(Channel::Channel0, 1023u16).unwrap();
+    emulator.set_input(Channel::Channel1, 200u16).unwrap();
+    let mut maestro = Builder::default()
+        .channel_mode(Channel::Channel0, ChannelMode::Input)
+        .channel_mode(Channel::Channel1, ChannelMode::Input)
+        .build_with(emulator)
+        .unwrap();
+
+    assert!(maestro.read_digital_input(Channel::Channel0).unwrap());
+    assert!(!maestro.read_digital_input(Channel::Channel1).unwrap());
+    maestro
+        .transport_mut()
+        .receive(&[0x84u8, 0x00u8, 0x70u8, 0x2eu8]);
+
+    assert_eq!(maestro.get_position(Channel::Channel0).unwrap(), 1023u16);
+    assert!(!maestro.is_moving().unwrap());
+}
+
+#[test]
+fn analog_input() {
+    let mut emulator = Emulator::new();
+    emulator.set_input(Channel::Channel2, 512u16).unwrap();
+    let mut maestro = Builder::default()
+        .channel_mode(Channel::Channel2, ChannelMode::Input)
+        .build_with(emulator)
+        .unwrap();
+    let reading = maestro.read_analog(Channel::Channel2).unwrap();
+
+    assert_eq!(reading.raw, 512u16);
+    assert!((reading.volts - 2.502f64).abs() < 0.001f64);
+    let reading = maestro
+        .read_analog_averaged(Channel::Channel2, 4usize, Duration::ZERO)
+        .unwrap();
+
+    assert_eq!(reading.raw, 512u16);
+}
+
+#[test]
+fn analog_input_rejected_on_digital_channels() {
+    let mut maestro = Builder::default()
+        .model(Model::MiniMaestro18)
+        .channel_mode(Channel::Channel12, ChannelMode::Input)
+        .build_with(Emulator::with_model(Model::MiniMaestro18))
+        .unwrap();
+    let result = maestro.read_analog(Channel::Channel12);
+
+    assert!(matches!(result, Err(Error::InvalidChannelMode(12u8))));
+}
+
+#[test]
+fn pwm_output() {
+    let mut maestro = Builder::default()
+        .model(Model::MiniMaestro18)
+        .channel_mode(Channel::Channel12, ChannelMode::Output)
+        .build_with(Emulator::with_model(Model::MiniMaestro18))
+        .unwrap();
+    maestro
+        .set_pwm(Pwm::new(1200u16, 4800u16).unwrap())
+        .unwrap();
+
+    let pwm = maestro.transport().pwm().unwrap();
+    assert_eq!((pwm.on_time, pwm.period), (1200u16, 4800u16));
+    maestro.set_pwm(Pwm::new(0u16, 4800u16).unwrap()).unwrap();
+
+    assert!(maestro.transport().pwm().is_none());
+    let mut emulator = Emulator::new();
+    emulator.receive(&[0x8au8, 0x30u8, 0x09u8, 0x40u8, 0x25u8]);
+
+    assert!(emulator.pwm().is_none());
+    assert_eq!(emulator.errors(), vec![ErrorValues::SerProtocolError]);
+}
+
+#[test]
+fn release() {
+    let mut maestro = Builder::default()
+        .model(Model::MiniMaestro12)
+        .crc(true)
+        .build_with(Emulator::with_model(Model::MiniMaestro12))
+        .unwrap();
+    maestro.transport_mut().set_crc(true);
+    maestro
+        .set_targets(Channel::Channel0, &[6000u16; 12usize])
+        .unwrap();
+    maestro.release(Channel::Channel3).unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel3).unwrap(), 0u16);
+    assert_eq!(maestro.get_position(Channel::Channel4).unwrap(), 6000u16);
+    maestro.release_all().unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel11).unwrap(), 0u16);
+    assert!(maestro.get_errors().unwrap().is_empty());
+}
+
+#[test]
+fn script_status() {
+    let mut emulator = Emulator::new();
+    emulator.receive(&[0xaau8, 0x0cu8, 0x2eu8]);
+    let mut buf = [0u8; 1usize];
+    emulator.read(&mut buf).unwrap();
+
+    assert_eq!(buf, [0x01u8]);
+}
+
+#[test]
+fn compact_maestro() {
+    let mut maestro = Builder::default()
+        .protocol(Protocol::Compact)
+        .build_with(Emulator::new())
+        .unwrap();
+    maestro.set_target(Channel::Channel5, 5000u16).unwrap();
+    maestro.set_speed(Channel::Channel5, 10u16).unwrap();
+    maestro.set_acceleration(Channel::Channel5, 4u8).unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel5).unwrap(), 5000u16);
+    assert_eq!(maestro.transport().speed(Channel::Channel5).unwrap(), 10u16);
+    assert_eq!(
+        maestro.transport().acceleration(Channel::Channel5).unwrap(),
+        4u8
+    );
+    assert!(maestro.get_errors().unwrap().is_empty());
+}
+
+#[test]
+fn mini_ssc() {
+    let settings = MiniSscSettings::default().offset(4u8).range(
+        Channel::Channel1,
+        6000u16,
+        2540u16,
+    );
+    let mut emulator = Emulator::new();
+    emulator.set_mini_ssc(settings.clone());
+    let mut maestro = Builder::default()
+        .mini_ssc(settings)
+        .build_with(emulator)
+        .unwrap();
+    maestro
+        .mini_ssc()
+        .set_position(Channel::Channel1, 254u8)
+        .unwrap();
+    maestro
+        .mini_ssc()
+        .set_target(Channel::Channel0, 6000u16)
+        .unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel1).unwrap(), 8540u16);
+    assert_eq!(maestro.get_position(Channel::Channel0).unwrap(), 6000u16);
+
+    // Servo numbers 0 to 3 belong to other devices.
+    let commands = maestro.transport_mut().receive(&[0xffu8, 0x03u8, 0xfeu8]);
+    assert_eq!(
+        commands,
+        vec![Command::MiniSsc {
+            servo: 3u8,
+            position: 254u8
+        }]
+    );
+    assert!(maestro.get_errors().unwrap().is_empty());
+}
+
+#[test]
+fn crc_mode() {
+    let mut emulator = Emulator::new();
+    emulator.set_crc(true);
+    let mut maestro =
+        Builder::default().crc(true).build_with(emulator).unwrap();
+    maestro.set_target(Channel::Channel0, 6000u16).unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel0).unwrap(), 6000u16);
+
+    // A corrupted target is discarded by the board.
+    let commands = maestro
+        .transport_mut()
+        .receive(&[0x84u8, 0x00u8, 0x70u8, 0x1fu8, 0x00u8]);
+    assert!(commands.is_empty());
+    assert_eq!(
+        maestro.transport().target(Channel::Channel0).unwrap(),
+        6000u16
+    );
+    assert_eq!(
+        maestro.get_errors().unwrap(),
+        vec![ErrorValues::SerCrcError]
+    );
+}
+
+#[test]
+fn crc_missing() {
+    let mut emulator = Emulator::new();
+    emulator.set_crc(true);
+    let mut maestro = Builder::default().build_with(emulator).unwrap();
+    let result = maestro.set_target(Channel::Channel0, 6000u16);
+
+    // Without CRC mode on the host, nothing is ever executed.
+    assert!(result.is_ok());
+    assert_eq!(maestro.transport().target(Channel::Channel0).unwrap(), 0u16);
+}
@@ -0,0 +1,75 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::transport::Transport;
+
+/// ### Purpose:
+/// An in-memory [`Transport`].
+///
+/// ### Notes:
+/// Every byte written is appended to an internal log which can be inspected
+/// through [`Memory::written`]. Reads are served from a queue of responses
+/// which must be loaded ahead of time through [`Memory::queue_response`]. If
+/// the queue runs dry, reads return early (as a real link would after timing
+/// out).
+#[derive(Default, Debug)]
+pub struct Memory {
+    incoming: VecDeque<u8>,
+    outgoing: Vec<u8>,
+}
+
+impl Memory {
+    /// ### Purpose:
+    /// Creates a new, empty in-memory transport.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Purpose:
+    /// Queues the given bytes to be returned by subsequent reads.
+    pub fn queue_response(&mut self, bytes: &[u8]) {
+        self.incoming.extend(bytes);
+    }
+
+    /// ### Purpose:
+    /// All bytes written to this transport so far.
+    pub fn written(&self) -> &[u8] {
+        &self.outgoing
+    }
+
+    /// ### Purpose:
+    /// Takes all bytes written to this transport so far, clearing the log.
+    pub fn take_written(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.outgoing)
+    }
+}
+
+impl Transport for Memory {
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        let count = buf.len().min(self.incoming.len());
+        buf.iter_mut()
+            .zip(self.incoming.drain(..count))
+            .for_each(|(slot, byte)| *slot = byte);
+        Ok(count)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize> {
+        self.outgoing.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn set_read_timeout(&mut self, _: Duration) -> crate::Result<()> {
+        Ok(())
+    }
+}
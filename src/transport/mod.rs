@@ -0,0 +1,93 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! The byte-level links over which a [`crate::maestro::Maestro`] can talk to
+//! its board.
+//!
+//! The [`Transport`] trait is implemented for [`rppal::uart::Uart`] (the
+//! Raspberry Pi's GPIO UART), for [`Serial`] (any serial port, including the
+//! Maestro's USB virtual COM port) and for [`Memory`], an in-memory buffer
+//! which is useful for running `raestro` off of the Pi.
+
+#[cfg(test)]
+mod tests;
+
+mod memory;
+mod serial;
+mod uart;
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+pub use self::memory::Memory;
+pub use self::serial::Serial;
+use crate::errors::Error;
+
+/// ### Purpose:
+/// A bidirectional byte stream connected to a Maestro board.
+///
+/// ### Notes:
+/// Implementors are expected to perform *no* framing of their own; the
+/// [`crate::maestro::Maestro`] passes fully-formed command packets to
+/// [`Transport::write`] and expects the raw response bytes back from
+/// [`Transport::read`].
+pub trait Transport {
+    /// ### Purpose:
+    /// Reads up to `buf.len()` bytes into `buf`, returning the number of bytes
+    /// actually read.
+    ///
+    /// ### Notes:
+    /// This call should block for at most the duration last passed into
+    /// [`Transport::set_read_timeout`].
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize>;
+
+    /// ### Purpose:
+    /// Writes the bytes in `buf`, returning the number of bytes actually
+    /// written.
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize>;
+
+    /// ### Purpose:
+    /// Blocks until all previously written bytes have been handed off to the
+    /// board.
+    fn flush(&mut self) -> crate::Result<()>;
+
+    /// ### Purpose:
+    /// Sets how long [`Transport::read`] should wait for bytes to arrive
+    /// before returning.
+    fn set_read_timeout(&mut self, timeout: Duration) -> crate::Result<()>;
+
+    /// ### Purpose:
+    /// Writes and flushes `request`, and then reads up to `response.len()`
+    /// bytes into `response`, returning the number of bytes actually read.
+    ///
+    /// ### Notes:
+    /// This is the only method the [`crate::maestro::Maestro`] calls when
+    /// sending commands. Transports which are shared between several users
+    /// (see [`crate::maestro::bus::MaestroBus`]) override it to make the
+    /// whole exchange atomic.
+    fn transact(
+        &mut self,
+        request: &[u8],
+        response: &mut [u8],
+    ) -> crate::Result<usize> {
+        let bytes_written = self.write(request)?;
+        let comparison = bytes_written.cmp(&request.len());
+        match comparison {
+            Ordering::Equal => self.flush()?,
+            _ => {
+                return Err(Error::FaultyWrite {
+                    actual_count: bytes_written,
+                    expected_count: request.len(),
+                })
+            },
+        };
+        match response.is_empty() {
+            true => Ok(0usize),
+            false => self.read(response),
+        }
+    }
+}
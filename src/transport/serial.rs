@@ -0,0 +1,99 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use serialport::SerialPort;
+
+use crate::maestro::constants::Baudrate;
+use crate::transport::Transport;
+
+/// ### Purpose:
+/// A [`Transport`] over a serial port, such as the Maestro's USB virtual COM
+/// port (`/dev/ttyACM0` on Linux, `COM3` on Windows, etc.).
+///
+/// ### Notes:
+/// When connected over USB, the Maestro exposes two ports. Commands must be
+/// sent to the "Command Port", which is usually the first of the two.
+pub struct Serial {
+    port: Box<dyn SerialPort>,
+}
+
+impl Serial {
+    /// ### Purpose:
+    /// Opens the serial port at the given path with the given baudrate.
+    ///
+    /// ### Notes:
+    /// The port is opened in raw mode with 8 data bits, 1 stop bit and no
+    /// parity (the only framing the Maestro understands).
+    pub fn open<P>(path: P, baudrate: Baudrate) -> crate::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_string_lossy();
+        let port = serialport::new(path, baudrate as u32)
+            .data_bits(serialport::DataBits::Eight)
+            .stop_bits(serialport::StopBits::One)
+            .parity(serialport::Parity::None)
+            .flow_control(serialport::FlowControl::None)
+            .open()?;
+        Ok(Self::from_port(port))
+    }
+
+    /// ### Purpose:
+    /// Wraps an already-opened serial port.
+    pub fn from_port(port: Box<dyn SerialPort>) -> Self {
+        Self { port }
+    }
+}
+
+impl fmt::Debug for Serial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Serial")
+            .field("name", &self.port.name())
+            .finish()
+    }
+}
+
+impl Transport for Serial {
+    /// ### Notes:
+    /// Serial reads may return a partial response. This implementation keeps
+    /// reading until either `buf` is full or the read timeout elapses.
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        let mut count = 0usize;
+        while count < buf.len() {
+            match self.port.read(&mut buf[count..]) {
+                Ok(0usize) => break,
+                Ok(bytes_read) => count += bytes_read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(count)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize> {
+        let bytes_written = self.port.write(buf)?;
+        Ok(bytes_written)
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        self.port.flush()?;
+        Ok(())
+    }
+
+    fn set_read_timeout(&mut self, timeout: Duration) -> crate::Result<()> {
+        self.port.set_timeout(timeout)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+use std::io::Read;
+use std::io::Write;
+use std::time::Duration;
+
+use serialport::SerialPort;
+use serialport::TTYPort;
+
+use crate::maestro::builder::Builder;
+use crate::maestro::constants::Baudrate;
+use crate::maestro::constants::Channel;
+use crate::maestro::Maestro;
+use crate::transport::Memory;
+use crate::transport::Serial;
+use crate::transport::Transport;
+
+#[test]
+fn memory_short_read() {
+    let mut memory = Memory::new();
+    memory.queue_response(&[0x01u8]);
+    let mut buf = [0u8; 2usize];
+
+    assert_eq!(memory.read(&mut buf).unwrap(), 1usize);
+    assert_eq!(buf, [0x01u8, 0x00u8]);
+}
+
+#[test]
+fn memory_take_written() {
+    let mut memory = Memory::new();
+    memory.write(&[0x01u8, 0x02u8]).unwrap();
+
+    assert_eq!(memory.take_written(), vec![0x01u8, 0x02u8]);
+    assert!(memory.written().is_empty());
+}
+
+/// Opens a pseudo-terminal pair and builds a `Maestro<Serial>` on the slave
+/// end through the normal [`Builder`] path.
+fn pty_maestro() -> (TTYPort, Maestro<Serial>) {
+    let (mut master, slave) = TTYPort::pair().unwrap();
+    master.set_timeout(Duration::from_secs(1)).unwrap();
+    let device_path = slave.name().unwrap();
+    drop(slave);
+    let maestro = Builder::default()
+        .device_path(device_path)
+        .baudrate(Baudrate::Baudrate11520)
+        .block_duration(Duration::from_secs(1))
+        .try_into()
+        .unwrap();
+    (master, maestro)
+}
+
+#[test]
+fn serial_set_target() {
+    let (mut master, mut maestro) = pty_maestro();
+    maestro.set_target(Channel::Channel2, 6000u16).unwrap();
+    let mut packet = [0u8; 6usize];
+    master.read_exact(&mut packet).unwrap();
+
+    assert_eq!(packet, [0xaau8, 0x0cu8, 0x04u8, 0x02u8, 0x70u8, 0x2eu8]);
+}
+
+#[test]
+fn serial_get_position() {
+    let (mut master, mut maestro) = pty_maestro();
+    master.write_all(&[0x70u8, 0x17u8]).unwrap();
+    let position = maestro.get_position(Channel::Channel1).unwrap();
+    let mut packet = [0u8; 4usize];
+    master.read_exact(&mut packet).unwrap();
+
+    assert_eq!(position, 6000u16);
+    assert_eq!(packet, [0xaau8, 0x0cu8, 0x10u8, 0x01u8]);
+}
+
+#[test]
+fn serial_missing_device_path() {
+    let result: crate::Result<Maestro<Serial>> = Builder::default()
+        .baudrate(Baudrate::Baudrate11520)
+        .try_into();
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,34 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+use std::time::Duration;
+
+use rppal::uart::Uart;
+
+use crate::transport::Transport;
+
+impl Transport for Uart {
+    fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+        let bytes_read = Uart::read(self, buf)?;
+        Ok(bytes_read)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> crate::Result<usize> {
+        let bytes_written = Uart::write(self, buf)?;
+        Ok(bytes_written)
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        self.drain()?;
+        Ok(())
+    }
+
+    fn set_read_timeout(&mut self, timeout: Duration) -> crate::Result<()> {
+        self.set_read_mode(0u8, timeout)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2021 UBC Bionics, Ltd.
+//
+// Licensed under the MIT license
+// <LICENSE.md or https://opensource.org/licenses/MIT>.
+// This file may not be copied, modified, or
+// distributed except according to those terms.
+
+//! Integration tests which drive the `raestro-virtual` daemon through the
+//! normal [`Builder`] path.
+
+use std::io::BufRead;
+use std::io::BufReader;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+use raestro::maestro::builder::Builder;
+use raestro::maestro::constants::Baudrate;
+use raestro::maestro::constants::Channel;
+use raestro::maestro::Maestro;
+use raestro::transport::Serial;
+
+/// Kills the daemon once the test is done with it.
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn spawn() -> (Daemon, Maestro<Serial>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_raestro-virtual"))
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut path = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut path)
+        .unwrap();
+    let maestro = Builder::default()
+        .device_path(path.trim())
+        .baudrate(Baudrate::Baudrate11520)
+        .block_duration(Duration::from_secs(1u64))
+        .try_into()
+        .unwrap();
+    (Daemon(child), maestro)
+}
+
+#[test]
+fn set_and_get_position() {
+    let (_daemon, mut maestro) = spawn();
+    maestro.set_target(Channel::Channel0, 6000u16).unwrap();
+
+    assert_eq!(maestro.get_position(Channel::Channel0).unwrap(), 6000u16);
+    assert!(maestro.get_errors().unwrap().is_empty());
+}
+
+#[test]
+fn speed_limited_move() {
+    let (_daemon, mut maestro) = spawn();
+    maestro.set_target(Channel::Channel1, 4000u16).unwrap();
+    maestro.set_speed(Channel::Channel1, 10u16).unwrap();
+    maestro.set_target(Channel::Channel1, 8000u16).unwrap();
+    thread::sleep(Duration::from_millis(100u64));
+    let position = maestro.get_position(Channel::Channel1).unwrap();
+
+    assert!(position > 4000u16 && position < 8000u16);
+}